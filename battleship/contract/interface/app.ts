@@ -50,6 +50,9 @@ export enum TurnState {
 
 	// player who joined won
 	GAME_OVER_JOINER_WON,
+
+	// game was aborted by the admin and the wagers refunded
+	GAME_ABORTED,
 }
 
 /**
@@ -103,6 +106,125 @@ export type ActiveGame = ListedGame & {
 	away: CellValue[];
 };
 
+/**
+ * Describes how a finished game ended for one of its tokens (fits into u8)
+ */
+export enum GameOutcome {
+	// token won the game
+	WON,
+
+	// token lost the game
+	LOST,
+
+	// initiator withdrew the game before anyone joined
+	WITHDRAWN,
+
+	// game was aborted by the admin
+	ABORTED,
+}
+
+/**
+ * Summarizes an unfinished game for a dashboard of the token's games
+ */
+export type ActiveGameSummary = {
+	game_id: string;
+	title: string;
+	role: PlayerRole;
+	opponent_token_id: string | null;
+	turn: TurnState;
+	wager: Coin;
+
+	// seconds since epoch after which victory can be claimed from a player who has not moved
+	deadline: number;
+};
+
+/**
+ * Describes one of the token's past games
+ */
+export type FinishedGame = {
+	game_id: string;
+	outcome: GameOutcome;
+	opponent_token_id: string | null;
+	wager: Coin;
+	payout: Coin;
+	finished: number;
+};
+
+/**
+ * A wager tier of the lobby that tokens can subscribe to
+ */
+export type LobbyTier = {
+	wager: Coin;
+	subscribers: Uint32;
+
+	// max number of subscribers notified of each game listed in the tier
+	notified_per_game: Uint32;
+};
+
+/**
+ * A leaderboard season
+ */
+export type Season = {
+	season_id: Uint32;
+	name: string;
+
+	// seconds since epoch, end is exclusive
+	start: number;
+	end: number;
+
+	// percent of each house fee that funds the prize pool
+	fee_percent: Uint8;
+
+	// percent of the prize pool paid to each place, first place first, at most 50 places
+	prize_split: Uint8[];
+	pool: Uint128;
+	closed: boolean;
+};
+
+/**
+ * A token's place in a season's standings
+ */
+export type SeasonStanding = {
+	token_id: string;
+	points: Uint32;
+	prize: Uint128;
+};
+
+/**
+ * Gameplay milestone that unlocks an achievement
+ */
+export type Milestone = {
+	wins: {
+		count: Uint32;
+	};
+} | 'perfect_game' | {
+	wager_win: {
+		min_wager: Uint128;
+	};
+};
+
+/**
+ * An achievement that mints a non-transferable badge token when unlocked
+ */
+export type Achievement = {
+	achievement_id: string;
+	name: string;
+	description: string | null;
+	image: string | null;
+	milestone: Milestone;
+};
+
+/**
+ * An achievement a token has unlocked
+ */
+export type UnlockedAchievement = {
+	achievement_id: string;
+	badge_token_id: string;
+
+	// seconds since epoch
+	unlocked: number;
+};
+
 type MsgsRequireTokenId<h_group extends MethodDescriptorGroup> = MethodGroup.Augment<h_group, {
 	msg: {
 		token_id: string;
@@ -133,7 +255,26 @@ type AuthenticatedQueries = MethodGroup.Canonicalize<
 			 * Gets the list of active games this player is party to
 			 */
 			active_games: [{}, {
-				game_ids: string[];
+				games: ActiveGameSummary[];
+			}];
+
+			/**
+			 * Gets the token's past games, most recent first
+			 */
+			finished_games: [{
+				page_size?: Uint32;
+				page?: Uint32;
+			}, {
+				games: FinishedGame[];
+				total: Uint32;
+			}];
+
+			/**
+			 * Lists the achievements the token has unlocked and the badges minted for them
+			 */
+			token_achievements: [{}, {
+				wins: Uint32;
+				unlocked: UnlockedAchievement[];
 			}];
 		}
 		& MsgsRequireGameId<{
@@ -153,23 +294,80 @@ export type AppInterface<w_defer=never> = SecretContractInterface<{
 
 	config: {
 		snip52_channels: {
-			// /**
-			//  * A new game has been added to the lobby
-			//  */
-			// game_listed: [
-			// 	game_id: string,
-			// 	title: string,
-			// 	wager_uscrt: Uint128,
-			// ];
+			/**
+			 * A new game has been added to a lobby tier the user subscribed to
+			 */
+			game_listed: [
+				game_id: string,
+				title: string,
+				wager_uscrt: number,
+			];
+
+			/**
+			 * Someone joined a game the user initiated
+			 */
+			player_joined: [
+				game_id: string,
+			];
+
+			/**
+			 * The opponent attacked one of the user's cells
+			 */
+			opponent_attacked: [
+				game_id: string,
+				cell: Uint8,
+				result: CellValue.MISS | CellValue.HIT,
+			];
 
 			/**
-			 * A player joined the user's new game
+			 * One of the user's games was updated (full board, superseded by `game_delta`)
 			 */
 			game_updated: [
 				game_id: string,
 				home: CellValue[],
 				turn: TurnState,
 			];
+
+			/**
+			 * One of the user's games was updated
+			 */
+			game_delta: [
+				version: Uint8,
+				game_id: string,
+				seq: Uint32,
+				cell: Uint8 | null,
+				result: CellValue.MISS | CellValue.HIT | null,
+				sunk: CellValue | null,
+				turn: TurnState,
+			];
+
+			/**
+			 * The user received a token
+			 */
+			nft_received: [
+				token_id: string,
+				from: string,
+			];
+
+			/**
+			 * One of the user's tokens was transferred or sent away
+			 */
+			nft_sent: [
+				token_id: string,
+				to: string,
+			];
+
+			/**
+			 * A transfer approval or storage delegation involving the user was granted or revoked.
+			 * An empty `token_ids` means every token of the owner
+			 */
+			approval_changed: [
+				kind: 'transfer' | 'storage_delegate',
+				token_ids: string[],
+				owner: string,
+				spender: string,
+				approved: boolean,
+			];
 		};
 	};
 
@@ -190,6 +388,30 @@ export type AppInterface<w_defer=never> = SecretContractInterface<{
 					denom: 'uscrt';
 				};
 			};
+
+			/**
+			 * Subscribes the token to the lobby tiers whose newly listed games its owner wants to be
+			 * notified of, replacing its previous subscriptions. Subscriptions expire after a week
+			 * and have to be renewed. An empty list unsubscribes it
+			 */
+			subscribe_lobby: [{
+				tiers: Uint128<'0' | `${WagerAmountsScrt}000000`>[];
+			}, {
+				tiers: Uint128[];
+
+				// seconds since epoch
+				expires: number | null;
+			}];
+
+			/**
+			 * Sets which of the owner's delegates (at most 3) also receive the token's game
+			 * notifications, replacing the previous list. Only the owner may call this
+			 */
+			set_notification_delegates: [{
+				delegates: string[];
+			}, {
+				delegates: string[];
+			}];
 		}
 		& MsgsRequireGameId<{
 			/**
@@ -223,6 +445,44 @@ export type AppInterface<w_defer=never> = SecretContractInterface<{
 	>;
 
 	queries: {
+		/**
+		 * Lists the lobby tiers with their number of subscribers
+		 */
+		lobby_tiers: [{}, {
+			tiers: LobbyTier[];
+		}];
+
+		/**
+		 * Lists leaderboard seasons, most recent first
+		 */
+		seasons: [{
+			page_size?: Uint32;
+			page?: Uint32;
+		}, {
+			seasons: Season[];
+			count: Uint32;
+		}];
+
+		/**
+		 * Fetches a season's leaderboard of its top 50 tokens: the final one once it is closed, the current one while it is open
+		 */
+		season_standings: [{
+			season_id: Uint32;
+			page_size?: Uint32;
+			page?: Uint32;
+		}, {
+			season: Season;
+			standings: SeasonStanding[];
+			total: Uint32;
+		}];
+
+		/**
+		 * Lists the achievements that mint badges
+		 */
+		achievements: [{}, {
+			achievements: Achievement[];
+		}];
+
 		with_permit: {
 			variants: U.ListOf<MakeQueryPermitVariants<AuthenticatedQueries>>;
 		};
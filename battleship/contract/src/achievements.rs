@@ -0,0 +1,318 @@
+use schemars::JsonSchema;
+use secret_toolkit::storage::{Item, Keymap};
+use serde::{Deserialize, Serialize};
+use cosmwasm_std::{
+    to_binary, Addr, Binary, CanonicalAddr, Deps, DepsMut, Env, Response, StdError, StdResult,
+    Storage, Uint128,
+};
+use crate::battleship::verify_owner_or_delegate;
+use crate::contract::{check_status, mint_list};
+use crate::msg::{ExecuteAnswer, Mint, QueryAnswer, ResponseStatus};
+use crate::state::{load, Config, CONFIG_KEY};
+use crate::token::{Extension, Metadata, Trait};
+
+/// token_subtype given to badge tokens
+pub const BADGE_SUBTYPE: &str = "badge";
+
+/// Gameplay milestone that unlocks an achievement
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Milestone {
+    /// token has won this many games
+    Wins { count: u32 },
+    /// token won a game without missing a single attack
+    PerfectGame,
+    /// token won a game with at least this wager
+    WagerWin { min_wager: Uint128 },
+}
+
+/// An achievement the admin has defined
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Debug)]
+pub struct Achievement {
+    pub achievement_id: String,
+    /// name given to the badge token
+    pub name: String,
+    /// description given to the badge token
+    pub description: Option<String>,
+    /// image url given to the badge token
+    pub image: Option<String>,
+    pub milestone: Milestone,
+}
+
+/// An achievement a token has unlocked
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Debug)]
+pub struct UnlockedAchievement {
+    pub achievement_id: String,
+    /// id of the badge token minted for it
+    pub badge_token_id: String,
+    /// time (in seconds since 01/01/1970) it was unlocked
+    pub unlocked: u64,
+}
+
+/// Describes a game the token just won, for checking milestones
+pub struct GameWin<'a> {
+    pub token_id: &'a str,
+    /// current owner of the token, who receives any badges
+    pub owner: &'a CanonicalAddr,
+    pub wager: u128,
+    /// true if the winner never missed
+    pub perfect: bool,
+}
+
+/// Returns bool, true if the token is a badge minted for an achievement
+pub fn is_badge(storage: &dyn Storage, token_id: &str) -> bool {
+    BADGE_TOKEN_STORE.add_suffix(token_id.as_bytes()).may_load(storage).ok().flatten().unwrap_or(false)
+}
+
+/// Returns Vec<Achievement> of the achievements defined when the contract is instantiated
+pub fn default_achievements() -> Vec<Achievement> {
+    vec![
+        Achievement {
+            achievement_id: "first-win".to_string(),
+            name: "First Win".to_string(),
+            description: Some("Won a game of battleship".to_string()),
+            image: None,
+            milestone: Milestone::Wins { count: 1 },
+        },
+        Achievement {
+            achievement_id: "ten-wins".to_string(),
+            name: "Ten Wins".to_string(),
+            description: Some("Won ten games of battleship".to_string()),
+            image: None,
+            milestone: Milestone::Wins { count: 10 },
+        },
+        Achievement {
+            achievement_id: "perfect-game".to_string(),
+            name: "Perfect Game".to_string(),
+            description: Some("Won a game without missing a shot".to_string()),
+            image: None,
+            milestone: Milestone::PerfectGame,
+        },
+        Achievement {
+            achievement_id: "high-roller".to_string(),
+            name: "High Roller".to_string(),
+            description: Some("Won a game at the top wager".to_string()),
+            image: None,
+            milestone: Milestone::WagerWin { min_wager: Uint128::from(10000000_u128) },
+        },
+    ]
+}
+
+/// Returns StdResult<Vec<String>> of the ids of any badges minted
+///
+/// counts a win for the token and mints a badge to its owner for every achievement this
+/// win unlocks
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `env` - a reference to the Env of the contract's environment
+/// * `config` - a mutable reference to the Config
+/// * `win` - the game the token won
+pub fn record_win(
+    mut deps: DepsMut,
+    env: &Env,
+    config: &mut Config,
+    win: GameWin,
+) -> StdResult<Vec<String>> {
+    let wins_store = TOKEN_WINS_STORE.add_suffix(win.token_id.as_bytes());
+    let wins = wins_store.may_load(deps.storage)?.unwrap_or(0) + 1;
+    wins_store.save(deps.storage, &wins)?;
+
+    let unlocked_store = TOKEN_ACHIEVEMENTS_STORE.add_suffix(win.token_id.as_bytes());
+    let mut unlocked: Vec<Achievement> = vec![];
+    for item in achievements_store().iter(deps.storage)? {
+        let (achievement_id, achievement) = item?;
+        if unlocked_store.contains(deps.storage, &achievement_id) {
+            continue;
+        }
+        let reached = match &achievement.milestone {
+            Milestone::Wins { count } => wins >= *count,
+            Milestone::PerfectGame => win.perfect,
+            Milestone::WagerWin { min_wager } => win.wager > 0 && win.wager >= min_wager.u128(),
+        };
+        if reached {
+            unlocked.push(achievement);
+        }
+    }
+    if unlocked.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let owner = deps.api.addr_humanize(win.owner)?.into_string();
+    let mints = unlocked
+        .iter()
+        .map(|achievement| Mint {
+            token_id: None,
+            owner: Some(owner.clone()),
+            public_metadata: Some(Metadata {
+                token_uri: None,
+                extension: Some(Extension {
+                    name: Some(achievement.name.clone()),
+                    description: achievement.description.clone(),
+                    image: achievement.image.clone(),
+                    attributes: Some(vec![
+                        Trait {
+                            display_type: None,
+                            trait_type: Some("achievement".to_string()),
+                            value: achievement.achievement_id.clone(),
+                            max_value: None,
+                        },
+                        Trait {
+                            display_type: None,
+                            trait_type: Some("earned_by".to_string()),
+                            value: win.token_id.to_string(),
+                            max_value: None,
+                        },
+                    ]),
+                    token_subtype: Some(BADGE_SUBTYPE.to_string()),
+                    ..Extension::default()
+                }),
+            }),
+            private_metadata: None,
+            serial_number: None,
+            royalty_info: None,
+            transferable: Some(false),
+            memo: None,
+        })
+        .collect();
+    let contract_raw = deps.api.addr_canonicalize(env.contract.address.as_str())?;
+    let minted = mint_list(deps.branch(), env, config, &contract_raw, mints, true)?;
+
+    for (achievement, badge_token_id) in unlocked.iter().zip(minted.iter()) {
+        unlocked_store.insert(
+            deps.storage,
+            &achievement.achievement_id,
+            &UnlockedAchievement {
+                achievement_id: achievement.achievement_id.clone(),
+                badge_token_id: badge_token_id.clone(),
+                unlocked: env.block.time.seconds(),
+            },
+        )?;
+    }
+    Ok(minted)
+}
+
+/// Returns StdResult<Response>
+///
+/// adds an achievement or replaces the one with the same id
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `sender` - a reference to the message sender
+/// * `config` - a reference to the Config
+/// * `priority` - u8 representation of highest status level this action is permitted at
+/// * `achievement` - the achievement definition
+pub fn set_achievement(
+    deps: DepsMut,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    achievement: Achievement,
+) -> StdResult<Response> {
+    check_status(config.status, priority)?;
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    if config.admin != sender_raw {
+        return Err(StdError::generic_err(
+            "This is an admin command and can only be run from the admin address",
+        ));
+    }
+    if let Milestone::Wins { count: 0 } = achievement.milestone {
+        return Err(StdError::generic_err("Win count must be at least 1"));
+    }
+    achievements_store().insert(deps.storage, &achievement.achievement_id, &achievement)?;
+
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::SetAchievement {
+        status: ResponseStatus::Success,
+    })?))
+}
+
+/// Returns StdResult<Response>
+///
+/// stops an achievement from being unlocked.  Badges already minted for it are kept
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `sender` - a reference to the message sender
+/// * `config` - a reference to the Config
+/// * `priority` - u8 representation of highest status level this action is permitted at
+/// * `achievement_id` - id of the achievement to remove
+pub fn remove_achievement(
+    deps: DepsMut,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    achievement_id: String,
+) -> StdResult<Response> {
+    check_status(config.status, priority)?;
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    if config.admin != sender_raw {
+        return Err(StdError::generic_err(
+            "This is an admin command and can only be run from the admin address",
+        ));
+    }
+    if !achievements_store().contains(deps.storage, &achievement_id) {
+        return Err(StdError::generic_err(format!("No achievement with id {}", achievement_id)));
+    }
+    achievements_store().remove(deps.storage, &achievement_id)?;
+
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::RemoveAchievement {
+        status: ResponseStatus::Success,
+    })?))
+}
+
+/// Returns StdResult<Binary> displaying every achievement that can be unlocked
+///
+/// # Arguments
+///
+/// * `deps` - a reference to Extern containing all the contract's external dependencies
+pub fn query_achievements(deps: Deps) -> StdResult<Binary> {
+    let achievements = achievements_store()
+        .iter(deps.storage)?
+        .map(|item| item.map(|(_, achievement)| achievement))
+        .collect::<StdResult<Vec<Achievement>>>()?;
+
+    to_binary(&QueryAnswer::Achievements { achievements })
+}
+
+/// Returns StdResult<Binary> displaying the achievements a token has unlocked
+///
+/// # Arguments
+///
+/// * `deps` - a reference to Extern containing all the contract's external dependencies
+/// * `token_id` - the token whose achievements to display
+/// * `address_raw` - a reference to the address of the token's owner or delegate
+pub fn query_token_achievements(
+    deps: Deps,
+    token_id: String,
+    address_raw: &CanonicalAddr,
+) -> StdResult<Binary> {
+    let config: Config = load(deps.storage, CONFIG_KEY)?;
+    verify_owner_or_delegate(deps.storage, address_raw, &config, &token_id)?;
+    let unlocked = TOKEN_ACHIEVEMENTS_STORE
+        .add_suffix(token_id.as_bytes())
+        .iter(deps.storage)?
+        .map(|item| item.map(|(_, unlocked)| unlocked))
+        .collect::<StdResult<Vec<UnlockedAchievement>>>()?;
+    let wins = TOKEN_WINS_STORE
+        .add_suffix(token_id.as_bytes())
+        .may_load(deps.storage)?
+        .unwrap_or(0);
+
+    to_binary(&QueryAnswer::TokenAchievements { wins, unlocked })
+}
+
+// ACHIEVEMENTS STATE
+
+// achievement definitions by achievement_id (built per use because keymaps cache their length)
+pub fn achievements_store() -> Keymap<'static, String, Achievement> {
+    Keymap::new(b"achievements")
+}
+// number of games won for prefix token_id
+pub static TOKEN_WINS_STORE: Item<u32> = Item::new(b"token-wins");
+// set for badge tokens minted by record_win, for prefix token_id
+pub static BADGE_TOKEN_STORE: Item<bool> = Item::new(b"badge-tokens");
+// unlocked achievements by achievement_id for prefix token_id
+pub static TOKEN_ACHIEVEMENTS_STORE: Keymap<String, UnlockedAchievement> = Keymap::new(b"token-achievements");
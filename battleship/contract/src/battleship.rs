@@ -1,229 +1,148 @@
 use std::convert::TryFrom;
 use minicbor_ser as cbor;
 use base64::{engine::general_purpose, Engine};
-use secret_toolkit::{storage::{Keyset, Item, Keymap}, crypto::ContractPrng};
+use secret_toolkit::{storage::{AppendStore, Keyset, Item, Keymap}, crypto::ContractPrng};
 use serde::{Deserialize, Serialize};
 use schemars::JsonSchema;
 use cosmwasm_std::{
     Coin, Timestamp, DepsMut, Addr, StdResult, Response, to_binary, 
     Uint128, Deps, Binary, StdError, CanonicalAddr, MessageInfo, Env, CosmosMsg, BankMsg, Storage,
+    BlockInfo,
+};
+use crate::{msg::{ContractStatus, ExecuteAnswer, ResponseStatus, QueryAnswer}, state::{load, save, CONFIG_KEY}};
+use crate::snip52_channel::{
+    channels_store, GAME_UPDATED_CHANNEL_ID, GAME_DELTA_CHANNEL_ID, GAME_DELTA_VERSION,
+    PLAYER_JOINED_CHANNEL_ID, OPPONENT_ATTACKED_CHANNEL_ID, GAME_LISTED_CHANNEL_ID,
+};
+use crate::snip52_exec_query::{render_notification, add_notifications};
+use crate::contract::{check_status, get_token};
+use crate::seasons::record_season_result;
+use crate::achievements::{is_badge, record_win, GameWin};
+use crate::engine::{
+    apply_attack, apply_setup, away_view, opponent_of, AttackOutcome, GameState,
+};
+pub use crate::engine::{
+    AttackResult, CellValue, PlayerRole, TurnState, BOARD_SIZE, CARRIER_SIZE, BATTLESHIP_SIZE,
+    CRUISER_SIZE, SUBMARINE_SIZE, DESTROYER_SIZE,
 };
-use crate::{msg::{ExecuteAnswer, ResponseStatus, QueryAnswer}, state::{load, CONFIG_KEY}};
-use crate::snip52_channel::GAME_UPDATED_CHANNEL_ID;
-use crate::snip52_exec_query::{notification_id, encrypt_notification_data};
-use crate::snip52_state::increment_count;
-use crate::contract::get_token;
 use crate::nfp::{ANY_DELEGATES, TOKEN_DELEGATES};
-use crate::state::Config;
+use crate::state::{store_game_event, Config, StoredTxAction};
 
 pub const DENOM: &str = "uscrt";
-pub const BOARD_SIZE: usize = 100; // 100
 pub const VALID_WAGERS: [u128; 5] = [0_u128, 1000000_u128, 2000000_u128, 5000000_u128, 10000000_u128];
-pub const CARRIER_SIZE: u8 = 5;
-pub const BATTLESHIP_SIZE: u8 = 4;
-pub const CRUISER_SIZE: u8 = 3;
-pub const SUBMARINE_SIZE: u8 = 3;
-pub const DESTROYER_SIZE: u8 = 2;
 pub const TIMEOUT_SEC: u64 = 45;
+/// max number of a tier's subscribers notified when a game is listed, which bounds the gas it
+/// costs.  Larger tiers take turns
+pub const LOBBY_NOTIFY_BATCH: u32 = 20;
+/// seconds a lobby subscription lasts before it has to be renewed
+pub const LOBBY_SUBSCRIPTION_SEC: u64 = 7 * 24 * 60 * 60;
+/// max number of delegates notified about a token's games, on top of its owner
+pub const MAX_NOTIFICATION_DELEGATES: usize = 3;
+/// most games an EmergencyRefundGames call aborts, so that it stays within the gas limit
+pub const MAX_EMERGENCY_REFUNDS: u32 = 50;
+
+/// Describes how a finished game ended for one of its tokens (fits into u8)
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, JsonSchema)]
+#[repr(u8)]
+pub enum GameOutcome {
+    /// token won the game
+    Won = 0,
+    /// token lost the game
+    Lost = 1,
+    /// initiator withdrew the game before anyone joined
+    Withdrawn = 2,
+    /// game was aborted by the admin
+    Aborted = 3,
+}
 
-/// Distinguishes to a player which role they fulfil
+/// Used to represent a game to prospective players browsing the lobby
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug, JsonSchema)]
-#[repr(u8)]
-pub enum PlayerRole {
-    /// this player initiated the game
-    Initiator = 0,
-    /// this player joined the game
-    Joiner = 1,
+pub struct ListedGame {
+    pub game_id: String,
+    pub wager: Coin,
+    pub title: String,
+    pub created: Timestamp,
 }
 
-/// Describes the state of an initiated game (fits into u8)
+/// Summarizes an unfinished game for a dashboard of the token's games
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug, JsonSchema)]
-#[repr(u8)]
-pub enum TurnState {
-    /// waiting for another player to join
-    WaitingForPlayer = 0,
-    /// waiting for both players to submit their setups
-    WaitingForBothPlayersSetup = 1,
-    /// waiting for only the initiator to submit their setup
-    WaitingForInitiatorSetup = 2,
-    /// waiting for only the joiner to submit their setup
-    WaitingForJoinerSetup = 3,
-    /// waiting for the player who initiated to submit their move
-    InitiatorsTurn = 4,
-    /// waiting for the player who joined to submit their move
-    JoinersTurn = 5,
-    /// player who initiated won
-    GameOverInitiatorWon = 6,
-    /// player who joined won
-    GameOverJoinerWon = 7,
+pub struct ActiveGameSummary {
+    pub game_id: String,
+    pub title: String,
+    /// role of the queried token
+    pub role: u8,
+    /// token id of the opponent, if someone joined
+    pub opponent_token_id: Option<String>,
+    pub turn: u8,
+    pub wager: Coin,
+    /// time (in seconds since 01/01/1970) after which victory can be claimed from a player
+    /// who has not moved
+    pub deadline: u64,
 }
 
-/// Describes the occupancy of a cell (fits into u8)
-#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, JsonSchema)]
-#[repr(u8)]
-pub enum CellValue {
-    /// nothing occupies the cell. used for both `home` and `away` grids
-    Empty = 0,
-    /// for `away` grid only: player missed the cell
-    Miss = 1,
-    /// part of the "Carrier" vessel occupies the cell
-    Carrier = 2,
-    /// part of the "Battleship" vessel occupies the cell
-    Battleship = 3,
-    /// part of the "Cruiser" vessel occupies the cell
-    Cruiser = 4,
-    /// part of the "Submarine" vessel occupies the cell
-    Submarine = 5,
-    /// part of the "Destroyer" vessel occupies the cell
-    Destroyer = 6,
-    /// bitmask: the cell contains a vessel that has been it
-    Hit = 0x80,
+/// Describes a wager tier of the lobby that tokens can subscribe to
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug, JsonSchema)]
+pub struct LobbyTier {
+    pub wager: Coin,
+    /// number of tokens subscribed to the tier
+    pub subscribers: u32,
+    /// max number of subscribers notified of each game listed in the tier
+    pub notified_per_game: u32,
 }
 
-// Implement TryFrom<u8> for CellValue (fallible)
-impl TryFrom<u8> for CellValue {
-    type Error = ();
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            0 => Ok(CellValue::Empty),
-            1 => Ok(CellValue::Miss),
-            2 => Ok(CellValue::Carrier),
-            3 => Ok(CellValue::Battleship),
-            4 => Ok(CellValue::Cruiser),
-            5 => Ok(CellValue::Submarine),
-            6 => Ok(CellValue::Destroyer),
-            0x80 => Ok(CellValue::Hit),
-            _ => Err(()), // Return an error for invalid values
-        }
-    }
+/// The lobby tiers a token subscribed to
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct LobbySubscription {
+    pub wagers: Vec<u128>,
+    /// time (in seconds since 01/01/1970) the subscription ends
+    pub expires: u64,
 }
 
-/// Used to represent a game to prospective players browsing the lobby
+/// Describes one of the token's past games
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug, JsonSchema)]
-pub struct ListedGame {
+pub struct FinishedGame {
     pub game_id: String,
+    pub outcome: u8,
+    /// token id of the opponent, if someone joined
+    pub opponent_token_id: Option<String>,
     pub wager: Coin,
-    pub title: String,
-    pub created: Timestamp,
+    /// amount paid to the token's owner when the game finished
+    pub payout: Coin,
+    /// time (in seconds since 01/01/1970) the game finished
+    pub finished: u64,
 }
 
-fn ship_found(
-    cells: &Vec<u8>,
-    matched: &mut Vec<bool>,
-    i: &usize,
-    ship_type: u8,
-    ship_size: usize,
-) -> bool {
-    let mut found = false;
-    // check horiz
-    if i % 10 <= 10 - ship_size && 
-       cells[*i..*i+ship_size] == vec![ship_type; ship_size] {
-        for j in 0..ship_size {
-            matched[i+j] = true;
-        }
-        found = true;
-    }
-    // check vert
-    if !found {
-        let mut vertical_cells = vec![];
-        for j in 0..ship_size {
-            vertical_cells.push(cells[i+(j*10)]);
-        }
-        if i / 10 <= 10 - ship_size &&
-           vertical_cells == vec![ship_type; ship_size] {
-            for j in 0..ship_size {
-                matched[i+(j*10)] = true;
-            }
-        }
-        found = true;
-    }
-    found
+/// A past game kept in the token's game history
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct StoredFinishedGame {
+    pub game_id: String,
+    pub outcome: u8,
+    pub opponent_token_id: Option<String>,
+    pub wager: u128,
+    pub payout: u128,
+    pub finished: u64,
 }
 
-fn valid_setup(
-    cells: &Vec<u8>,
-) -> bool {
-    if cells.len() != BOARD_SIZE as usize {
-        return false; // The board should have exactly 100 cells.
-    }
-
-    let mut matched = vec![false; BOARD_SIZE];
-    let mut carrier_found = false;
-    let mut battleship_found = false;
-    let mut cruiser_found = false;
-    let mut submarine_found = false;
-    let mut destroyer_found = false;
-
-    for (i, cell) in cells.iter().enumerate() {
-        if !matched[i] {
-            if *cell != CellValue::Empty as u8 {
-                if *cell == CellValue::Carrier as u8 {
-                    if carrier_found { return false; }
-                    carrier_found = ship_found(
-                        cells, 
-                        &mut matched, 
-                        &i, 
-                        CellValue::Carrier as u8, 
-                        CARRIER_SIZE as usize,
-                    );
-                    if !carrier_found { return false };
-                } else if *cell == CellValue::Battleship as u8 {
-                    if battleship_found { return false; }
-                    battleship_found = ship_found(
-                        cells, 
-                        &mut matched, 
-                        &i, 
-                        CellValue::Battleship as u8, 
-                        BATTLESHIP_SIZE as usize,
-                    );
-                    if !battleship_found { return false };
-                } else if *cell == CellValue::Cruiser as u8 {
-                    if cruiser_found { return false; }
-                    cruiser_found = ship_found(
-                        cells, 
-                        &mut matched, 
-                        &i, 
-                        CellValue::Cruiser as u8, 
-                        CRUISER_SIZE as usize,
-                    );
-                    if !cruiser_found { return false };
-                } else if *cell == CellValue::Submarine as u8 {
-                    if submarine_found { return false; }
-                    submarine_found = ship_found(
-                        cells, 
-                        &mut matched, 
-                        &i, 
-                        CellValue::Submarine as u8, 
-                        SUBMARINE_SIZE as usize,
-                    );
-                    if !submarine_found { return false };
-                } else if *cell == CellValue::Destroyer as u8 {
-                    if destroyer_found { return false; }
-                    destroyer_found = ship_found(
-                        cells, 
-                        &mut matched, 
-                        &i, 
-                        CellValue::Destroyer as u8, 
-                        DESTROYER_SIZE as usize,
-                    );
-                    if !destroyer_found { return false };
-                } else {
-                    return false;
-                }
-            } else {
-                matched[i] = true;
-            }
+impl StoredFinishedGame {
+    fn into_display(self) -> FinishedGame {
+        FinishedGame {
+            game_id: self.game_id,
+            outcome: self.outcome,
+            opponent_token_id: self.opponent_token_id,
+            wager: Coin {
+                denom: DENOM.to_string(),
+                amount: Uint128::from(self.wager),
+            },
+            payout: Coin {
+                denom: DENOM.to_string(),
+                amount: Uint128::from(self.payout),
+            },
+            finished: self.finished,
         }
     }
-
-    if !(carrier_found && battleship_found && cruiser_found && submarine_found && destroyer_found) {
-        return false;
-    }
-    true
 }
 
-fn verify_owner_or_delegate(
+pub(crate) fn verify_owner_or_delegate(
     storage: &dyn Storage,
     sender_raw: &CanonicalAddr,
     config: &Config,
@@ -241,25 +160,188 @@ fn verify_owner_or_delegate(
     // get owner of the token
     let (token, _) = get_token(storage, &token_id, opt_err)?;
 
-    if *sender_raw != token.owner {
-        let mut delegate = false;
-        // check if sender is delegated to see ANY token by owner
-        if ANY_DELEGATES
-            .add_suffix(token.owner.as_slice())
-            .contains(storage, sender_raw) {
-                delegate = true;
-        // check if there is a token delegation matching the token_id
-        } else if let Some(tokens) = TOKEN_DELEGATES
-            .add_suffix(token.owner.as_slice())
-            .get(storage, &sender_raw) {
-                delegate = tokens.contains(token_id);
+    if *sender_raw != token.owner && !is_delegate(storage, &token.owner, sender_raw, token_id) {
+        return Err(StdError::generic_err(not_authorized_msg));
+    }
+    Ok(token.owner)
+}
+
+/// returns true if the owner delegated the address for ANY of their tokens, or for this one
+fn is_delegate(
+    storage: &dyn Storage,
+    owner: &CanonicalAddr,
+    address: &CanonicalAddr,
+    token_id: &str,
+) -> bool {
+    // check if address is delegated to see ANY token by owner
+    if ANY_DELEGATES
+        .add_suffix(owner.as_slice())
+        .contains(storage, address) {
+            return true;
+    }
+    // check if there is a token delegation matching the token_id
+    let tokens = TOKEN_DELEGATES
+        .add_suffix(owner.as_slice())
+        .get(storage, address);
+    matches!(tokens, Some(tokens) if tokens.contains(token_id))
+}
+
+/// returns the addresses that receive a token's game notifications: its owner, followed by
+/// the notification delegates the owner registered that are still delegates for the token
+fn notification_recipients(
+    storage: &dyn Storage,
+    token_id: &str,
+    owner: &CanonicalAddr,
+) -> StdResult<Vec<CanonicalAddr>> {
+    let mut recipients = vec![owner.clone()];
+    let delegates = NOTIFICATION_DELEGATES_STORE
+        .add_suffix(token_id.as_bytes())
+        .may_load(storage)?
+        .unwrap_or_default();
+    for delegate in delegates {
+        // delegates the owner has since revoked are skipped
+        if is_delegate(storage, owner, &delegate, token_id) {
+            recipients.push(delegate);
+        }
+    }
+    Ok(recipients)
+}
+
+/// CBOR encodes a `game_delta` payload
+pub(crate) fn encode_game_delta(
+    game_id: &str,
+    seq: u32,
+    attack: Option<&AttackResult>,
+    turn: u8,
+) -> StdResult<Vec<u8>> {
+    cbor::to_vec(&(
+        GAME_DELTA_VERSION,
+        game_id,
+        seq,
+        attack.map(|attack| attack.cell),
+        attack.map(|attack| attack.result),
+        attack.and_then(|attack| attack.sunk),
+        turn,
+    )).map_err(|e|
+        StdError::generic_err(format!("{:?}", e))
+    )
+}
+
+/// CBOR encodes a full-board `game_updated` payload
+pub(crate) fn encode_game_updated(game_id: &str, home: &[u8], turn: u8) -> StdResult<Vec<u8>> {
+    cbor::to_vec(&(game_id, home, turn)).map_err(|e|
+        StdError::generic_err(format!("{:?}", e))
+    )
+}
+
+/// renders the notifications telling a player their game was updated: the compact
+/// `game_delta` event, and the full-board `game_updated` payload for older
+/// clients, each for as long as its channel is registered
+fn game_updated_notifications(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    game_id: &str,
+    turn: u8,
+    recipient: &mut StoredPlayer,
+    attack: Option<AttackResult>,
+) -> StdResult<Vec<(Binary, Binary)>> {
+    let mut notifications = vec![];
+    recipient.seq += 1;
+
+    let addresses = notification_recipients(storage, &recipient.token_id, &recipient.owner)?;
+
+    if channels_store().contains(storage, &GAME_DELTA_CHANNEL_ID.to_string()) {
+        let data = encode_game_delta(game_id, recipient.seq, attack.as_ref(), turn)?;
+        for address in addresses.iter() {
+            notifications.push(render_notification(
+                storage,
+                env,
+                sender,
+                address,
+                GAME_DELTA_CHANNEL_ID,
+                data.clone(),
+            )?);
         }
-            
-        if !delegate {
-            return Err(StdError::generic_err(not_authorized_msg));
+    }
+
+    if channels_store().contains(storage, &GAME_UPDATED_CHANNEL_ID.to_string()) {
+        let home = recipient.home.clone().unwrap_or(vec![CellValue::Empty as u8; BOARD_SIZE]);
+        let data = encode_game_updated(game_id, &home, turn)?;
+        for address in addresses.iter() {
+            notifications.push(render_notification(
+                storage,
+                env,
+                sender,
+                address,
+                GAME_UPDATED_CHANNEL_ID,
+                data.clone(),
+            )?);
         }
     }
-    Ok(token.owner)
+
+    Ok(notifications)
+}
+
+/// renders the `player_joined` notifications telling the initiator that someone joined
+/// their game, if that channel is registered
+fn player_joined_notifications(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    game_id: &str,
+    recipient: &StoredPlayer,
+) -> StdResult<Vec<(Binary, Binary)>> {
+    if !channels_store().contains(storage, &PLAYER_JOINED_CHANNEL_ID.to_string()) {
+        return Ok(vec![]);
+    }
+    let data = cbor::to_vec(&(game_id,)).map_err(|e|
+        StdError::generic_err(format!("{:?}", e))
+    )?;
+    render_player_notifications(storage, env, sender, recipient, PLAYER_JOINED_CHANNEL_ID, data)
+}
+
+/// renders the `opponent_attacked` notifications telling a player which of their cells
+/// was attacked, if that channel is registered
+fn opponent_attacked_notifications(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    game_id: &str,
+    recipient: &StoredPlayer,
+    attack: &AttackResult,
+) -> StdResult<Vec<(Binary, Binary)>> {
+    if !channels_store().contains(storage, &OPPONENT_ATTACKED_CHANNEL_ID.to_string()) {
+        return Ok(vec![]);
+    }
+    let data = cbor::to_vec(&(game_id, attack.cell, attack.result)).map_err(|e|
+        StdError::generic_err(format!("{:?}", e))
+    )?;
+    render_player_notifications(storage, env, sender, recipient, OPPONENT_ATTACKED_CHANNEL_ID, data)
+}
+
+/// renders a notification on the channel for the player's owner and each of its
+/// notification delegates
+fn render_player_notifications(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    recipient: &StoredPlayer,
+    channel: &str,
+    data: Vec<u8>,
+) -> StdResult<Vec<(Binary, Binary)>> {
+    let mut notifications = vec![];
+    for address in notification_recipients(storage, &recipient.token_id, &recipient.owner)? {
+        notifications.push(render_notification(
+            storage,
+            env,
+            sender,
+            &address,
+            channel,
+            data.clone(),
+        )?);
+    }
+    Ok(notifications)
 }
 
 pub fn new_game(
@@ -267,15 +349,20 @@ pub fn new_game(
     env: Env,
     info: MessageInfo,
     config: &Config,
+    priority: u8,
     token_id: String,
     title: String,
 ) -> StdResult<Response> {
+    check_status(config.status, priority)?;
     let token_owner = verify_owner_or_delegate(
-        deps.storage, 
-        &deps.api.addr_canonicalize(info.sender.as_str())?, 
-        &config, 
+        deps.storage,
+        &deps.api.addr_canonicalize(info.sender.as_str())?,
+        &config,
         &token_id
     )?;
+    if is_badge(deps.storage, &token_id) {
+        return Err(StdError::generic_err("Achievement badges can not play battleship"));
+    }
 
     let created = env.block.time.clone();
     let mut wager = 0_u128;
@@ -296,112 +383,341 @@ pub fn new_game(
     let initiator_goes_first = prng.rand_bytes()[0] & 2 == 0;
     let game_id = general_purpose::STANDARD.encode(&prng.rand_bytes());
 
-    LISTED_GAMES_STORE.insert(
-        deps.storage, 
-        &game_id, 
-        &StoredListedGame {
+    save_game(
+        deps.storage,
+        &game_id,
+        &GameRecord {
+            version: GAME_RECORD_VERSION,
             title: title.clone(),
-            wager, 
+            wager,
             created,
-            initiator_token_id: token_id.clone(),
-            initiator_owner: token_owner,
-            initiator_goes_first
+            turn: TurnState::WaitingForPlayer as u8,
+            initiator_goes_first,
+            last_move_time: env.block.time.seconds(),
+            initiator: StoredPlayer::new(token_id.clone(), token_owner),
+            joiner: None,
         },
     )?;
+    listed_games_store().insert(deps.storage, &game_id)?;
 
-    TURN_STATE_STORE
-        .add_suffix(game_id.as_bytes())
-        .save(deps.storage, &(TurnState::WaitingForPlayer as u8))?;
-
-    let game = ListedGame { 
-        game_id: game_id.clone(), 
+    let game = ListedGame {
+        game_id: game_id.clone(),
         wager: Coin {
             denom: DENOM.to_string(),
             amount: Uint128::from(wager),
-        }, 
-        title, 
-        created 
+        },
+        title,
+        created
     };
 
     ACTIVE_GAMES_STORE
         .add_suffix(token_id.as_bytes())
         .insert(deps.storage, &game_id)?;
 
-    LAST_MOVE_TIME_STORE
-        .add_suffix(game_id.as_bytes())
-        .save(deps.storage, &env.block.time.seconds())?;
-
-/* 
-    // handle snip-52 channel data
-    let channel = GAME_LISTED_CHANNEL_ID.to_string();
+    // tell the tier's subscribers about the new game
+    let notifications = game_listed_notifications(
+        deps.storage,
+        &env,
+        &info.sender,
+        &game,
+        &token_id,
+    )?;
 
-    // get notification id for recipient
-    // todo: who is recipient?
-    let id = notification_id(deps.storage, &recipient_raw, &channel)?;
+    let response = Response::new()
+        .set_data(to_binary(&ExecuteAnswer::NewGame { game })?);
+    Ok(add_notifications(response, notifications))
+}
 
-    // use CBOR to encode data
+/// renders a `game_listed` notification for the owner and notification delegates of the next
+/// batch of tokens subscribed to the game's wager tier, other than the initiator, if that
+/// channel is registered. tokens that no longer exist or whose subscriptions expired are
+/// dropped from the tier
+fn game_listed_notifications(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    game: &ListedGame,
+    initiator_token_id: &str,
+) -> StdResult<Vec<(Binary, Binary)>> {
+    let mut notifications = vec![];
+    if !channels_store().contains(storage, &GAME_LISTED_CHANNEL_ID.to_string()) {
+        return Ok(notifications);
+    }
+    let wager = game.wager.amount.u128();
+    let tier_store = LOBBY_SUBSCRIBERS_STORE.add_suffix(&wager.to_be_bytes());
+    let cursor_store = LOBBY_CURSOR_STORE.add_suffix(&wager.to_be_bytes());
+    let len = tier_store.get_len(storage)?;
+    if len == 0 {
+        return Ok(notifications);
+    }
+    // pick up where the last listed game in the tier left off, wrapping around
+    let start = cursor_store.may_load(storage)?.unwrap_or(0) % len;
+    let batch = len.min(LOBBY_NOTIFY_BATCH);
+    let token_ids = tier_store
+        .iter(storage)?
+        .skip(start as usize)
+        .chain(tier_store.iter(storage)?)
+        .take(batch as usize)
+        .collect::<StdResult<Vec<String>>>()?;
+    cursor_store.save(storage, &((start + batch) % len))?;
+    let initiator_owner = get_token(storage, initiator_token_id, None)?.0.owner;
     let data = cbor::to_vec(&(
-        game_id,
-        title,
-        wager,
-    )).map_err(|e| 
+        &game.game_id,
+        &game.title,
+        u64::try_from(wager).map_err(|_| StdError::generic_err("Wager is too large"))?,
+    )).map_err(|e|
         StdError::generic_err(format!("{:?}", e))
     )?;
 
-    // encrypt the message
-    let encrypted_data = encrypt_notification_data(
+    let mut recipients: Vec<CanonicalAddr> = vec![initiator_owner];
+    for token_id in token_ids {
+        let subscription = LOBBY_SUBSCRIPTIONS_STORE
+            .add_suffix(token_id.as_bytes())
+            .may_load(storage)?;
+        let owner = match (get_token(storage, &token_id, None), subscription) {
+            (Ok((token, _)), Some(subscription))
+                if subscription.expires > env.block.time.seconds() => token.owner,
+            (_, Some(_)) => {
+                clear_lobby_subscriptions(storage, &token_id)?;
+                continue;
+            }
+            (_, None) => {
+                tier_store.remove(storage, &token_id)?;
+                continue;
+            }
+        };
+        if token_id == initiator_token_id {
+            continue;
+        }
+        for address in notification_recipients(storage, &token_id, &owner)? {
+            if recipients.contains(&address) {
+                continue;
+            }
+            notifications.push(render_notification(
+                storage,
+                env,
+                sender,
+                &address,
+                GAME_LISTED_CHANNEL_ID,
+                data.clone(),
+            )?);
+            recipients.push(address);
+        }
+    }
+    Ok(notifications)
+}
+
+/// Returns StdResult<Response>
+///
+/// subscribes a token to the lobby tiers whose newly listed games its owner wants to be
+/// notified of, replacing its previous subscriptions.  Subscriptions last
+/// LOBBY_SUBSCRIPTION_SEC.  An empty list unsubscribes it
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `env` - a reference to the Env of contract's environment
+/// * `sender` - a reference to the message sender
+/// * `config` - a reference to the Config
+/// * `priority` - u8 representation of highest status level this action is permitted at
+/// * `token_id` - the subscribing token
+/// * `tiers` - wagers of the tiers to subscribe to
+pub fn subscribe_lobby(
+    deps: DepsMut,
+    env: &Env,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    token_id: String,
+    tiers: Vec<Uint128>,
+) -> StdResult<Response> {
+    check_status(config.status, priority)?;
+    verify_owner_or_delegate(
         deps.storage,
-        &env,
-        &info.sender,
-        &recipient_raw,
-        &channel,
-        data.clone()
+        &deps.api.addr_canonicalize(sender.as_str())?,
+        config,
+        &token_id
     )?;
+    if is_badge(deps.storage, &token_id) {
+        return Err(StdError::generic_err("Achievement badges can not play battleship"));
+    }
+
+    let mut wagers: Vec<u128> = vec![];
+    for tier in tiers {
+        let wager = tier.u128();
+        if !VALID_WAGERS.contains(&wager) {
+            return Err(StdError::generic_err("Invalid wager amount"));
+        }
+        if !wagers.contains(&wager) {
+            wagers.push(wager);
+        }
+    }
+    wagers.sort_unstable();
 
-    increment_count(deps.storage, &channel, &recipient_raw)?;
-*/
+    clear_lobby_subscriptions(deps.storage, &token_id)?;
+    for wager in wagers.iter() {
+        LOBBY_SUBSCRIBERS_STORE
+            .add_suffix(&wager.to_be_bytes())
+            .insert(deps.storage, &token_id)?;
+    }
+    let expires = if wagers.is_empty() {
+        None
+    } else {
+        let expires = env.block.time.seconds() + LOBBY_SUBSCRIPTION_SEC;
+        LOBBY_SUBSCRIPTIONS_STORE
+            .add_suffix(token_id.as_bytes())
+            .save(deps.storage, &LobbySubscription { wagers: wagers.clone(), expires })?;
+        Some(expires)
+    };
 
-    Ok(
-        Response::new()
-            .set_data(to_binary(&ExecuteAnswer::NewGame { game })?)
-    )
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::SubscribeLobby {
+        tiers: wagers.into_iter().map(Uint128::from).collect(),
+        expires,
+    })?))
+}
+
+/// removes a token from every lobby tier it subscribed to
+pub fn clear_lobby_subscriptions(storage: &mut dyn Storage, token_id: &str) -> StdResult<()> {
+    let subscriptions_store = LOBBY_SUBSCRIPTIONS_STORE.add_suffix(token_id.as_bytes());
+    let wagers = subscriptions_store
+        .may_load(storage)?
+        .map(|subscription| subscription.wagers)
+        .unwrap_or_default();
+    for wager in wagers {
+        LOBBY_SUBSCRIBERS_STORE
+            .add_suffix(&wager.to_be_bytes())
+            .remove(storage, &token_id.to_string())?;
+    }
+    subscriptions_store.remove(storage);
+    Ok(())
+}
+
+/// Returns StdResult<Response>
+///
+/// sets which of the owner's delegates also receive the token's game notifications,
+/// replacing the previous list.  An empty list leaves only the owner notified
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `sender` - a reference to the message sender
+/// * `config` - a reference to the Config
+/// * `priority` - u8 representation of highest status level this action is permitted at
+/// * `token_id` - the token whose notifications are delegated
+/// * `delegates` - addresses of the delegates to notify
+pub fn set_notification_delegates(
+    deps: DepsMut,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    token_id: String,
+    delegates: Vec<String>,
+) -> StdResult<Response> {
+    check_status(config.status, priority)?;
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    // only the owner picks who else hears about the token's games
+    let owner = verify_owner_or_delegate(deps.storage, &sender_raw, config, &token_id)?;
+    if owner != sender_raw {
+        return Err(StdError::generic_err("Unauthorized"));
+    }
+
+    let mut addresses: Vec<CanonicalAddr> = vec![];
+    for delegate in delegates.iter() {
+        let address = deps.api.addr_canonicalize(deps.api.addr_validate(delegate)?.as_str())?;
+        if !is_delegate(deps.storage, &owner, &address, &token_id) {
+            return Err(StdError::generic_err(format!(
+                "{} is not a delegate for token {}", delegate, token_id
+            )));
+        }
+        if !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
+    if addresses.len() > MAX_NOTIFICATION_DELEGATES {
+        return Err(StdError::generic_err(format!(
+            "A token can have at most {} notification delegates", MAX_NOTIFICATION_DELEGATES
+        )));
+    }
+
+    let delegates_store = NOTIFICATION_DELEGATES_STORE.add_suffix(token_id.as_bytes());
+    if addresses.is_empty() {
+        delegates_store.remove(deps.storage);
+    } else {
+        delegates_store.save(deps.storage, &addresses)?;
+    }
+
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::SetNotificationDelegates {
+        delegates: addresses
+            .iter()
+            .map(|address| deps.api.addr_humanize(address))
+            .collect::<StdResult<Vec<Addr>>>()?,
+    })?))
+}
+
+/// removes a token's notification delegates
+pub fn clear_notification_delegates(storage: &mut dyn Storage, token_id: &str) {
+    NOTIFICATION_DELEGATES_STORE.add_suffix(token_id.as_bytes()).remove(storage);
+}
+
+/// Returns StdResult<Binary> displaying how many tokens are subscribed to each lobby tier
+///
+/// # Arguments
+///
+/// * `deps` - a reference to Extern containing all the contract's external dependencies
+pub fn query_lobby_tiers(deps: Deps) -> StdResult<Binary> {
+    let tiers = VALID_WAGERS
+        .iter()
+        .map(|wager| {
+            Ok(LobbyTier {
+                wager: Coin {
+                    denom: DENOM.to_string(),
+                    amount: Uint128::from(*wager),
+                },
+                subscribers: LOBBY_SUBSCRIBERS_STORE
+                    .add_suffix(&wager.to_be_bytes())
+                    .get_len(deps.storage)?,
+                notified_per_game: LOBBY_NOTIFY_BATCH,
+            })
+        })
+        .collect::<StdResult<Vec<LobbyTier>>>()?;
+
+    to_binary(&QueryAnswer::LobbyTiers { tiers })
 }
 
 pub fn join_game(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    config: &Config,
+    config: &mut Config,
+    priority: u8,
     token_id: String,
     game_id: String,
 ) -> StdResult<Response> {
+    check_status(config.status, priority)?;
     let token_owner = verify_owner_or_delegate(
         deps.storage,
         &deps.api.addr_canonicalize(info.sender.as_str())?,
         &config,
         &token_id
     )?;
-
-    // check if game id exists
-    let listed_game = LISTED_GAMES_STORE.get(deps.storage, &game_id);
-    if listed_game.is_none() {
-        return Err(StdError::generic_err("No listed game with that id"));
+    if is_badge(deps.storage, &token_id) {
+        return Err(StdError::generic_err("Achievement badges can not play battleship"));
     }
-    let listed_game = listed_game.unwrap();
+
+    let mut game = load_game_for_update(deps.storage, &game_id)?;
 
     // check if there is already a joiner
-    let joiner = JOINER_TOKEN_STORE.add_suffix(game_id.as_bytes()).may_load(deps.storage)?;
-    if joiner.is_some() {
+    if game.joiner.is_some() {
         return Err(StdError::generic_err("There is already a joiner for this game"));
     }
 
-    if token_id == listed_game.initiator_token_id {
+    if token_id == game.initiator.token_id {
         return Err(StdError::generic_err("You can't play yourself!"));
     }
 
     // check that wager equals initiator's
     if info.funds.len() == 0 {
-        if listed_game.wager != 0_u128 {
+        if game.wager != 0_u128 {
             return Err(StdError::generic_err("Incorrect wager sent"));
         }
     } else if info.funds.len() == 1 {
@@ -409,87 +725,81 @@ pub fn join_game(
             return Err(StdError::generic_err("Can only send scrt"));
         }
         let wager = info.funds[0].amount.u128();
-        if wager != listed_game.wager {
+        if wager != game.wager {
             return Err(StdError::generic_err("Incorrect wager sent"));
         }
     } else {
         return Err(StdError::generic_err("Can only send scrt"));
     }
 
-    JOINER_TOKEN_STORE
-        .add_suffix(game_id.as_bytes())
-        .save(deps.storage, &token_id)?;
-
-    JOINER_OWNER_STORE
-        .add_suffix(game_id.as_bytes())
-        .save(deps.storage, &token_owner)?;
-
-    if let Some(game_state) = TURN_STATE_STORE.add_suffix(game_id.as_bytes()).may_load(deps.storage)? {
-        if game_state != TurnState::WaitingForPlayer as u8 {
-            return Err(StdError::generic_err("Game state is not waiting for player"));
-        }
-    } else {
-        return Err(StdError::generic_err("Invalid game state"));
+    if game.turn != TurnState::WaitingForPlayer as u8 {
+        return Err(StdError::generic_err("Game state is not waiting for player"));
     }
-    TURN_STATE_STORE
-        .add_suffix(game_id.as_bytes())
-        .save(deps.storage, &(TurnState::WaitingForBothPlayersSetup as u8))?;
-
-    ACTIVE_GAMES_STORE
-        .add_suffix(token_id.as_bytes())
-        .insert(deps.storage, &game_id)?;
-
-    LAST_MOVE_TIME_STORE
-        .add_suffix(game_id.as_bytes())
-        .save(deps.storage, &env.block.time.seconds())?;
-
-    // handle snip-52 channel data
-    let channel = GAME_UPDATED_CHANNEL_ID.to_string();
 
-    // get notification id for recipient (initiator)
-    let id = notification_id(deps.storage, &listed_game.initiator_owner, &channel)?;
+    game.joiner = Some(StoredPlayer::new(token_id.clone(), token_owner));
+    game.turn = TurnState::WaitingForBothPlayersSetup as u8;
+    game.last_move_time = env.block.time.seconds();
 
-    // use CBOR to encode data
-    let data = cbor::to_vec(&(
-        game_id,
-        vec![CellValue::Empty as u8; BOARD_SIZE],
-        TurnState::WaitingForBothPlayersSetup as u8,
-    )).map_err(|e| 
-        StdError::generic_err(format!("{:?}", e))
-    )?;
-
-    // encrypt the message
-    let encrypted_data = encrypt_notification_data(
+    // notify the initiator
+    let mut notifications = game_updated_notifications(
         deps.storage,
         &env,
         &info.sender,
-        &listed_game.initiator_owner,
-        &channel,
-        data.clone()
+        &game_id,
+        game.turn,
+        &mut game.initiator,
+        None,
     )?;
+    notifications.extend(player_joined_notifications(
+        deps.storage,
+        &env,
+        &info.sender,
+        &game_id,
+        &game.initiator,
+    )?);
+    save_game(deps.storage, &game_id, &game)?;
 
-    increment_count(deps.storage, &channel, &listed_game.initiator_owner)?;
+    ACTIVE_GAMES_STORE
+        .add_suffix(token_id.as_bytes())
+        .insert(deps.storage, &game_id)?;
+
+    // record the start of the game in both tokens' histories
+    let joiner = game.joiner.as_ref().ok_or_else(|| StdError::generic_err("Missing joiner"))?;
+    for (player, opponent) in [(&game.initiator, joiner), (joiner, &game.initiator)] {
+        store_game_event(
+            deps.storage,
+            config,
+            &env.block,
+            player.token_id.clone(),
+            StoredTxAction::GameStarted {
+                game_id: game_id.clone(),
+                owner: player.owner.clone(),
+                opponent_token_id: opponent.token_id.clone(),
+                wager: Uint128::from(game.wager),
+            },
+        )?;
+    }
+    save(deps.storage, CONFIG_KEY, config)?;
 
-    Ok(Response::new()
+    let response = Response::new()
         .set_data(
             to_binary(&ExecuteAnswer::JoinGame { status: ResponseStatus::Success })?
-        )
-        .add_attribute_plaintext(
-            id.to_base64(), 
-            encrypted_data.to_base64()
-        )
-    )
+        );
+    Ok(add_notifications(response, notifications))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn submit_setup(
     deps: DepsMut,
     sender: &Addr,
     env: Env,
     config: &Config,
+    priority: u8,
     token_id: String,
     game_id: String,
     cells: Vec<u8>,
 ) -> StdResult<Response> {
+    check_status(config.status, priority)?;
     let _token_owner = verify_owner_or_delegate(
         deps.storage,
         &deps.api.addr_canonicalize(sender.as_str())?,
@@ -497,563 +807,162 @@ pub fn submit_setup(
         &token_id
     )?;
 
-    if !valid_setup(&cells) {
-        return Err(StdError::generic_err("Not a valid battleship setup"));
-    }
-
-    // check if game id exists
-    let listed_game = LISTED_GAMES_STORE.get(deps.storage, &game_id);
-    if listed_game.is_none() {
-        return Err(StdError::generic_err("No listed game with that id"));
-    }
-    let listed_game = listed_game.unwrap();
-    let first_mover_turn = match listed_game.initiator_goes_first {
-        true => TurnState::InitiatorsTurn,
-        false => TurnState::JoinersTurn,
-    };
+    let mut game = load_game_for_update(deps.storage, &game_id)?;
 
     // identify if initiator or joiner (or neither)
-    let initiator: bool;
-    if token_id == listed_game.initiator_token_id {
-        initiator = true;
-    } else {
-        let joiner = JOINER_TOKEN_STORE.add_suffix(game_id.as_bytes()).may_load(deps.storage)?;
-        if let Some(joiner) = joiner {
-            if token_id == joiner {
-                initiator = false;
-            } else {
-                return Err(StdError::generic_err("Unauthorized"));
-            }
-        } else {
-            return Err(StdError::generic_err("Unauthorized"));
-        }
-    }
-
-    let opponent_owner: CanonicalAddr;
-    let opponent_home: Vec<u8>;
-    if let Some(game_state) = TURN_STATE_STORE.add_suffix(game_id.as_bytes()).may_load(deps.storage)? {
-        if initiator {
-            if game_state == TurnState::WaitingForBothPlayersSetup as u8 {
-                TURN_STATE_STORE
-                    .add_suffix(game_id.as_bytes())
-                    .save(deps.storage, &(TurnState::WaitingForJoinerSetup as u8))?;
-            } else if game_state == TurnState::WaitingForInitiatorSetup as u8 {
-                TURN_STATE_STORE
-                    .add_suffix(game_id.as_bytes())
-                    .save(deps.storage, &(first_mover_turn as u8))?;
-            } else {
-                return Err(StdError::generic_err("You already submitted a setup"));
-            }
-
-            INITIATOR_HOME_STORE
-                .add_suffix(game_id.as_bytes())
-                .save(deps.storage, &cells)?;
-            INITIATOR_AWAY_STORE
-                .add_suffix(game_id.as_bytes())
-                .save(
-                    deps.storage,
-                    &StoredAway {
-                        away_values: vec![CellValue::Empty as u8; BOARD_SIZE],
-                        carrier_hits: 0,
-                        battleship_hits: 0,
-                        cruiser_hits: 0,
-                        submarine_hits: 0,
-                        destroyer_hits: 0,
-                    }
-                )?;
-            opponent_owner = JOINER_OWNER_STORE
-                .add_suffix(game_id.as_bytes())
-                .load(deps.storage)?;
-            opponent_home = JOINER_HOME_STORE
-                .add_suffix(game_id.as_bytes())
-                .may_load(deps.storage)?
-                .unwrap_or(vec![CellValue::Empty as u8; BOARD_SIZE]);
-        } else { // joiner
-            if game_state == TurnState::WaitingForBothPlayersSetup as u8 {
-                TURN_STATE_STORE
-                    .add_suffix(game_id.as_bytes())
-                    .save(deps.storage, &(TurnState::WaitingForInitiatorSetup as u8))?;
-            } else if game_state == TurnState::WaitingForJoinerSetup as u8 {
-                TURN_STATE_STORE
-                    .add_suffix(game_id.as_bytes())
-                    .save(deps.storage, &(first_mover_turn as u8))?;
-            } else {
-                return Err(StdError::generic_err("You already submitted a setup"));
-            }
-
-            JOINER_HOME_STORE
-                .add_suffix(game_id.as_bytes())
-                .save(deps.storage, &cells)?;
-            JOINER_AWAY_STORE
-                .add_suffix(game_id.as_bytes())
-                .save(
-                    deps.storage,
-                    &StoredAway {
-                        away_values: vec![CellValue::Empty as u8; BOARD_SIZE],
-                        carrier_hits: 0,
-                        battleship_hits: 0,
-                        cruiser_hits: 0,
-                        submarine_hits: 0,
-                        destroyer_hits: 0,
-                    }
-                )?;
-            opponent_owner = listed_game.initiator_owner;
-            opponent_home = INITIATOR_HOME_STORE
-                .add_suffix(game_id.as_bytes())
-                .may_load(deps.storage)?
-                .unwrap_or(vec![CellValue::Empty as u8; BOARD_SIZE]);
-        }
-    } else {
-        return Err(StdError::generic_err("Invalid game state"));
-    }
-
-    LAST_MOVE_TIME_STORE
-        .add_suffix(game_id.as_bytes())
-        .save(deps.storage, &env.block.time.seconds())?;
-
-    // handle snip-52 channel data
-    let channel = GAME_UPDATED_CHANNEL_ID.to_string();
-
-    // get notification id for recipient (opponent owner)
-    let id = notification_id(deps.storage, &opponent_owner, &channel)?;
+    let role = game.role_of(&token_id)?;
+    let mut state = game.state();
+    apply_setup(&mut state, &role, cells)?;
+    game.set_state(state);
 
-    // use CBOR to encode data
-    let data = cbor::to_vec(&(
-        game_id.clone(),
-        opponent_home,
-        TURN_STATE_STORE
-            .add_suffix(game_id.as_bytes())
-            .load(deps.storage)?,
-    )).map_err(|e| 
-        StdError::generic_err(format!("{:?}", e))
-    )?;
+    game.last_move_time = env.block.time.seconds();
+    let turn = game.turn;
+    let (_, opponent) = game.players_mut(&role)?;
 
-    // encrypt the message
-    let encrypted_data = encrypt_notification_data(
+    // notify the opponent
+    let notifications = game_updated_notifications(
         deps.storage,
         &env,
-        &sender,
-        &opponent_owner,
-        &channel,
-        data.clone()
+        sender,
+        &game_id,
+        turn,
+        opponent,
+        None,
     )?;
+    save_game(deps.storage, &game_id, &game)?;
 
-    increment_count(deps.storage, &channel, &opponent_owner)?;
-
-    Ok(Response::new()
-        .set_data(to_binary(&ExecuteAnswer::SubmitSetup { 
-            status: ResponseStatus::Success 
-        })?)
-        .add_attribute_plaintext(
-            id.to_base64(), 
-            encrypted_data.to_base64()
-        )
-    )
-}
-
-fn has_won(
-    away: &StoredAway
-) -> bool {
-    let total_hits = away.carrier_hits + away.battleship_hits + away.cruiser_hits + away.submarine_hits + away.destroyer_hits;
-    if total_hits >= CARRIER_SIZE + BATTLESHIP_SIZE + CRUISER_SIZE + SUBMARINE_SIZE + DESTROYER_SIZE {
-        return true;
-    }
-    false
-}
-
-fn record_hit(
-    away: &mut StoredAway,
-    home: &mut Vec<u8>,
-    cell: usize,
-    ship_type: u8,
-    ship_size: u8,
-) -> StdResult<()> {
-    away.away_values[cell] |= CellValue::Hit as u8;
-    let hits = match CellValue::try_from(ship_type) {
-        Ok(CellValue::Carrier) => { 
-            away.carrier_hits += 1; 
-            away.carrier_hits
-        }
-        Ok(CellValue::Battleship) => {
-            away.battleship_hits += 1;
-            away.battleship_hits
-        }
-        Ok(CellValue::Cruiser) => {
-            away.cruiser_hits += 1;
-            away.cruiser_hits
-        }
-        Ok(CellValue::Submarine) => {
-            away.submarine_hits += 1;
-            away.submarine_hits
-        }
-        Ok(CellValue::Destroyer) => {
-            away.destroyer_hits += 1;
-            away.destroyer_hits
-        }
-        _ => { return Err(StdError::generic_err("Invalid ship type when recording hit")); }
-    };
-    if hits == ship_size {
-        // the ship has been sunk, reveal the type
-        for (i, value) in home.iter().enumerate() {
-            if *value & 0x0F == ship_type {
-                away.away_values[i] |= ship_type;
-            }
-        }
-    }
-    home[cell] |= CellValue::Hit as u8;
-    Ok(())
+    let response = Response::new()
+        .set_data(to_binary(&ExecuteAnswer::SubmitSetup {
+            status: ResponseStatus::Success
+        })?);
+    Ok(add_notifications(response, notifications))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn attack_cell(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     sender: &Addr,
-    config: &Config,
+    config: &mut Config,
+    priority: u8,
     token_id: String,
     game_id: String,
     cell: u8,
 ) -> StdResult<Response> {
+    check_status(config.status, priority)?;
     let _token_owner = verify_owner_or_delegate(
-        deps.storage, 
+        deps.storage,
         &deps.api.addr_canonicalize(sender.as_str())?,
         &config,
         &token_id
     )?;
 
-    // check if game id exists
-    let listed_game = LISTED_GAMES_STORE.get(deps.storage, &game_id);
-    if listed_game.is_none() {
-        return Err(StdError::generic_err("No listed game with that id"));
-    }
-    let listed_game = listed_game.unwrap();
+    let mut game = load_game_for_update(deps.storage, &game_id)?;
 
     // identify if initiator or joiner (or neither)
-    let initiator: bool;
-    if token_id == listed_game.initiator_token_id {
-        initiator = true;
-    } else {
-        let joiner = JOINER_TOKEN_STORE.add_suffix(game_id.as_bytes()).may_load(deps.storage)?;
-        if let Some(joiner) = joiner {
-            if token_id == joiner {
-                initiator = false;
-            } else {
-                return Err(StdError::generic_err("Unauthorized"));
-            }
-        } else {
-            return Err(StdError::generic_err("Unauthorized"));
-        }
+    let role = game.role_of(&token_id)?;
+
+    let mut state = game.state();
+    let AttackOutcome { attack, won: winner } = apply_attack(&mut state, &role, cell)?;
+    let opponent_home = state
+        .home(&opponent_of(&role))
+        .ok_or_else(|| StdError::generic_err("Error reading opponent home from storage"))?;
+    let perfect = !opponent_home.contains(&(CellValue::Miss as u8));
+    let away = away_view(opponent_home);
+    game.set_state(state);
+
+    if winner {
+        finish_game(deps.storage, &game_id, &game, env.block.time.seconds())?;
     }
+    game.last_move_time = env.block.time.seconds();
+    let turn = game.turn;
 
-    let cell = cell as usize;
-    if cell >= BOARD_SIZE {
-        return Err(StdError::generic_err("Cell index is out of bounds"));
-    }
-
-    let away: Vec<u8>;
-    let winner: bool;
-    let opponent_owner: CanonicalAddr;
-    let opponent_home: Vec<u8>;
-    if let Some(game_state) = TURN_STATE_STORE.add_suffix(game_id.as_bytes()).may_load(deps.storage)? {
-        if initiator && game_state == TurnState::InitiatorsTurn as u8 {
-            let initiator_away = INITIATOR_AWAY_STORE
-                .add_suffix(game_id.as_bytes())
-                .may_load(deps.storage)?;
-            if let Some(mut initiator_away) = initiator_away {
-                if initiator_away.away_values[cell] != CellValue::Empty as u8 {
-                    return Err(StdError::generic_err("You have already attacked this cell"));
-                }
-                let joiner_home = JOINER_HOME_STORE
-                    .add_suffix(game_id.as_bytes())
-                    .may_load(deps.storage)?;
-                if let Some(mut joiner_home) = joiner_home {
-                    let opponent_cell_value = joiner_home[cell];
-                    if opponent_cell_value == CellValue::Empty as u8 {
-                        initiator_away.away_values[cell] = CellValue::Miss as u8;
-                        joiner_home[cell] = CellValue::Miss as u8;
-                    } else if opponent_cell_value == CellValue::Carrier as u8 {
-                        record_hit(
-                            &mut initiator_away,
-                            &mut joiner_home,
-                            cell,
-                            CellValue::Carrier as u8,
-                            CARRIER_SIZE
-                        )?;
-                    } else if opponent_cell_value == CellValue::Battleship as u8 {
-                        record_hit(
-                            &mut initiator_away,
-                            &mut joiner_home,
-                            cell,
-                            CellValue::Battleship as u8,
-                            BATTLESHIP_SIZE
-                        )?;
-                    } else if opponent_cell_value == CellValue::Cruiser as u8 {
-                        record_hit(
-                            &mut initiator_away,
-                            &mut joiner_home,
-                            cell,
-                            CellValue::Cruiser as u8,
-                            CRUISER_SIZE
-                        )?;
-                    } else if opponent_cell_value == CellValue::Submarine as u8 {
-                        record_hit(
-                            &mut initiator_away,
-                            &mut joiner_home,
-                            cell,
-                            CellValue::Submarine as u8,
-                            SUBMARINE_SIZE
-                        )?;  
-                    } else if opponent_cell_value == CellValue::Destroyer as u8 {
-                        record_hit(
-                            &mut initiator_away,
-                            &mut joiner_home,
-                            cell,
-                            CellValue::Destroyer as u8,
-                            DESTROYER_SIZE
-                        )?;  
-                    } else {
-                        return Err(StdError::generic_err("Invalid cell value"));
-                    }
-                    INITIATOR_AWAY_STORE
-                        .add_suffix(game_id.as_bytes())
-                        .save(deps.storage, &initiator_away)?;
-                    JOINER_HOME_STORE
-                        .add_suffix(game_id.as_bytes())
-                        .save(deps.storage, &joiner_home)?;
-
-                    winner = has_won(&initiator_away);
-                    if winner {
-                        TURN_STATE_STORE
-                            .add_suffix(game_id.as_bytes())
-                            .save(deps.storage, &(TurnState::GameOverInitiatorWon as u8))?;
-
-                        // remove from active games for both tokens
-                        ACTIVE_GAMES_STORE
-                            .add_suffix(token_id.as_bytes())
-                            .remove(deps.storage, &game_id)?;
-                        let joiner_token = JOINER_TOKEN_STORE
-                            .add_suffix(game_id.as_bytes())
-                            .load(deps.storage)?;
-                        ACTIVE_GAMES_STORE
-                            .add_suffix(joiner_token.as_bytes())
-                            .remove(deps.storage, &game_id)?;
-
-                        // remove game from listed games
-                        LISTED_GAMES_STORE
-                            .remove(deps.storage, &game_id)?;
-                        // add game to finished games
-                        FINISHED_GAMES_STORE
-                            .insert(deps.storage, &game_id, &listed_game)?;
-                    } else {
-                        TURN_STATE_STORE
-                            .add_suffix(game_id.as_bytes())
-                            .save(deps.storage, &(TurnState::JoinersTurn as u8))?;
-                    }
-
-                    away = initiator_away.away_values;
-                    opponent_owner = JOINER_OWNER_STORE
-                        .add_suffix(game_id.as_bytes())
-                        .load(deps.storage)?;
-                    opponent_home = joiner_home;
-                } else {
-                    return Err(StdError::generic_err("Error reading opponent home from storage"));
-                }
-            } else {
-                return Err(StdError::generic_err("Error reading away from storage"));
-            }
-        } else if !initiator && game_state == TurnState::JoinersTurn as u8 {
-            let joiner_away = JOINER_AWAY_STORE
-                .add_suffix(game_id.as_bytes())
-                .may_load(deps.storage)?;
-            if let Some(mut joiner_away) = joiner_away {
-                if joiner_away.away_values[cell] != CellValue::Empty as u8 {
-                    return Err(StdError::generic_err("You have already attacked this cell"));
-                }
-                let initiator_home = INITIATOR_HOME_STORE
-                    .add_suffix(game_id.as_bytes())
-                    .may_load(deps.storage)?;
-                if let Some(mut initiator_home) = initiator_home {
-                    let opponent_cell_value = initiator_home[cell];
-                    if opponent_cell_value == CellValue::Empty as u8 {
-                        joiner_away.away_values[cell] = CellValue::Miss as u8;
-                        initiator_home[cell] = CellValue::Miss as u8;
-                    } else if opponent_cell_value == CellValue::Carrier as u8 {
-                        record_hit(
-                            &mut joiner_away,
-                            &mut initiator_home,
-                            cell,
-                            CellValue::Carrier as u8,
-                            CARRIER_SIZE
-                        )?;
-                    } else if opponent_cell_value == CellValue::Battleship as u8 {
-                        record_hit(
-                            &mut joiner_away,
-                            &mut initiator_home,
-                            cell,
-                            CellValue::Battleship as u8,
-                            BATTLESHIP_SIZE
-                        )?;
-                    } else if opponent_cell_value == CellValue::Cruiser as u8 {
-                        record_hit(
-                            &mut joiner_away,
-                            &mut initiator_home,
-                            cell,
-                            CellValue::Cruiser as u8,
-                            CRUISER_SIZE
-                        )?;
-                    } else if opponent_cell_value == CellValue::Submarine as u8 {
-                        record_hit(
-                            &mut joiner_away,
-                            &mut initiator_home,
-                            cell,
-                            CellValue::Submarine as u8,
-                            SUBMARINE_SIZE
-                        )?;  
-                    } else if opponent_cell_value == CellValue::Destroyer as u8 {
-                        record_hit(
-                            &mut joiner_away,
-                            &mut initiator_home,
-                            cell,
-                            CellValue::Destroyer as u8,
-                            DESTROYER_SIZE
-                        )?;
-                    } else {
-                        return Err(StdError::generic_err("Invalid cell value"));
-                    }
-                    JOINER_AWAY_STORE
-                        .add_suffix(game_id.as_bytes())
-                        .save(deps.storage, &joiner_away)?;
-                    INITIATOR_HOME_STORE
-                        .add_suffix(game_id.as_bytes())
-                        .save(deps.storage, &initiator_home)?;
-
-                    winner = has_won(&joiner_away);
-                    if winner {
-                        TURN_STATE_STORE
-                            .add_suffix(game_id.as_bytes())
-                            .save(deps.storage, &(TurnState::GameOverJoinerWon as u8))?;
-
-                        // remove from active games for both tokens
-                        ACTIVE_GAMES_STORE
-                            .add_suffix(token_id.as_bytes())
-                            .remove(deps.storage, &game_id)?;
-                        let initiator_token = listed_game.clone().initiator_token_id;
-                        ACTIVE_GAMES_STORE
-                            .add_suffix(initiator_token.as_bytes())
-                            .remove(deps.storage, &game_id)?;
-
-                        // remove game from listed games
-                        LISTED_GAMES_STORE
-                            .remove(deps.storage, &game_id)?;
-                        // add game to finished games
-                        FINISHED_GAMES_STORE
-                            .insert(deps.storage, &game_id, &listed_game)?;
-                    } else {
-                        TURN_STATE_STORE
-                            .add_suffix(game_id.as_bytes())
-                            .save(deps.storage, &(TurnState::InitiatorsTurn as u8))?;
-                    }
-
-                    away = joiner_away.away_values;
-                    opponent_owner = listed_game.initiator_owner;
-                    opponent_home = initiator_home;
-                } else {
-                    return Err(StdError::generic_err("Error reading opponent home from storage"));
-                }
-            } else {
-                return Err(StdError::generic_err("Error reading away from storage"));
-            }
-        } else {
-            return Err(StdError::generic_err("Not your turn to attack"));
-        }
-    } else {
-        return Err(StdError::generic_err("Invalid game state"));
-    }
-
-    LAST_MOVE_TIME_STORE
-        .add_suffix(game_id.as_bytes())
-        .save(deps.storage, &env.block.time.seconds())?;
-
-    // handle snip-52 channel data
-    let channel = GAME_UPDATED_CHANNEL_ID.to_string();
-
-    // get notification id for recipient (opponent owner)
-    let id = notification_id(deps.storage, &opponent_owner, &channel)?;
-
-    let turn = TURN_STATE_STORE
-        .add_suffix(game_id.as_bytes())
-        .load(deps.storage)?;
-
-    // use CBOR to encode data
-    let data = cbor::to_vec(&(
-        game_id.clone(),
-        opponent_home,
+    // notify the opponent
+    let (player, opponent) = game.players_mut(&role)?;
+    let player_owner = player.owner.clone();
+    let mut notifications = game_updated_notifications(
+        deps.storage,
+        &env,
+        sender,
+        &game_id,
         turn,
-    )).map_err(|e| 
-        StdError::generic_err(format!("{:?}", e))
+        opponent,
+        Some(attack.clone()),
     )?;
-
-    // encrypt the message
-    let encrypted_data = encrypt_notification_data(
+    notifications.extend(opponent_attacked_notifications(
         deps.storage,
         &env,
-        &sender,
-        &opponent_owner,
-        &channel,
-        data.clone()
-    )?;
-
-    increment_count(deps.storage, &channel, &opponent_owner)?;
+        sender,
+        &game_id,
+        opponent,
+        &attack,
+    )?);
+    save_game(deps.storage, &game_id, &game)?;
+
+    let mut house_fee = 0_u128;
+    if winner {
+        let wager = game.wager;
+        let (player, opponent) = game.players_mut(&role)?;
+        house_fee = store_game_result(
+            deps.storage,
+            config,
+            &env.block,
+            &game_id,
+            player,
+            opponent,
+            wager,
+        )?;
+        record_win(
+            deps.branch(),
+            &env,
+            config,
+            GameWin { token_id: &player.token_id, owner: &player.owner, wager, perfect },
+        )?;
+    }
 
-    let response: Response;
-    if winner && listed_game.wager > 0_u128 {
-        response = Response::new()
-            .set_data(
-                to_binary(&ExecuteAnswer::AttackCell { away, turn }
-            )?)        
+    let mut response = Response::new()
+        .set_data(
+            to_binary(&ExecuteAnswer::AttackCell { away, turn }
+        )?);
+    if winner && game.wager > 0_u128 {
+        // pay out to whoever owns the winning token now
+        response = response
             .add_message(CosmosMsg::Bank(BankMsg::Send {
-                to_address: sender.clone().into_string(),
+                to_address: deps.api.addr_humanize(&player_owner)?.into_string(),
                 amount: vec![
                     Coin {
                         denom: "uscrt".to_string(),
-                        amount: Uint128::from((listed_game.wager * 2) - 1000000_u128),
+                        amount: Uint128::from((game.wager * 2) - 1000000_u128),
                     }
                 ]
-            }))
-            .add_message(CosmosMsg::Bank(BankMsg::Send { 
-                to_address: deps.api.addr_humanize(&config.admin)?.into_string(), 
+            }));
+    }
+    if house_fee > 0 {
+        response = response
+            .add_message(CosmosMsg::Bank(BankMsg::Send {
+                to_address: deps.api.addr_humanize(&config.admin)?.into_string(),
                 amount: vec![
                     Coin {
                         denom: "uscrt".to_string(),
-                        amount: Uint128::from(1000000_u128),
+                        amount: Uint128::from(house_fee),
                     }
                 ]
-            }))
-            .add_attribute_plaintext(
-                id.to_base64(), 
-                encrypted_data.to_base64()
-            );
-    } else {
-        response = Response::new()
-            .set_data(
-                to_binary(&ExecuteAnswer::AttackCell { away, turn }
-            )?)
-            .add_attribute_plaintext(
-                id.to_base64(), 
-                encrypted_data.to_base64()
-            );
+            }));
     }
 
-    Ok(response)
+    Ok(add_notifications(response, notifications))
 }
 
 pub fn claim_victory(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     sender: &Addr,
-    config: &Config,
+    config: &mut Config,
+    priority: u8,
     token_id: String,
     game_id: String,
 ) -> StdResult<Response> {
+    check_status(config.status, priority)?;
     let _token_owner = verify_owner_or_delegate(
         deps.storage,
         &deps.api.addr_canonicalize(sender.as_str())?,
@@ -1061,38 +970,19 @@ pub fn claim_victory(
         &token_id
     )?;
 
-    // check if game id exists
-    let listed_game = LISTED_GAMES_STORE.get(deps.storage, &game_id);
-    if listed_game.is_none() {
-        return Err(StdError::generic_err("No listed game with that id"));
-    }
-    let listed_game = listed_game.unwrap();
+    let mut game = load_game_for_update(deps.storage, &game_id)?;
 
     // identify if initiator or joiner (or neither)
-    let initiator: bool;
-    if token_id == listed_game.initiator_token_id {
-        initiator = true;
-    } else {
-        let joiner = JOINER_TOKEN_STORE.add_suffix(game_id.as_bytes()).may_load(deps.storage)?;
-        if let Some(joiner) = joiner {
-            if token_id == joiner {
-                initiator = false;
-            } else {
-                return Err(StdError::generic_err("Unauthorized"));
-            }
-        } else {
-            return Err(StdError::generic_err("Unauthorized"));
-        }
-    }
+    let role = game.role_of(&token_id)?;
+    let initiator = role == PlayerRole::Initiator;
 
     // identify if turn is one where you can claim victory
-    let turn = TURN_STATE_STORE
-        .add_suffix(game_id.as_bytes())
-        .load(deps.storage)?;
+    let turn = game.turn;
 
     if turn == TurnState::WaitingForBothPlayersSetup as u8 ||
        turn == TurnState::GameOverInitiatorWon as u8 ||
-       turn == TurnState::GameOverJoinerWon as u8 {
+       turn == TurnState::GameOverJoinerWon as u8 ||
+       turn == TurnState::GameAborted as u8 {
         return Err(StdError::generic_err("Cannot claim victory this turn"));
     }
 
@@ -1109,154 +999,346 @@ pub fn claim_victory(
     }
 
     let mut bank_msgs: Vec<CosmosMsg> = vec![];
-    let mut id: Option<Binary> = None;
-    let mut encrypted_data: Option<Binary> = None;
+    let mut notifications = vec![];
     if initiator && turn == TurnState::WaitingForPlayer as u8 {
         // can always pull out of game before someone joins
-        ACTIVE_GAMES_STORE
-            .add_suffix(listed_game.initiator_token_id.as_bytes())
-            .remove(deps.storage, &game_id)?;
-        TURN_STATE_STORE
-            .add_suffix(game_id.as_bytes())
-            .save(deps.storage, &(TurnState::GameOverInitiatorWon as u8))?;
-        // remove game from listed games
-        LISTED_GAMES_STORE
-            .remove(deps.storage, &game_id)?;
-        // add game to finished games
-        FINISHED_GAMES_STORE
-            .insert(deps.storage, &game_id, &listed_game)?;
-
-        if listed_game.wager > 0 {
+        game.turn = TurnState::GameOverInitiatorWon as u8;
+        finish_game(deps.storage, &game_id, &game, env.block.time.seconds())?;
+        save_game(deps.storage, &game_id, &game)?;
+
+        if game.wager > 0 {
+            store_game_event(
+                deps.storage,
+                config,
+                &env.block,
+                token_id.clone(),
+                StoredTxAction::WagerPaid {
+                    game_id: game_id.clone(),
+                    owner: game.initiator.owner.clone(),
+                    amount: Uint128::from(game.wager),
+                },
+            )?;
+            save(deps.storage, CONFIG_KEY, config)?;
             bank_msgs.push(CosmosMsg::Bank(BankMsg::Send {
-                to_address: sender.clone().into_string(),
+                to_address: deps.api.addr_humanize(&game.initiator.owner)?.into_string(),
                 amount: vec![
                     Coin {
                         denom: "uscrt".to_string(),
-                        amount: Uint128::from(listed_game.wager),
+                        amount: Uint128::from(game.wager),
                     }
                 ],
             }));
         }
     } else {
         let time = env.block.time.seconds();
-        let last_move_time = LAST_MOVE_TIME_STORE
-            .add_suffix(game_id.as_bytes())
-            .load(deps.storage)?;
-        if time < last_move_time + TIMEOUT_SEC {
+        if time < game.last_move_time + TIMEOUT_SEC {
             return Err(StdError::generic_err("Not enough time elapsed to claim victory"));
         }
 
-        ACTIVE_GAMES_STORE
-            .add_suffix(listed_game.initiator_token_id.as_bytes())
-            .remove(deps.storage, &game_id)?;
-        let joiner_token = JOINER_TOKEN_STORE
-            .add_suffix(game_id.as_bytes())
-            .load(deps.storage)?;
-        ACTIVE_GAMES_STORE
-            .add_suffix(joiner_token.as_bytes())
-            .remove(deps.storage, &game_id)?;
-
-        let opponent_owner: CanonicalAddr;
-        let opponent_home: Vec<u8>;
-        if initiator {
-            TURN_STATE_STORE
-                .add_suffix(game_id.as_bytes())
-                .save(deps.storage, &(TurnState::GameOverInitiatorWon as u8))?;
-            opponent_owner = JOINER_OWNER_STORE
-                .add_suffix(game_id.as_bytes())
-                .load(deps.storage)?;
-            opponent_home = JOINER_HOME_STORE
-                .add_suffix(game_id.as_bytes())
-                .load(deps.storage)?;
-        } else {
-            TURN_STATE_STORE
-                .add_suffix(game_id.as_bytes())
-                .save(deps.storage, &(TurnState::GameOverJoinerWon as u8))?;
-            opponent_owner = listed_game.clone().initiator_owner;
-            opponent_home = INITIATOR_HOME_STORE
-                .add_suffix(game_id.as_bytes())
-                .load(deps.storage)?;
-        }
-
-        // remove game from listed games
-        LISTED_GAMES_STORE
-            .remove(deps.storage, &game_id)?;
-        // add game to finished games
-        FINISHED_GAMES_STORE
-            .insert(deps.storage, &game_id, &listed_game)?;
+        game.turn = match role {
+            PlayerRole::Initiator => TurnState::GameOverInitiatorWon as u8,
+            PlayerRole::Joiner => TurnState::GameOverJoinerWon as u8,
+        };
+        finish_game(deps.storage, &game_id, &game, env.block.time.seconds())?;
+
+        // notify the opponent
+        let turn = game.turn;
+        let wager = game.wager;
+        let (player, opponent) = game.players_mut(&role)?;
+        let player_owner = player.owner.clone();
+        notifications = game_updated_notifications(
+            deps.storage,
+            &env,
+            sender,
+            &game_id,
+            turn,
+            opponent,
+            None,
+        )?;
+        let house_fee = store_game_result(
+            deps.storage,
+            config,
+            &env.block,
+            &game_id,
+            player,
+            opponent,
+            wager,
+        )?;
+        record_win(
+            deps.branch(),
+            &env,
+            config,
+            GameWin { token_id: &player.token_id, owner: &player.owner, wager, perfect: false },
+        )?;
+        save_game(deps.storage, &game_id, &game)?;
 
-        if listed_game.wager > 0 {
+        if game.wager > 0 {
             bank_msgs.push(CosmosMsg::Bank(BankMsg::Send {
-                to_address: sender.clone().into_string(),
+                to_address: deps.api.addr_humanize(&player_owner)?.into_string(),
                 amount: vec![
                     Coin {
                         denom: "uscrt".to_string(),
-                        amount: Uint128::from(listed_game.wager * 2 - 1000000_u128),
+                        amount: Uint128::from(game.wager * 2 - 1000000_u128),
                     }
                 ],
             }));
-            bank_msgs.push(CosmosMsg::Bank(BankMsg::Send { 
-                to_address: deps.api.addr_humanize(&config.admin)?.into_string(), 
+        }
+        if house_fee > 0 {
+            bank_msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: deps.api.addr_humanize(&config.admin)?.into_string(),
                 amount: vec![
                     Coin {
                         denom: "uscrt".to_string(),
-                        amount: Uint128::from(1000000_u128),
+                        amount: Uint128::from(house_fee),
                     }
                 ]
             }));
         }
+    }
 
-        // handle snip-52 channel data
-        let channel = GAME_UPDATED_CHANNEL_ID.to_string();
-
-        // get notification id for recipient (opponent owner)
-        id = Some(notification_id(deps.storage, &opponent_owner, &channel)?);
-
-        // use CBOR to encode data
-        let data = cbor::to_vec(&(
-            game_id.clone(),
-            opponent_home,
-            TURN_STATE_STORE
-                .add_suffix(game_id.as_bytes())
-                .load(deps.storage)?,
-        )).map_err(|e| 
-            StdError::generic_err(format!("{:?}", e))
-        )?;
+    let response = Response::new()
+        .set_data(to_binary(&ExecuteAnswer::ClaimVictory {
+            status: ResponseStatus::Success
+        })?)
+        .add_messages(bank_msgs);
+
+    Ok(add_notifications(response, notifications))
+}
+
+/// Returns StdResult<Response>
+///
+/// aborts a game that cannot be finished normally, refunding each player's wager to the
+/// current owner of their token and releasing the tokens for transfer.
+/// admin-only function
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `env` - Env of contract's environment
+/// * `sender` - a reference to the message sender
+/// * `config` - a mutable reference to the Config
+/// * `priority` - u8 representation of highest status level this action is permitted at
+/// * `game_id` - id of the game to abort
+pub fn force_unlock_game(
+    mut deps: DepsMut,
+    env: Env,
+    sender: &Addr,
+    config: &mut Config,
+    priority: u8,
+    game_id: String,
+) -> StdResult<Response> {
+    check_status(config.status, priority)?;
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    if config.admin != sender_raw {
+        return Err(StdError::generic_err(
+            "This is an admin command and can only be run from the admin address",
+        ));
+    }
+
+    let (bank_msgs, notifications) = abort_game(&mut deps, &env, sender, config, &game_id)?;
 
-        // encrypt the message
-        encrypted_data = Some(encrypt_notification_data(
+    let response = Response::new()
+        .set_data(to_binary(&ExecuteAnswer::ForceUnlockGame {
+            status: ResponseStatus::Success
+        })?)
+        .add_messages(bank_msgs);
+
+    Ok(add_notifications(response, notifications))
+}
+
+/// Returns StdResult<Response>
+///
+/// aborts unfinished games while the contract is stopped, refunding each player's wager
+/// to the current owner of their token.
+/// admin-only function
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `env` - Env of contract's environment
+/// * `sender` - a reference to the message sender
+/// * `config` - a mutable reference to the Config
+/// * `priority` - u8 representation of highest status level this action is permitted at
+/// * `limit` - optional max number of games to abort in this call, at most MAX_EMERGENCY_REFUNDS
+pub fn emergency_refund_games(
+    mut deps: DepsMut,
+    env: Env,
+    sender: &Addr,
+    config: &mut Config,
+    priority: u8,
+    limit: Option<u32>,
+) -> StdResult<Response> {
+    check_status(config.status, priority)?;
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    if config.admin != sender_raw {
+        return Err(StdError::generic_err(
+            "This is an admin command and can only be run from the admin address",
+        ));
+    }
+    if config.status == ContractStatus::Normal.to_u8() {
+        return Err(StdError::generic_err(
+            "Emergency refunds are only available while the contract is stopped",
+        ));
+    }
+
+    // every unfinished game stays in the lobby list until it is finished. games that have
+    // not been migrated yet are in the legacy list, and are migrated as they are aborted
+    let limit = limit.unwrap_or(30).min(MAX_EMERGENCY_REFUNDS) as usize;
+    let mut game_ids: Vec<String> = listed_games_store()
+        .iter(deps.storage)?
+        .take(limit)
+        .collect::<StdResult<Vec<String>>>()?;
+    if game_ids.len() < limit {
+        let legacy: Vec<String> = legacy_listed_games_store()
+            .iter_keys(deps.storage)?
+            .take(limit - game_ids.len())
+            .collect::<StdResult<Vec<String>>>()?;
+        game_ids.extend(legacy);
+    }
+
+    let mut bank_msgs: Vec<CosmosMsg> = vec![];
+    let mut notifications = vec![];
+    for game_id in game_ids.iter() {
+        let (refunds, game_notifications) = abort_game(&mut deps, &env, sender, config, game_id)?;
+        bank_msgs.extend(refunds);
+        notifications.extend(game_notifications);
+    }
+
+    let response = Response::new()
+        .set_data(to_binary(&ExecuteAnswer::EmergencyRefundGames {
+            refunded: game_ids.len() as u32,
+            remaining: listed_games_store().get_len(deps.storage)? +
+                legacy_listed_games_store().get_len(deps.storage)?,
+        })?)
+        .add_messages(bank_msgs);
+
+    Ok(add_notifications(response, notifications))
+}
+
+/// aborts an unfinished game, returning the refunds of each player's wager to the current
+/// owner of their token and the notifications telling both players
+#[allow(clippy::type_complexity)]
+fn abort_game(
+    deps: &mut DepsMut,
+    env: &Env,
+    sender: &Addr,
+    config: &mut Config,
+    game_id: &String,
+) -> StdResult<(Vec<CosmosMsg>, Vec<(Binary, Binary)>)> {
+    let mut game = load_game_for_update(deps.storage, game_id)?;
+    if game.turn >= TurnState::GameOverInitiatorWon as u8 {
+        return Err(StdError::generic_err("Game is already over"));
+    }
+
+    game.turn = TurnState::GameAborted as u8;
+    finish_game(deps.storage, game_id, &game, env.block.time.seconds())?;
+
+    let turn = game.turn;
+    let mut refund_to = vec![(game.initiator.token_id.clone(), game.initiator.owner.clone())];
+    let mut notifications = game_updated_notifications(
+        deps.storage,
+        env,
+        sender,
+        game_id,
+        turn,
+        &mut game.initiator,
+        None,
+    )?;
+    if let Some(joiner) = game.joiner.as_mut() {
+        refund_to.push((joiner.token_id.clone(), joiner.owner.clone()));
+        notifications.extend(game_updated_notifications(
             deps.storage,
-            &env,
-            &sender,
-            &opponent_owner,
-            &channel,
-            data.clone()
+            env,
+            sender,
+            game_id,
+            turn,
+            joiner,
+            None,
         )?);
+    }
+    save_game(deps.storage, game_id, &game)?;
+
+    let mut bank_msgs: Vec<CosmosMsg> = vec![];
+    if game.wager > 0 {
+        for (token_id, owner) in refund_to.iter() {
+            store_game_event(
+                deps.storage,
+                config,
+                &env.block,
+                token_id.clone(),
+                StoredTxAction::WagerPaid {
+                    game_id: game_id.clone(),
+                    owner: owner.clone(),
+                    amount: Uint128::from(game.wager),
+                },
+            )?;
+            bank_msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: deps.api.addr_humanize(owner)?.into_string(),
+                amount: vec![
+                    Coin {
+                        denom: "uscrt".to_string(),
+                        amount: Uint128::from(game.wager),
+                    }
+                ],
+            }));
+        }
+        save(deps.storage, CONFIG_KEY, config)?;
+    }
+
+    Ok((bank_msgs, notifications))
+}
 
-        increment_count(deps.storage, &channel, &opponent_owner)?;
+/// Returns StdResult<Response>
+///
+/// moves games still kept in the legacy per-field stores into game records.
+/// admin-only function
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `sender` - a reference to the message sender
+/// * `config` - a reference to the Config
+/// * `priority` - u8 representation of highest status level this action is permitted at
+/// * `limit` - optional max number of games to migrate in this call
+pub fn migrate_games(
+    deps: DepsMut,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    limit: Option<u32>,
+) -> StdResult<Response> {
+    check_status(config.status, priority)?;
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    if config.admin != sender_raw {
+        return Err(StdError::generic_err(
+            "This is an admin command and can only be run from the admin address",
+        ));
     }
 
-    let response;
+    let limit = limit.unwrap_or(30) as usize;
+    let mut game_ids: Vec<String> = legacy_listed_games_store()
+        .iter_keys(deps.storage)?
+        .take(limit)
+        .collect::<StdResult<Vec<String>>>()?;
+    if game_ids.len() < limit {
+        let finished: Vec<String> = legacy_finished_games_store()
+            .iter_keys(deps.storage)?
+            .take(limit - game_ids.len())
+            .collect::<StdResult<Vec<String>>>()?;
+        game_ids.extend(finished);
+    }
 
-    if id.is_some() {
-        response = Response::new()
-            .set_data(to_binary(&ExecuteAnswer::ClaimVictory { 
-                status: ResponseStatus::Success 
-            })?)
-            .add_messages(bank_msgs)
-            .add_attribute_plaintext(
-                id.unwrap().to_base64(), 
-                encrypted_data.unwrap().to_base64()
-            );
-    } else {
-        response = Response::new()
-            .set_data(to_binary(&ExecuteAnswer::ClaimVictory { 
-                status: ResponseStatus::Success 
-            })?)
-            .add_messages(bank_msgs)
+    for game_id in game_ids.iter() {
+        migrate_legacy_game(deps.storage, game_id)?;
     }
 
-    Ok(response)
+    let remaining = legacy_listed_games_store().get_len(deps.storage)? +
+        legacy_finished_games_store().get_len(deps.storage)?;
+
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::MigrateGames {
+        migrated: game_ids.len() as u32,
+        remaining,
+    })?))
 }
 
 pub fn query_list_games(
@@ -1275,20 +1357,38 @@ pub fn query_list_games(
     )?;
 
     let page = page.unwrap_or(0_u32);
-    let page_size = page_size.unwrap_or(20_u32);
-    let games: Vec<ListedGame> = LISTED_GAMES_STORE
-        .paging(deps.storage, page, page_size)?
+    let page_size = page_size.unwrap_or(20_u32) as usize;
+    // games that have not been migrated yet are listed after the migrated ones
+    let skip = page as usize * page_size;
+    let listed = listed_games_store().get_len(deps.storage)? as usize;
+    let mut game_ids: Vec<String> = listed_games_store()
+        .iter(deps.storage)?
+        .skip(skip)
+        .take(page_size)
+        .collect::<StdResult<Vec<String>>>()?;
+    if game_ids.len() < page_size {
+        let legacy: Vec<String> = legacy_listed_games_store()
+            .iter_keys(deps.storage)?
+            .skip(skip.saturating_sub(listed))
+            .take(page_size - game_ids.len())
+            .collect::<StdResult<Vec<String>>>()?;
+        game_ids.extend(legacy);
+    }
+    let games: Vec<ListedGame> = game_ids
         .into_iter()
-        .map(|(game_id, stored_game)| ListedGame {
-            game_id,
-            wager: Coin {
-                denom: DENOM.to_string(),
-                amount: Uint128::from(stored_game.wager),
-            },
-            title: stored_game.title,
-            created: stored_game.created,
+        .map(|game_id| {
+            let game = load_game(deps.storage, &game_id)?;
+            Ok(ListedGame {
+                game_id,
+                wager: Coin {
+                    denom: DENOM.to_string(),
+                    amount: Uint128::from(game.wager),
+                },
+                title: game.title,
+                created: game.created,
+            })
         })
-        .collect();
+        .collect::<StdResult<Vec<ListedGame>>>()?;
     to_binary(&QueryAnswer::ListGames { games })
 }
 
@@ -1304,13 +1404,67 @@ pub fn query_active_games(
         &config,
         &token_id
     )?;
-    let game_ids = ACTIVE_GAMES_STORE
+    let game_ids: Vec<String> = ACTIVE_GAMES_STORE
         .add_suffix(token_id.as_bytes())
         .iter(deps.storage)?
-        .map(|game_id| game_id.unwrap())
-        .collect();
-        
-    to_binary(&QueryAnswer::ActiveGames { game_ids })
+        .collect::<StdResult<Vec<String>>>()?;
+
+    let mut games: Vec<ActiveGameSummary> = vec![];
+    for game_id in game_ids {
+        let game = load_game(deps.storage, &game_id)?;
+        let role = game.role_of(&token_id)?;
+        let opponent = match role {
+            PlayerRole::Initiator => game.joiner.as_ref(),
+            PlayerRole::Joiner => Some(&game.initiator),
+        };
+        games.push(ActiveGameSummary {
+            game_id,
+            title: game.title.clone(),
+            role: role as u8,
+            opponent_token_id: opponent.map(|opponent| opponent.token_id.clone()),
+            turn: game.turn,
+            wager: Coin {
+                denom: DENOM.to_string(),
+                amount: Uint128::from(game.wager),
+            },
+            deadline: game.last_move_time + TIMEOUT_SEC,
+        });
+    }
+
+    to_binary(&QueryAnswer::ActiveGames { games })
+}
+
+pub fn query_finished_games(
+    deps: Deps,
+    token_id: String,
+    page: Option<u32>,
+    page_size: Option<u32>,
+    address_raw: &CanonicalAddr,
+) -> StdResult<Binary> {
+    let config: Config = load(deps.storage, CONFIG_KEY)?;
+    let _token_owner = verify_owner_or_delegate(
+        deps.storage,
+        address_raw,
+        &config,
+        &token_id
+    )?;
+    let page = page.unwrap_or(0);
+    let page_size = page_size.unwrap_or(30);
+
+    // most recently finished games first
+    let history = FINISHED_GAMES_STORE.add_suffix(token_id.as_bytes());
+    let games = history
+        .iter(deps.storage)?
+        .rev()
+        .skip((page * page_size) as usize)
+        .take(page_size as usize)
+        .map(|game| game.map(StoredFinishedGame::into_display))
+        .collect::<StdResult<Vec<FinishedGame>>>()?;
+
+    to_binary(&QueryAnswer::FinishedGames {
+        games,
+        total: history.get_len(deps.storage)?,
+    })
 }
 
 pub fn query_game_state(
@@ -1327,69 +1481,30 @@ pub fn query_game_state(
         &token_id
     )?;
 
-    let mut listed_game = LISTED_GAMES_STORE
-        .get(deps.storage, &game_id);
-    if listed_game.is_none() {
-        //check if it is a finished game instead
-        listed_game = FINISHED_GAMES_STORE
-            .get(deps.storage, &game_id);
-        if listed_game.is_none() {
-            return Err(StdError::generic_err("Game is not listed or finished"));
-        }
-    }
-    let listed_game = listed_game.unwrap();
-    let joiner_token = JOINER_TOKEN_STORE
-        .add_suffix(game_id.as_bytes())
-        .may_load(deps.storage)?;
-    let role: PlayerRole;
-    let home: Vec<u8>;
-    let away: Vec<u8>;
-    if token_id == listed_game.initiator_token_id {
-        role = PlayerRole::Initiator;
-        home = INITIATOR_HOME_STORE
-            .add_suffix(game_id.as_bytes())
-            .may_load(deps.storage)?
-            .unwrap_or(vec![CellValue::Empty as u8; BOARD_SIZE]);
-        let stored_away = INITIATOR_AWAY_STORE
-            .add_suffix(game_id.as_bytes())
-            .may_load(deps.storage)?;
-        if stored_away.is_some() {
-            away = stored_away.unwrap().away_values;
-        } else {
-            away = vec![CellValue::Empty as u8; BOARD_SIZE];
-        }
-    } else if Some(token_id) == joiner_token {
-        role = PlayerRole::Joiner;
-        home = JOINER_HOME_STORE
-            .add_suffix(game_id.as_bytes())
-            .may_load(deps.storage)?
-            .unwrap_or(vec![CellValue::Empty as u8; BOARD_SIZE]);
-        let stored_away = JOINER_AWAY_STORE
-            .add_suffix(game_id.as_bytes())
-            .may_load(deps.storage)?;
-        if stored_away.is_some() {
-            away = stored_away.unwrap().away_values;
-        } else {
-            away = vec![CellValue::Empty as u8; BOARD_SIZE];
-        }
-    } else {
-        return Err(StdError::generic_err("Unauthorized"));
-    }
-
-    let turn = TURN_STATE_STORE
-        .add_suffix(game_id.as_bytes())
-        .load(deps.storage)?;
+    let game = load_game(deps.storage, &game_id)
+        .map_err(|_| StdError::generic_err("Game is not listed or finished"))?;
+    let role = game.role_of(&token_id)?;
+    let (player, opponent) = match role {
+        PlayerRole::Initiator => (Some(&game.initiator), game.joiner.as_ref()),
+        PlayerRole::Joiner => (game.joiner.as_ref(), Some(&game.initiator)),
+    };
+    let home = player
+        .and_then(|player| player.home.clone())
+        .unwrap_or(vec![CellValue::Empty as u8; BOARD_SIZE]);
+    let away = match opponent.and_then(|opponent| opponent.home.as_ref()) {
+        Some(opponent_home) => away_view(opponent_home),
+        None => vec![CellValue::Empty as u8; BOARD_SIZE],
+    };
 
-    let game = listed_game;
     let wager = Coin {
         denom: "uscrt".to_string(),
         amount: Uint128::from(game.wager)
     };
 
-    to_binary(&QueryAnswer::GameState { 
-        role: role as u8, 
-        turn: turn, 
-        home, 
+    to_binary(&QueryAnswer::GameState {
+        role: role as u8,
+        turn: game.turn,
+        home,
         away,
         game_id,
         wager,
@@ -1400,6 +1515,398 @@ pub fn query_game_state(
 
 // STATE
 
+/// layout version written into every game record
+pub const GAME_RECORD_VERSION: u8 = 1;
+
+/// everything known about one player's side of a game
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StoredPlayer {
+    pub token_id: String, // token id
+    pub owner: CanonicalAddr, // owner of token
+    /// number of game update notifications sent to this player
+    pub seq: u32,
+    /// None until the player submits their setup. attacks are recorded on the
+    /// home board, the opponent's away grid is derived from it
+    #[serde(with = "packed_board")]
+    pub home: Option<Vec<u8>>,
+}
+
+impl StoredPlayer {
+    pub fn new(token_id: String, owner: CanonicalAddr) -> Self {
+        StoredPlayer {
+            token_id,
+            owner,
+            seq: 0,
+            home: None,
+        }
+    }
+}
+
+/// stores a board as two cells per byte. every cell value fits in a nibble:
+/// Miss or the ship type in the low three bits and the Hit flag in the high bit
+mod packed_board {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    fn pack(value: u8) -> u8 {
+        (value & 0x07) | ((value & 0x80) >> 4)
+    }
+
+    fn unpack(nibble: u8) -> u8 {
+        (nibble & 0x07) | ((nibble & 0x08) << 4)
+    }
+
+    pub fn serialize<S: Serializer>(board: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error> {
+        board
+            .as_ref()
+            .map(|cells| {
+                cells
+                    .chunks(2)
+                    .map(|pair| pack(pair[0]) | pack(*pair.get(1).unwrap_or(&0)) << 4)
+                    .collect::<Vec<u8>>()
+            })
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error> {
+        let packed: Option<Vec<u8>> = Option::deserialize(deserializer)?;
+        Ok(packed.map(|bytes| {
+            bytes
+                .iter()
+                .flat_map(|byte| [unpack(byte & 0x0F), unpack(byte >> 4)])
+                .collect()
+        }))
+    }
+}
+
+/// the complete state of a game, read and written as a single record
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    /// layout version, see GAME_RECORD_VERSION
+    pub version: u8,
+    pub title: String,
+    pub wager: u128,
+    pub created: Timestamp,
+    /// TurnState
+    pub turn: u8,
+    pub initiator_goes_first: bool,
+    pub last_move_time: u64,
+    pub initiator: StoredPlayer,
+    pub joiner: Option<StoredPlayer>,
+}
+
+impl GameRecord {
+    /// returns the role the token plays in this game
+    pub fn role_of(&self, token_id: &str) -> StdResult<PlayerRole> {
+        if token_id == self.initiator.token_id {
+            return Ok(PlayerRole::Initiator);
+        }
+        match &self.joiner {
+            Some(joiner) if joiner.token_id == token_id => Ok(PlayerRole::Joiner),
+            _ => Err(StdError::generic_err("Unauthorized")),
+        }
+    }
+
+    /// returns the part of the game the rules act on
+    pub fn state(&self) -> GameState {
+        GameState {
+            turn: self.turn,
+            initiator_goes_first: self.initiator_goes_first,
+            initiator_home: self.initiator.home.clone(),
+            joiner_home: self.joiner.as_ref().and_then(|joiner| joiner.home.clone()),
+        }
+    }
+
+    /// updates the game after the rules have acted on it
+    pub fn set_state(&mut self, state: GameState) {
+        self.turn = state.turn;
+        self.initiator.home = state.initiator_home;
+        if let Some(joiner) = self.joiner.as_mut() {
+            joiner.home = state.joiner_home;
+        }
+    }
+
+    /// returns (player, opponent) for the given role
+    pub fn players_mut(&mut self, role: &PlayerRole) -> StdResult<(&mut StoredPlayer, &mut StoredPlayer)> {
+        let joiner = self
+            .joiner
+            .as_mut()
+            .ok_or_else(|| StdError::generic_err("Game does not have a joiner"))?;
+        match role {
+            PlayerRole::Initiator => Ok((&mut self.initiator, joiner)),
+            PlayerRole::Joiner => Ok((joiner, &mut self.initiator)),
+        }
+    }
+}
+
+/// loads a game record, falling back to the legacy stores for games that
+/// have not been migrated yet
+pub fn load_game(storage: &dyn Storage, game_id: &str) -> StdResult<GameRecord> {
+    let game = match GAME_RECORD_STORE.add_suffix(game_id.as_bytes()).may_load(storage)? {
+        Some(game) => game,
+        None => load_legacy_game(storage, game_id)?
+            .ok_or_else(|| StdError::generic_err("No listed game with that id"))?,
+    };
+    check_game_version(game)
+}
+
+/// loads a game record that is about to be modified. only a game without a record
+/// is looked for in the legacy stores, and migrated out of them
+pub fn load_game_for_update(storage: &mut dyn Storage, game_id: &str) -> StdResult<GameRecord> {
+    let game = match GAME_RECORD_STORE.add_suffix(game_id.as_bytes()).may_load(storage)? {
+        Some(game) => game,
+        None => migrate_legacy_game(storage, game_id)?
+            .ok_or_else(|| StdError::generic_err("No listed game with that id"))?,
+    };
+    check_game_version(game)
+}
+
+fn check_game_version(game: GameRecord) -> StdResult<GameRecord> {
+    if game.version != GAME_RECORD_VERSION {
+        return Err(StdError::generic_err(format!(
+            "Unsupported game record version {}", game.version
+        )));
+    }
+    Ok(game)
+}
+
+pub fn save_game(storage: &mut dyn Storage, game_id: &str, game: &GameRecord) -> StdResult<()> {
+    GAME_RECORD_STORE.add_suffix(game_id.as_bytes()).save(storage, game)
+}
+
+/// records a decided game in both tokens' transaction histories, along with the
+/// winnings paid to the owner of the winning token, and credits the season points.
+/// returns the part of the house fee left for the admin after funding the season prize pool
+fn store_game_result(
+    storage: &mut dyn Storage,
+    config: &mut Config,
+    block: &BlockInfo,
+    game_id: &str,
+    winner: &StoredPlayer,
+    loser: &StoredPlayer,
+    wager: u128,
+) -> StdResult<u128> {
+    store_game_event(
+        storage,
+        config,
+        block,
+        winner.token_id.clone(),
+        StoredTxAction::GameWon {
+            game_id: game_id.to_string(),
+            owner: winner.owner.clone(),
+            opponent_token_id: loser.token_id.clone(),
+        },
+    )?;
+    store_game_event(
+        storage,
+        config,
+        block,
+        loser.token_id.clone(),
+        StoredTxAction::GameLost {
+            game_id: game_id.to_string(),
+            owner: loser.owner.clone(),
+            opponent_token_id: winner.token_id.clone(),
+        },
+    )?;
+    if wager > 0 {
+        store_game_event(
+            storage,
+            config,
+            block,
+            winner.token_id.clone(),
+            StoredTxAction::WagerPaid {
+                game_id: game_id.to_string(),
+                owner: winner.owner.clone(),
+                amount: Uint128::from(wager * 2 - 1000000_u128),
+            },
+        )?;
+    }
+    save(storage, CONFIG_KEY, config)?;
+
+    let house_fee = if wager > 0 { 1000000_u128 } else { 0 };
+    let pooled = record_season_result(
+        storage,
+        block.time.seconds(),
+        &winner.token_id,
+        &loser.token_id,
+        house_fee,
+    )?;
+    Ok(house_fee - pooled)
+}
+
+/// returns an error listing the token's active games that have a wager, because the
+/// token may not change hands until those games are decided
+pub fn check_wager_lock(storage: &dyn Storage, token_id: &str) -> StdResult<()> {
+    let mut locked: Vec<String> = vec![];
+    for game_id in ACTIVE_GAMES_STORE.add_suffix(token_id.as_bytes()).iter(storage)? {
+        let game_id = game_id?;
+        if load_game(storage, &game_id)?.wager > 0 {
+            locked.push(game_id);
+        }
+    }
+    if !locked.is_empty() {
+        return Err(StdError::generic_err(format!(
+            "Token ID: {} is locked in wagered games until they finish: {}",
+            token_id,
+            locked.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// points the token's entries in its active games at the token's new owner, so
+/// that notifications and payouts follow the token when it changes hands
+pub fn update_active_game_owners(
+    storage: &mut dyn Storage,
+    token_id: &str,
+    new_owner: &CanonicalAddr,
+) -> StdResult<()> {
+    let game_ids: Vec<String> = ACTIVE_GAMES_STORE
+        .add_suffix(token_id.as_bytes())
+        .iter(storage)?
+        .collect::<StdResult<Vec<String>>>()?;
+    for game_id in game_ids {
+        let mut game = load_game_for_update(storage, &game_id)?;
+        let role = game.role_of(token_id)?;
+        match role {
+            PlayerRole::Initiator => game.initiator.owner = new_owner.clone(),
+            PlayerRole::Joiner => game.players_mut(&role)?.0.owner = new_owner.clone(),
+        }
+        save_game(storage, &game_id, &game)?;
+    }
+    Ok(())
+}
+
+/// removes a finished game from the lobby and from both tokens' active games, and adds it
+/// to both tokens' game histories
+fn finish_game(
+    storage: &mut dyn Storage,
+    game_id: &String,
+    game: &GameRecord,
+    time: u64,
+) -> StdResult<()> {
+    let winnings = if game.wager > 0 { game.wager * 2 - 1000000_u128 } else { 0 };
+    let mut players = vec![(&game.initiator, game.joiner.as_ref(), PlayerRole::Initiator)];
+    if let Some(joiner) = &game.joiner {
+        players.push((joiner, Some(&game.initiator), PlayerRole::Joiner));
+    }
+    for (player, opponent, role) in players {
+        ACTIVE_GAMES_STORE
+            .add_suffix(player.token_id.as_bytes())
+            .remove(storage, game_id)?;
+
+        let (outcome, payout) = match (game.turn, &role, opponent) {
+            (turn, _, _) if turn == TurnState::GameAborted as u8 => (GameOutcome::Aborted, game.wager),
+            (_, _, None) => (GameOutcome::Withdrawn, game.wager),
+            (turn, PlayerRole::Initiator, _) if turn == TurnState::GameOverInitiatorWon as u8 => (GameOutcome::Won, winnings),
+            (turn, PlayerRole::Joiner, _) if turn == TurnState::GameOverJoinerWon as u8 => (GameOutcome::Won, winnings),
+            _ => (GameOutcome::Lost, 0),
+        };
+        FINISHED_GAMES_STORE.add_suffix(player.token_id.as_bytes()).push(
+            storage,
+            &StoredFinishedGame {
+                game_id: game_id.clone(),
+                outcome: outcome as u8,
+                opponent_token_id: opponent.map(|opponent| opponent.token_id.clone()),
+                wager: game.wager,
+                payout,
+                finished: time,
+            },
+        )?;
+    }
+    listed_games_store().remove(storage, game_id)
+}
+
+/// assembles a game record out of the legacy per-field stores
+fn load_legacy_game(storage: &dyn Storage, game_id: &str) -> StdResult<Option<GameRecord>> {
+    let game_key = game_id.to_string();
+    let listed_game = match legacy_listed_games_store().get(storage, &game_key) {
+        Some(listed_game) => listed_game,
+        None => match legacy_finished_games_store().get(storage, &game_key) {
+            Some(finished_game) => finished_game,
+            None => return Ok(None),
+        },
+    };
+    let suffix = game_id.as_bytes();
+    let joiner = match LEGACY_JOINER_TOKEN_STORE.add_suffix(suffix).may_load(storage)? {
+        Some(token_id) => Some(StoredPlayer {
+            token_id,
+            owner: LEGACY_JOINER_OWNER_STORE.add_suffix(suffix).load(storage)?,
+            seq: 0,
+            home: LEGACY_JOINER_HOME_STORE.add_suffix(suffix).may_load(storage)?,
+        }),
+        None => None,
+    };
+    Ok(Some(GameRecord {
+        version: GAME_RECORD_VERSION,
+        title: listed_game.title,
+        wager: listed_game.wager,
+        created: listed_game.created,
+        turn: LEGACY_TURN_STATE_STORE.add_suffix(suffix).load(storage)?,
+        initiator_goes_first: listed_game.initiator_goes_first,
+        last_move_time: LEGACY_LAST_MOVE_TIME_STORE
+            .add_suffix(suffix)
+            .may_load(storage)?
+            .unwrap_or(listed_game.created.seconds()),
+        initiator: StoredPlayer {
+            token_id: listed_game.initiator_token_id,
+            owner: listed_game.initiator_owner,
+            seq: 0,
+            home: LEGACY_INITIATOR_HOME_STORE.add_suffix(suffix).may_load(storage)?,
+        },
+        joiner,
+    }))
+}
+
+/// one-time migration of a game out of the legacy stores. returns None if the
+/// game is not in the legacy stores
+fn migrate_legacy_game(storage: &mut dyn Storage, game_id: &str) -> StdResult<Option<GameRecord>> {
+    let game = match load_legacy_game(storage, game_id)? {
+        Some(game) => game,
+        None => return Ok(None),
+    };
+    let game_key = game_id.to_string();
+    if legacy_listed_games_store().contains(storage, &game_key) {
+        listed_games_store().insert(storage, &game_key)?;
+        legacy_listed_games_store().remove(storage, &game_key)?;
+    } else {
+        legacy_finished_games_store().remove(storage, &game_key)?;
+    }
+    let suffix = game_id.as_bytes();
+    LEGACY_TURN_STATE_STORE.add_suffix(suffix).remove(storage);
+    LEGACY_JOINER_TOKEN_STORE.add_suffix(suffix).remove(storage);
+    LEGACY_JOINER_OWNER_STORE.add_suffix(suffix).remove(storage);
+    LEGACY_INITIATOR_HOME_STORE.add_suffix(suffix).remove(storage);
+    LEGACY_JOINER_HOME_STORE.add_suffix(suffix).remove(storage);
+    LEGACY_INITIATOR_AWAY_STORE.add_suffix(suffix).remove(storage);
+    LEGACY_JOINER_AWAY_STORE.add_suffix(suffix).remove(storage);
+    LEGACY_LAST_MOVE_TIME_STORE.add_suffix(suffix).remove(storage);
+    save_game(storage, game_id, &game)?;
+    Ok(Some(game))
+}
+
+// prefix game_id. value is the game record
+pub static GAME_RECORD_STORE: Item<GameRecord> = Item::new(b"game-record");
+// set of game_ids that have not finished yet.
+// the unsuffixed collections are built on use instead of being statics, because
+// Keyset/Keymap cache their length and a static would share it across unit tests
+pub fn listed_games_store() -> Keyset<'static, String> {
+    Keyset::new(b"listed-game-ids")
+}
+// set of active game_ids for prefix token_id
+pub static ACTIVE_GAMES_STORE: Keyset<String> = Keyset::new(b"active-games");
+// set of token_ids subscribed to the lobby tier for prefix wager (big-endian u128)
+pub static LOBBY_SUBSCRIBERS_STORE: Keyset<String> = Keyset::new(b"lobby-subscribers");
+// index of the next subscriber to notify in the lobby tier for prefix wager (big-endian u128)
+pub static LOBBY_CURSOR_STORE: Item<u32> = Item::new(b"lobby-cursor");
+// lobby tiers subscribed to for prefix token_id
+pub static LOBBY_SUBSCRIPTIONS_STORE: Item<LobbySubscription> = Item::new(b"lobby-subscriptions");
+// delegates that receive the game notifications for prefix token_id
+pub static NOTIFICATION_DELEGATES_STORE: Item<Vec<CanonicalAddr>> = Item::new(b"notification-delegates");
+// history of finished games for prefix token_id
+pub static FINISHED_GAMES_STORE: AppendStore<StoredFinishedGame> = AppendStore::new(b"game-history");
+
+// LEGACY STATE, only read when migrating games created before game records
+
 /// a listed game
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct StoredListedGame {
@@ -1422,27 +1929,29 @@ pub struct StoredAway {
 }
 
 // prefix game_id. value is TurnState
-pub static TURN_STATE_STORE: Item<u8> = Item::new(b"turn-state");
+pub static LEGACY_TURN_STATE_STORE: Item<u8> = Item::new(b"turn-state");
 // prefix game_id. value is joiner token_id
-pub static JOINER_TOKEN_STORE: Item<String> = Item::new(b"game-joiner-tok");
+pub static LEGACY_JOINER_TOKEN_STORE: Item<String> = Item::new(b"game-joiner-tok");
 // prefix game_id. value is joiner token owner
-pub static JOINER_OWNER_STORE: Item<CanonicalAddr> = Item::new(b"game-joiner-owner");
+pub static LEGACY_JOINER_OWNER_STORE: Item<CanonicalAddr> = Item::new(b"game-joiner-owner");
 // prefix game_id. value is initiator home board
-pub static INITIATOR_HOME_STORE: Item<Vec<u8>> = Item::new(b"initiator-home");
+pub static LEGACY_INITIATOR_HOME_STORE: Item<Vec<u8>> = Item::new(b"initiator-home");
 // prefix game_id. value is joiner home board
-pub static JOINER_HOME_STORE: Item<Vec<u8>> = Item::new(b"joiner-home");
+pub static LEGACY_JOINER_HOME_STORE: Item<Vec<u8>> = Item::new(b"joiner-home");
 // prefix game_id. value is initiator away board
-pub static INITIATOR_AWAY_STORE: Item<StoredAway> = Item::new(b"initiator-away");
+pub static LEGACY_INITIATOR_AWAY_STORE: Item<StoredAway> = Item::new(b"initiator-away");
 // prefix game_id. value is joiner away board
-pub static JOINER_AWAY_STORE: Item<StoredAway> = Item::new(b"joiner-away");
+pub static LEGACY_JOINER_AWAY_STORE: Item<StoredAway> = Item::new(b"joiner-away");
 // game_id -> listed game
-pub static LISTED_GAMES_STORE: Keymap<String, StoredListedGame> = Keymap::new(b"listed-games");
+pub fn legacy_listed_games_store() -> Keymap<'static, String, StoredListedGame> {
+    Keymap::new(b"listed-games")
+}
 // game_id -> finished game
-pub static FINISHED_GAMES_STORE: Keymap<String, StoredListedGame> = Keymap::new(b"finished-games");
-// set of active game_ids for prefix token_id
-pub static ACTIVE_GAMES_STORE: Keyset<String> = Keyset::new(b"active-games");
+pub fn legacy_finished_games_store() -> Keymap<'static, String, StoredListedGame> {
+    Keymap::new(b"finished-games")
+}
 // prefix game_id. value is last move timestamp
-pub static LAST_MOVE_TIME_STORE: Item<u64> = Item::new(b"last-move");
+pub static LEGACY_LAST_MOVE_TIME_STORE: Item<u64> = Item::new(b"last-move");
 
 // SVG template
 pub static SVG_TEMPLATE: Item<String> = Item::new(b"svg_template");
@@ -1451,117 +1960,330 @@ pub static SVG_TEMPLATE: Item<String> = Item::new(b"svg_template");
 
 #[cfg(test)]
 mod tests {
-    use std::any::Any;
+    use std::cell::Cell;
+    use std::marker::PhantomData;
 
     use cosmwasm_std::{testing::*, Coin, Uint128};
     use cosmwasm_std::{
-        from_binary, Binary, OwnedDeps,
-        Response, StdError, StdResult,
+        from_binary, Addr, Api, Binary, OwnedDeps,
+        Response, StdResult, Storage, CosmosMsg, BankMsg,
+    };
+    use crate::battleship::{
+        encode_game_delta, AttackResult, CellValue, load_game, GameRecord, StoredAway,
+        GameOutcome, FINISHED_GAMES_STORE, TIMEOUT_SEC,
+        StoredListedGame, TurnState, BOARD_SIZE, GAME_RECORD_STORE, listed_games_store,
+        ACTIVE_GAMES_STORE, legacy_listed_games_store, LEGACY_TURN_STATE_STORE,
+        LEGACY_JOINER_TOKEN_STORE, LEGACY_JOINER_OWNER_STORE, LEGACY_INITIATOR_HOME_STORE,
+        LEGACY_JOINER_HOME_STORE, LEGACY_INITIATOR_AWAY_STORE, LEGACY_JOINER_AWAY_STORE,
+        LEGACY_LAST_MOVE_TIME_STORE, LOBBY_SUBSCRIBERS_STORE, LOBBY_NOTIFY_BATCH, LOBBY_SUBSCRIPTION_SEC, VALID_WAGERS,
+        MAX_EMERGENCY_REFUNDS, query_list_games,
     };
-    use crate::battleship::valid_setup;
     use crate::contract::{execute, instantiate, query,};
     use crate::msg::{
-        ExecuteAnswer, ExecuteMsg, InstantiateConfig,
-        InstantiateMsg, QueryAnswer, ViewerInfo, QueryMsg, 
+        ContractStatus, ExecuteAnswer, ExecuteMsg, InstantiateConfig,
+        InstantiateMsg, QueryAnswer, ViewerInfo, QueryMsg, Tx, TxAction, TxActionFilter,
     };
+    use crate::contract::get_token;
+    use cosmwasm_storage::PrefixedStorage;
+    use crate::engine::{valid_setup, away_view};
+    use crate::snip52_channel::{
+        GAME_LISTED_CHANNEL_ID, OPPONENT_ATTACKED_CHANNEL_ID, PLAYER_JOINED_CHANNEL_ID,
+    };
+    use crate::snip52_exec_query::notification_id;
+    use crate::snip52_state::get_count;
+    use crate::state::{
+        get_txs, PREFIX_INFOS, PREFIX_MAP_TO_INDEX, TX_ID_BY_TYPE_START_STORE, TX_ID_BY_TYPE_STORE, TX_ID_STORE,
+    };
+    use crate::seasons::{LEADERBOARD_SIZE, LOSS_POINTS, WIN_POINTS};
+    use crate::test_helpers::extract_error_msg;
     use crate::token::{Metadata, Extension};
 
     // Helper functions
 
-    fn init_helper_default() -> (
+    fn init_helper_with_config(
+        public_token_supply: bool,
+        public_owner: bool,
+        enable_sealed_metadata: bool,
+        unwrapped_metadata_is_private: bool,
+        minter_may_update_metadata: bool,
+        owner_may_update_metadata: bool,
+        enable_burn: bool,
+        minter_may_put_token_storage: bool,
+        minter_may_put_global_storage: bool,
+    ) -> (
         StdResult<Response>,
         OwnedDeps<MockStorage, MockApi, MockQuerier>,
     ) {
         let mut deps = mock_dependencies();
+
         let env = mock_env();
+        let init_config: InstantiateConfig = from_binary(&Binary::from(
+            format!(
+                "{{\"public_token_supply\":{},
+                \"public_owner\":{},
+                \"enable_sealed_metadata\":{},
+                \"unwrapped_metadata_is_private\":{},
+                \"minter_may_update_metadata\":{},
+                \"owner_may_update_metadata\":{},
+                \"enable_burn\":{},
+                \"minter_may_put_token_storage\":{},
+                \"minter_may_put_global_storage\":{}}}",
+                public_token_supply,
+                public_owner,
+                enable_sealed_metadata,
+                unwrapped_metadata_is_private,
+                minter_may_update_metadata,
+                owner_may_update_metadata,
+                enable_burn,
+                minter_may_put_token_storage,
+                minter_may_put_global_storage,
+            )
+            .as_bytes(),
+        ))
+        .unwrap();
         let info = mock_info("instantiator", &[]);
         let init_msg = InstantiateMsg {
-            name: "sec721".to_string(),
-            symbol: "S721".to_string(),
+            name: "sec821".to_string(),
+            symbol: "S821".to_string(),
             admin: Some("admin".to_string()),
             entropy: "We're going to need a bigger boat".to_string(),
             royalty_info: None,
-            config: None,
+            config: Some(init_config),
             post_init_callback: None,
-            template: None,
+            template: Some("<svg> [[ @{CONTRACT_ADDR} ]] [[ @{TOKEN_ID} ]] [[ @{CONTRACT_ADDR} ]] [[ @{TOKEN_ID} ]] </svg>".to_string()),
         };
 
         (instantiate(deps.as_mut(), env, info, init_msg), deps)
     }
 
-    fn init_helper_with_config(
-        public_token_supply: bool,
-        public_owner: bool,
-        enable_sealed_metadata: bool,
-        unwrapped_metadata_is_private: bool,
-        minter_may_update_metadata: bool,
-        owner_may_update_metadata: bool,
-        enable_burn: bool,
-        minter_may_put_token_storage: bool,
-        minter_may_put_global_storage: bool,
-    ) -> (
-        StdResult<Response>,
-        OwnedDeps<MockStorage, MockApi, MockQuerier>,
-    ) {
-        let mut deps = mock_dependencies();
+    fn extract_log(resp: StdResult<Response>) -> String {
+        match resp {
+            Ok(response) => response.attributes[0].value.clone(),
+            Err(_err) => "These are not the logs you are looking for".to_string(),
+        }
+    }
+
+    // gas costs from the cosmos-sdk KVStore gas config
+    const READ_COST_FLAT: u64 = 1000;
+    const READ_COST_PER_BYTE: u64 = 3;
+    const WRITE_COST_FLAT: u64 = 2000;
+    const WRITE_COST_PER_BYTE: u64 = 30;
+    const DELETE_COST: u64 = 1000;
+
+    /// MockStorage that charges storage gas the same way the chain does
+    #[derive(Default)]
+    struct GasMeteredStorage {
+        storage: MockStorage,
+        gas_used: Cell<u64>,
+    }
+
+    impl GasMeteredStorage {
+        /// returns the gas used since the last call
+        fn take_gas(&self) -> u64 {
+            self.gas_used.replace(0)
+        }
+
+        fn charge(&self, gas: u64) {
+            self.gas_used.set(self.gas_used.get() + gas);
+        }
+    }
+
+    impl Storage for GasMeteredStorage {
+        fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+            let value = self.storage.get(key);
+            let len = key.len() + value.as_ref().map_or(0, |v| v.len());
+            self.charge(READ_COST_FLAT + READ_COST_PER_BYTE * len as u64);
+            value
+        }
+
+        fn set(&mut self, key: &[u8], value: &[u8]) {
+            self.charge(WRITE_COST_FLAT + WRITE_COST_PER_BYTE * (key.len() + value.len()) as u64);
+            self.storage.set(key, value);
+        }
+
+        fn remove(&mut self, key: &[u8]) {
+            self.charge(DELETE_COST);
+            self.storage.remove(key);
+        }
+    }
+
+    fn gas_metered_dependencies() -> OwnedDeps<GasMeteredStorage, MockApi, MockQuerier> {
+        OwnedDeps {
+            storage: GasMeteredStorage::default(),
+            api: MockApi::default(),
+            querier: MockQuerier::new(&[]),
+            custom_query_type: PhantomData,
+        }
+    }
 
-        let env = mock_env();
-        let init_config: InstantiateConfig = from_binary(&Binary::from(
-            format!(
-                "{{\"public_token_supply\":{},
-                \"public_owner\":{},
-                \"enable_sealed_metadata\":{},
-                \"unwrapped_metadata_is_private\":{},
-                \"minter_may_update_metadata\":{},
-                \"owner_may_update_metadata\":{},
-                \"enable_burn\":{},
-                \"minter_may_put_token_storage\":{},
-                \"minter_may_put_global_storage\":{}}}",
-                public_token_supply,
-                public_owner,
-                enable_sealed_metadata,
-                unwrapped_metadata_is_private,
-                minter_may_update_metadata,
-                owner_may_update_metadata,
-                enable_burn,
-                minter_may_put_token_storage,
-                minter_may_put_global_storage,
-            )
-            .as_bytes(),
-        ))
-        .unwrap();
-        let info = mock_info("instantiator", &[]);
+    const SETUP: [u8; BOARD_SIZE] = [
+        0,0,0,0,0,2,2,2,2,2,
+        0,0,0,0,0,0,0,0,0,3,
+        0,0,0,0,0,0,0,0,0,3,
+        0,0,0,0,0,0,0,0,0,3,
+        0,0,0,0,0,0,0,0,0,3,
+        4,4,4,0,0,0,0,0,0,0,
+        0,0,0,0,0,0,0,0,0,0,
+        0,0,5,0,0,0,0,0,0,0,
+        0,0,5,0,0,0,0,0,0,0,
+        0,0,5,0,0,0,0,0,6,6,
+    ];
+
+    fn ship_cells() -> Vec<u8> {
+        SETUP
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| **value != 0)
+            .map(|(i, _)| i as u8)
+            .collect()
+    }
+
+    fn uscrt(amount: u128) -> Vec<Coin> {
+        match amount {
+            0 => vec![],
+            _ => vec![Coin { denom: "uscrt".to_string(), amount: Uint128::from(amount) }],
+        }
+    }
+
+    /// instantiates the contract, mints "NFT1" to alice and "NFT2" to bob, and
+    /// has them start a game with both setups submitted. returns the game id
+    fn start_game<S: Storage>(deps: &mut OwnedDeps<S, MockApi, MockQuerier>, wager: u128) -> String {
         let init_msg = InstantiateMsg {
             name: "sec821".to_string(),
             symbol: "S821".to_string(),
             admin: Some("admin".to_string()),
             entropy: "We're going to need a bigger boat".to_string(),
             royalty_info: None,
-            config: Some(init_config),
+            config: None,
             post_init_callback: None,
-            template: Some("<svg> [[ @{CONTRACT_ADDR} ]] [[ @{TOKEN_ID} ]] [[ @{CONTRACT_ADDR} ]] [[ @{TOKEN_ID} ]] </svg>".to_string()),
+            template: None,
         };
+        instantiate(deps.as_mut(), mock_env(), mock_info("instantiator", &[]), init_msg).unwrap();
+        for (token_id, owner) in [("NFT1", "alice"), ("NFT2", "bob")] {
+            let execute_msg = ExecuteMsg::MintNft {
+                token_id: Some(token_id.to_string()),
+                owner: Some(owner.to_string()),
+                public_metadata: None,
+                private_metadata: None,
+                royalty_info: None,
+                serial_number: None,
+                transferable: None,
+                memo: None,
+                padding: None,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), execute_msg).unwrap();
+        }
 
-        (instantiate(deps.as_mut(), env, info, init_msg), deps)
+        let execute_msg = ExecuteMsg::NewGame {
+            token_id: "NFT1".to_string(),
+            title: "game 1".to_string(),
+            padding: None,
+        };
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("alice", &uscrt(wager)), execute_msg);
+        let game_id = match from_binary(&exec_result.unwrap().data.unwrap()).unwrap() {
+            ExecuteAnswer::NewGame { game } => game.game_id,
+            _ => panic!("unexpected"),
+        };
+
+        let execute_msg = ExecuteMsg::JoinGame {
+            token_id: "NFT2".to_string(),
+            game_id: game_id.clone(),
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &uscrt(wager)), execute_msg).unwrap();
+
+        for (token_id, sender) in [("NFT1", "alice"), ("NFT2", "bob")] {
+            let execute_msg = ExecuteMsg::SubmitSetup {
+                token_id: token_id.to_string(),
+                game_id: game_id.clone(),
+                cells: SETUP.to_vec(),
+                padding: None,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), execute_msg).unwrap();
+        }
+        game_id
     }
 
-    fn extract_error_msg<T: Any>(error: StdResult<T>) -> String {
-        match error {
-            Ok(_response) => panic!("Expected error, but had Ok response"),
-            Err(err) => match err {
-                StdError::GenericErr { msg, .. } => msg,
-                _ => panic!("Unexpected error result {:?}", err),
-            },
+    fn attack<S: Storage>(
+        deps: &mut OwnedDeps<S, MockApi, MockQuerier>,
+        token_id: &str,
+        sender: &str,
+        game_id: &str,
+        cell: u8,
+    ) -> StdResult<Response> {
+        let execute_msg = ExecuteMsg::AttackCell {
+            token_id: token_id.to_string(),
+            game_id: game_id.to_string(),
+            cell,
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), execute_msg)
+    }
+
+    /// plays the game out with the player whose turn it is sinking every ship, returning
+    /// the response to the winning attack
+    fn win_game<S: Storage>(deps: &mut OwnedDeps<S, MockApi, MockQuerier>, game_id: &str) -> Response {
+        let [first, second] = turn_order(&load_game(&deps.storage, game_id).unwrap());
+        let targets = ship_cells();
+        let misses: Vec<u8> = (0..BOARD_SIZE as u8).filter(|cell| !targets.contains(cell)).collect();
+        for (i, cell) in targets.iter().enumerate() {
+            let response = attack(deps, first.0, first.1, game_id, *cell).unwrap();
+            if i == targets.len() - 1 {
+                return response;
+            }
+            attack(deps, second.0, second.1, game_id, misses[i]).unwrap();
         }
+        unreachable!()
     }
 
-    fn extract_log(resp: StdResult<Response>) -> String {
-        match resp {
-            Ok(response) => response.attributes[0].value.clone(),
-            Err(_err) => "These are not the logs you are looking for".to_string(),
+    /// (token_id, sender) of the player whose turn it is followed by their opponent
+    fn turn_order(game: &GameRecord) -> [(&'static str, &'static str); 2] {
+        if game.turn == TurnState::InitiatorsTurn as u8 {
+            [("NFT1", "alice"), ("NFT2", "bob")]
+        } else {
+            [("NFT2", "bob"), ("NFT1", "alice")]
+        }
+    }
+
+    fn legacy_away(opponent_home: &[u8]) -> StoredAway {
+        StoredAway {
+            away_values: away_view(opponent_home),
+            carrier_hits: 0,
+            battleship_hits: 0,
+            cruiser_hits: 0,
+            submarine_hits: 0,
+            destroyer_hits: 0,
         }
     }
 
+    /// copies a game record into the stores used before game records existed
+    fn save_as_legacy_game(storage: &mut dyn Storage, game_id: &str, game: &GameRecord) {
+        let joiner = game.joiner.clone().unwrap();
+        let suffix = game_id.as_bytes();
+        legacy_listed_games_store().insert(
+            storage,
+            &game_id.to_string(),
+            &StoredListedGame {
+                title: game.title.clone(),
+                wager: game.wager,
+                created: game.created,
+                initiator_token_id: game.initiator.token_id.clone(),
+                initiator_owner: game.initiator.owner.clone(),
+                initiator_goes_first: game.initiator_goes_first,
+            },
+        ).unwrap();
+        LEGACY_TURN_STATE_STORE.add_suffix(suffix).save(storage, &game.turn).unwrap();
+        LEGACY_JOINER_TOKEN_STORE.add_suffix(suffix).save(storage, &joiner.token_id).unwrap();
+        LEGACY_JOINER_OWNER_STORE.add_suffix(suffix).save(storage, &joiner.owner).unwrap();
+        LEGACY_INITIATOR_HOME_STORE.add_suffix(suffix).save(storage, game.initiator.home.as_ref().unwrap()).unwrap();
+        LEGACY_JOINER_HOME_STORE.add_suffix(suffix).save(storage, joiner.home.as_ref().unwrap()).unwrap();
+        let initiator_away = legacy_away(joiner.home.as_ref().unwrap());
+        LEGACY_INITIATOR_AWAY_STORE.add_suffix(suffix).save(storage, &initiator_away).unwrap();
+        let joiner_away = legacy_away(game.initiator.home.as_ref().unwrap());
+        LEGACY_JOINER_AWAY_STORE.add_suffix(suffix).save(storage, &joiner_away).unwrap();
+        LEGACY_LAST_MOVE_TIME_STORE.add_suffix(suffix).save(storage, &game.last_move_time).unwrap();
+    }
+
     #[test]
     fn test_new_game() {
         let (init_result, mut deps) =
@@ -1799,4 +2521,790 @@ mod tests {
         */
 
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_play_game() {
+        let mut deps = mock_dependencies();
+        let game_id = start_game(&mut deps, 1000000);
+        let [first, second] = turn_order(&load_game(&deps.storage, &game_id).unwrap());
+        let targets = ship_cells();
+        let execute_msg = ExecuteMsg::SetViewingKey { key: "key".to_string(), padding: None };
+        execute(deps.as_mut(), mock_env(), mock_info(first.1, &[]), execute_msg).unwrap();
+        let viewer = ViewerInfo { address: first.1.to_string(), viewing_key: "key".to_string() };
+
+        // the dashboard summarizes the game without a GameState query
+        let query_msg = QueryMsg::ActiveGames { token_id: first.0.to_string(), viewer: viewer.clone() };
+        match from_binary(&query(deps.as_ref(), mock_env(), query_msg).unwrap()).unwrap() {
+            QueryAnswer::ActiveGames { games } => {
+                assert_eq!(games.len(), 1);
+                assert_eq!(games[0].opponent_token_id, Some(second.0.to_string()));
+                assert_eq!(games[0].turn, load_game(&deps.storage, &game_id).unwrap().turn);
+                assert_eq!(games[0].deadline, mock_env().block.time.seconds() + TIMEOUT_SEC);
+            }
+            _ => panic!("unexpected"),
+        }
+
+        // attacking out of turn is rejected
+        let exec_result = attack(&mut deps, second.0, second.1, &game_id, targets[0]);
+        assert_eq!(extract_error_msg(exec_result), "Not your turn to attack");
+
+        // first player sinks every ship while second player only misses
+        let misses: Vec<u8> = (0..BOARD_SIZE as u8).filter(|cell| !targets.contains(cell)).collect();
+        let mut last_result = None;
+        for (i, cell) in targets.iter().enumerate() {
+            last_result = Some(attack(&mut deps, first.0, first.1, &game_id, *cell).unwrap());
+            if i < targets.len() - 1 {
+                attack(&mut deps, second.0, second.1, &game_id, misses[i]).unwrap();
+            }
+        }
+
+        let game = load_game(&deps.storage, &game_id).unwrap();
+        let won = if first.0 == "NFT1" { TurnState::GameOverInitiatorWon } else { TurnState::GameOverJoinerWon };
+        assert_eq!(game.turn, won as u8);
+        assert!(!listed_games_store().contains(&deps.storage, &game_id));
+        assert!(!ACTIVE_GAMES_STORE.add_suffix(b"NFT1").contains(&deps.storage, &game_id));
+        assert!(!ACTIVE_GAMES_STORE.add_suffix(b"NFT2").contains(&deps.storage, &game_id));
+
+        let response = last_result.unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: first.1.to_string(),
+                amount: uscrt(1000000),
+            })
+        );
+
+        // the game shows up in both owners' histories, mints and other games filtered out
+        let winner_raw = deps.api.addr_canonicalize(first.1).unwrap();
+        let game_filter = [TxActionFilter::GameStarted, TxActionFilter::GameWon, TxActionFilter::WagerPaid];
+        let (txs, total) = get_txs(&deps.api, &deps.storage, &winner_raw, 0, 10, Some(&game_filter)).unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(
+            txs.into_iter().map(|tx| tx.action).collect::<Vec<TxAction>>(),
+            vec![
+                TxAction::WagerPaid {
+                    game_id: game_id.clone(),
+                    owner: Addr::unchecked(first.1),
+                    amount: Uint128::from(1000000_u128),
+                },
+                TxAction::GameWon {
+                    game_id: game_id.clone(),
+                    owner: Addr::unchecked(first.1),
+                    opponent_token_id: second.0.to_string(),
+                },
+                TxAction::GameStarted {
+                    game_id: game_id.clone(),
+                    owner: Addr::unchecked(first.1),
+                    opponent_token_id: second.0.to_string(),
+                    wager: Uint128::from(1000000_u128),
+                },
+            ]
+        );
+        // pages run across the types, and duplicate types are only counted once
+        let filter = [TxActionFilter::WagerPaid, TxActionFilter::GameStarted, TxActionFilter::WagerPaid];
+        let (txs, total) = get_txs(&deps.api, &deps.storage, &winner_raw, 1, 1, Some(&filter)).unwrap();
+        assert_eq!(total, 2);
+        assert!(matches!(txs[..], [Tx { action: TxAction::GameStarted { .. }, .. }]));
+        let loser_raw = deps.api.addr_canonicalize(second.1).unwrap();
+        let (txs, _) = get_txs(&deps.api, &deps.storage, &loser_raw, 0, 1, Some(&[TxActionFilter::GameLost])).unwrap();
+        assert!(matches!(txs[0].action, TxAction::GameLost { .. }));
+
+        // txs recorded before the type index existed are still found, after the indexed ones.
+        // here the index starts at the wager payout, so the game start and win are unindexed
+        let (indexed_txs, _) = get_txs(&deps.api, &deps.storage, &winner_raw, 0, 10, Some(&game_filter)).unwrap();
+        let start = TX_ID_STORE
+            .add_suffix(winner_raw.as_slice())
+            .iter(&deps.storage)
+            .unwrap()
+            .position(|id| id.unwrap() == indexed_txs[0].tx_id)
+            .unwrap() as u32;
+        TX_ID_BY_TYPE_START_STORE.add_suffix(winner_raw.as_slice()).save(&mut deps.storage, &start).unwrap();
+        for kind in [TxActionFilter::GameStarted, TxActionFilter::GameWon] {
+            let type_store = TX_ID_BY_TYPE_STORE.add_suffix(winner_raw.as_slice()).add_suffix(&[kind as u8]);
+            type_store.pop(&mut deps.storage).unwrap();
+            assert!(type_store.is_empty(&deps.storage).unwrap());
+        }
+        let (txs, total) = get_txs(&deps.api, &deps.storage, &winner_raw, 0, 10, Some(&game_filter)).unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(txs, indexed_txs);
+        let (txs, total) = get_txs(&deps.api, &deps.storage, &winner_raw, 1, 1, Some(&filter)).unwrap();
+        assert_eq!(total, 2);
+        assert!(matches!(txs[..], [Tx { action: TxAction::GameStarted { .. }, .. }]));
+
+        // both tokens keep the game in their history
+        let query_msg = QueryMsg::FinishedGames {
+            page_size: None,
+            page: None,
+            token_id: first.0.to_string(),
+            viewer,
+        };
+        let query_answer = from_binary(&query(deps.as_ref(), mock_env(), query_msg).unwrap()).unwrap();
+        match query_answer {
+            QueryAnswer::FinishedGames { games, total } => {
+                assert_eq!(total, 1);
+                assert_eq!(games[0].game_id, game_id);
+                assert_eq!(games[0].outcome, GameOutcome::Won as u8);
+                assert_eq!(games[0].opponent_token_id, Some(second.0.to_string()));
+                assert_eq!(games[0].payout.amount, Uint128::from(1000000_u128));
+            }
+            _ => panic!("unexpected"),
+        }
+        let history = FINISHED_GAMES_STORE.add_suffix(second.0.as_bytes());
+        assert_eq!(history.get_at(&deps.storage, 0).unwrap().outcome, GameOutcome::Lost as u8);
+    }
+
+    #[test]
+    fn test_transfer_mid_game() {
+        let mut deps = mock_dependencies();
+        let game_id = start_game(&mut deps, 0);
+        let [first, second] = turn_order(&load_game(&deps.storage, &game_id).unwrap());
+        let targets = ship_cells();
+
+        // the first player's token changes hands before the game is decided
+        let execute_msg = ExecuteMsg::TransferNft {
+            recipient: "carol".to_string(),
+            token_id: first.0.to_string(),
+            memo: None,
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(first.1, &[]), execute_msg).unwrap();
+        let game = load_game(&deps.storage, &game_id).unwrap();
+        let carol_raw = deps.api.addr_canonicalize("carol").unwrap();
+        let first_player = if game.initiator.token_id == first.0 { &game.initiator } else { game.joiner.as_ref().unwrap() };
+        assert_eq!(first_player.owner, carol_raw);
+
+        // the previous owner can no longer play the token, but the new owner can
+        let exec_result = attack(&mut deps, first.0, first.1, &game_id, targets[0]);
+        assert!(exec_result.is_err());
+        attack(&mut deps, first.0, "carol", &game_id, targets[0]).unwrap();
+        attack(&mut deps, second.0, second.1, &game_id, targets[0]).unwrap();
+    }
+
+    #[test]
+    fn test_wager_lock() {
+        let mut deps = mock_dependencies();
+        let game_id = start_game(&mut deps, 1000000);
+        let transfer = || ExecuteMsg::TransferNft {
+            recipient: "carol".to_string(),
+            token_id: "NFT1".to_string(),
+            memo: None,
+            padding: None,
+        };
+
+        // a token in a wagered game cannot change hands
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), transfer());
+        let error = extract_error_msg(exec_result);
+        assert!(error.contains("locked in wagered games"));
+        assert!(error.contains(&game_id));
+
+        let unlock = || ExecuteMsg::ForceUnlockGame { game_id: game_id.clone(), padding: None };
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), unlock());
+        assert!(extract_error_msg(exec_result).contains("admin command"));
+
+        // aborting the game refunds both wagers and releases the tokens
+        let response = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), unlock()).unwrap();
+        let refunds: Vec<CosmosMsg> = response.messages.into_iter().map(|msg| msg.msg).collect();
+        assert_eq!(
+            refunds,
+            vec![
+                CosmosMsg::Bank(BankMsg::Send { to_address: "alice".to_string(), amount: uscrt(1000000) }),
+                CosmosMsg::Bank(BankMsg::Send { to_address: "bob".to_string(), amount: uscrt(1000000) }),
+            ]
+        );
+        assert_eq!(load_game(&deps.storage, &game_id).unwrap().turn, TurnState::GameAborted as u8);
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), unlock());
+        assert_eq!(extract_error_msg(exec_result), "Game is already over");
+
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), transfer()).unwrap();
+    }
+
+    #[test]
+    fn test_emergency_refund_games() {
+        let mut deps = mock_dependencies();
+        let game_id = start_game(&mut deps, 1000000);
+        let [first, _] = turn_order(&load_game(&deps.storage, &game_id).unwrap());
+        let refund = || ExecuteMsg::EmergencyRefundGames { limit: None, padding: None };
+
+        // more games than a single call can abort
+        for i in 0..MAX_EMERGENCY_REFUNDS {
+            let mut env = mock_env();
+            env.block.random = Some(Binary::from(vec![i as u8; 32]));
+            let execute_msg = ExecuteMsg::NewGame {
+                token_id: "NFT1".to_string(),
+                title: format!("game {}", i + 2),
+                padding: None,
+            };
+            execute(deps.as_mut(), env, mock_info("alice", &[]), execute_msg).unwrap();
+        }
+
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), refund());
+        assert!(extract_error_msg(exec_result).contains("only available while the contract is stopped"));
+
+        let execute_msg = ExecuteMsg::SetContractStatus {
+            level: ContractStatus::StopTransactions,
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), execute_msg).unwrap();
+
+        // games are frozen while the contract is stopped
+        let exec_result = attack(&mut deps, first.0, first.1, &game_id, 0);
+        assert!(extract_error_msg(exec_result).contains("temporarily disabled"));
+
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), refund());
+        assert!(extract_error_msg(exec_result).contains("admin command"));
+
+        // the limit is capped
+        let execute_msg = ExecuteMsg::EmergencyRefundGames { limit: Some(u32::MAX), padding: None };
+        let response = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), execute_msg).unwrap();
+        match from_binary(response.data.as_ref().unwrap()).unwrap() {
+            ExecuteAnswer::EmergencyRefundGames { refunded, remaining } => {
+                assert_eq!(refunded, MAX_EMERGENCY_REFUNDS);
+                assert_eq!(remaining, 1);
+            }
+            _ => panic!("unexpected"),
+        }
+        // only the first game had wagers to refund
+        assert_eq!(response.messages.len(), 2);
+
+        let response = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), refund()).unwrap();
+        match from_binary(response.data.as_ref().unwrap()).unwrap() {
+            ExecuteAnswer::EmergencyRefundGames { refunded, remaining } => {
+                assert_eq!(refunded, 1);
+                assert_eq!(remaining, 0);
+            }
+            _ => panic!("unexpected"),
+        }
+        assert_eq!(load_game(&deps.storage, &game_id).unwrap().turn, TurnState::GameAborted as u8);
+        assert!(!ACTIVE_GAMES_STORE.add_suffix(b"NFT1").contains(&deps.storage, &game_id));
+    }
+
+    #[test]
+    fn test_season() {
+        let mut deps = mock_dependencies();
+        let game_id = start_game(&mut deps, 1000000);
+        let [first, second] = turn_order(&load_game(&deps.storage, &game_id).unwrap());
+        let now = mock_env().block.time.seconds();
+        let create = |start: u64, end: u64| ExecuteMsg::CreateSeason {
+            name: "season 1".to_string(),
+            start,
+            end,
+            fee_percent: 50,
+            prize_split: vec![100],
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), create(now, now + 100)).unwrap();
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), create(now + 50, now + 200));
+        assert!(extract_error_msg(exec_result).contains("before the previous season ends"));
+        let execute_msg = ExecuteMsg::CreateSeason {
+            name: "season 2".to_string(),
+            start: now + 100,
+            end: now + 200,
+            fee_percent: 50,
+            prize_split: vec![1; LEADERBOARD_SIZE + 1],
+            padding: None,
+        };
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), execute_msg);
+        assert!(extract_error_msg(exec_result).contains("can not pay more than 50 places"));
+
+        // half of the house fee stays in the prize pool
+        let response = win_game(&mut deps, &game_id);
+        assert_eq!(
+            response.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send { to_address: "admin".to_string(), amount: uscrt(500000) })
+        );
+
+        let close = || ExecuteMsg::CloseSeason { season_id: 0, padding: None };
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), close());
+        assert_eq!(extract_error_msg(exec_result), "Season has not ended yet");
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let response = execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), close()).unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send { to_address: first.1.to_string(), amount: uscrt(500000) })
+        );
+        let exec_result = execute(deps.as_mut(), env, mock_info("admin", &[]), close());
+        assert_eq!(extract_error_msg(exec_result), "Season is already closed");
+
+        // the final standings stay queryable
+        let query_msg = QueryMsg::SeasonStandings { season_id: 0, page_size: None, page: None };
+        match from_binary(&query(deps.as_ref(), mock_env(), query_msg).unwrap()).unwrap() {
+            QueryAnswer::SeasonStandings { season, standings, total } => {
+                assert!(season.closed);
+                assert_eq!(total, 2);
+                assert_eq!(standings[0].token_id, first.0);
+                assert_eq!(standings[0].points, WIN_POINTS);
+                assert_eq!(standings[0].prize, Uint128::from(500000_u128));
+                assert_eq!(standings[1].token_id, second.0);
+                assert_eq!(standings[1].points, LOSS_POINTS);
+                assert!(standings[1].prize.is_zero());
+            }
+            _ => panic!("unexpected"),
+        }
+    }
+
+    #[test]
+    fn test_season_burned_winner() {
+        let mut deps = mock_dependencies();
+        let game_id = start_game(&mut deps, 1000000);
+        let [first, _] = turn_order(&load_game(&deps.storage, &game_id).unwrap());
+        let now = mock_env().block.time.seconds();
+        let execute_msg = ExecuteMsg::CreateSeason {
+            name: "season 1".to_string(),
+            start: now,
+            end: now + 100,
+            fee_percent: 50,
+            prize_split: vec![100],
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), execute_msg).unwrap();
+        win_game(&mut deps, &game_id);
+
+        // this contract has no burn message, so remove the winner the way a SNIP-721 burn does
+        let (_, idx) = get_token(&deps.storage, first.0, None).unwrap();
+        PrefixedStorage::new(&mut deps.storage, PREFIX_MAP_TO_INDEX).remove(first.0.as_bytes());
+        PrefixedStorage::new(&mut deps.storage, PREFIX_INFOS).remove(&idx.to_le_bytes());
+
+        // the burned winner's prize goes back to the admin
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let execute_msg = ExecuteMsg::CloseSeason { season_id: 0, padding: None };
+        let response = execute(deps.as_mut(), env, mock_info("admin", &[]), execute_msg).unwrap();
+        assert_eq!(response.messages.len(), 1);
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send { to_address: "admin".to_string(), amount: uscrt(500000) })
+        );
+    }
+
+    #[test]
+    fn test_achievement_badges() {
+        let mut deps = mock_dependencies();
+        let game_id = start_game(&mut deps, 10000000);
+        let [first, _] = turn_order(&load_game(&deps.storage, &game_id).unwrap());
+
+        let remove = || ExecuteMsg::RemoveAchievement {
+            achievement_id: "ten-wins".to_string(),
+            padding: None,
+        };
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), remove());
+        assert!(extract_error_msg(exec_result).contains("admin command"));
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), remove()).unwrap();
+
+        // the winner never misses, so this is a perfect game at the top wager
+        win_game(&mut deps, &game_id);
+        for sender in [first.1, "charlie"] {
+            let execute_msg = ExecuteMsg::SetViewingKey { key: "key".to_string(), padding: None };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), execute_msg).unwrap();
+        }
+        let token_achievements = |token_id: &str, address: &str| QueryMsg::TokenAchievements {
+            token_id: token_id.to_string(),
+            viewer: ViewerInfo { address: address.to_string(), viewing_key: "key".to_string() },
+        };
+        // only the owner and their delegates see a token's achievements, and nobody learns
+        // whether a token exists while the supply is private
+        let query_result = query(deps.as_ref(), mock_env(), token_achievements(first.0, "charlie"));
+        assert_eq!(extract_error_msg(query_result), "Unauthorized");
+        let query_result = query(deps.as_ref(), mock_env(), token_achievements("NFT9", first.1));
+        assert_eq!(extract_error_msg(query_result), "Unauthorized");
+        let query_msg = token_achievements(first.0, first.1);
+        let unlocked = match from_binary(&query(deps.as_ref(), mock_env(), query_msg).unwrap()).unwrap() {
+            QueryAnswer::TokenAchievements { wins, unlocked } => {
+                assert_eq!(wins, 1);
+                unlocked
+            }
+            _ => panic!("unexpected"),
+        };
+        let mut unlocked_ids: Vec<&str> = unlocked.iter().map(|u| u.achievement_id.as_str()).collect();
+        unlocked_ids.sort();
+        assert_eq!(unlocked_ids, vec!["first-win", "high-roller", "perfect-game"]);
+
+        // badges go to the owner of the winning token and can not be transferred
+        let badge_token_id = unlocked[0].badge_token_id.clone();
+        let (token, _) = get_token(&deps.storage, &badge_token_id, None).unwrap();
+        assert_eq!(token.owner, deps.api.addr_canonicalize(first.1).unwrap());
+        assert!(!token.transferable);
+        let execute_msg = ExecuteMsg::TransferNft {
+            recipient: "charlie".to_string(),
+            token_id: badge_token_id.clone(),
+            memo: None,
+            padding: None,
+        };
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info(first.1, &[]), execute_msg);
+        assert!(extract_error_msg(exec_result).contains("non-transferable"));
+
+        // badges can not open games
+        let new_game = |token_id: &str| ExecuteMsg::NewGame {
+            token_id: token_id.to_string(),
+            title: "badge game".to_string(),
+            padding: None,
+        };
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info(first.1, &[]), new_game(&badge_token_id));
+        assert!(extract_error_msg(exec_result).contains("badges can not play"));
+        let execute_msg = ExecuteMsg::SubscribeLobby {
+            token_id: badge_token_id.clone(),
+            tiers: vec![Uint128::zero()],
+            padding: None,
+        };
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info(first.1, &[]), execute_msg);
+        assert!(extract_error_msg(exec_result).contains("badges can not play"));
+
+        // a badge subtype in the metadata does not make a token a badge
+        let execute_msg = ExecuteMsg::MintNft {
+            token_id: Some("NFT3".to_string()),
+            owner: Some("charlie".to_string()),
+            public_metadata: Some(Metadata {
+                token_uri: None,
+                extension: Some(Extension {
+                    token_subtype: Some("badge".to_string()),
+                    ..Extension::default()
+                }),
+            }),
+            private_metadata: None,
+            royalty_info: None,
+            serial_number: None,
+            transferable: None,
+            memo: None,
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), execute_msg).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("charlie", &[]), new_game("NFT3")).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_legacy_game() {
+        let mut deps = mock_dependencies();
+        let game_id = start_game(&mut deps, 0);
+        let mut game = load_game(&deps.storage, &game_id).unwrap();
+        // the legacy stores did not keep notification sequence numbers
+        game.initiator.seq = 0;
+        game.joiner.as_mut().unwrap().seq = 0;
+
+        // move the game back into the legacy stores
+        GAME_RECORD_STORE.add_suffix(game_id.as_bytes()).remove(&mut deps.storage);
+        listed_games_store().remove(&mut deps.storage, &game_id).unwrap();
+        save_as_legacy_game(&mut deps.storage, &game_id, &game);
+
+        // legacy games can still be read before they are migrated
+        assert_eq!(load_game(&deps.storage, &game_id).unwrap(), game);
+
+        let execute_msg = ExecuteMsg::MigrateGames { limit: None, padding: None };
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), execute_msg);
+        assert!(extract_error_msg(exec_result).contains("admin command"));
+
+        let execute_msg = ExecuteMsg::MigrateGames { limit: None, padding: None };
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), execute_msg);
+        match from_binary(&exec_result.unwrap().data.unwrap()).unwrap() {
+            ExecuteAnswer::MigrateGames { migrated, remaining } => {
+                assert_eq!(migrated, 1);
+                assert_eq!(remaining, 0);
+            }
+            _ => panic!("unexpected"),
+        }
+
+        assert_eq!(GAME_RECORD_STORE.add_suffix(game_id.as_bytes()).load(&deps.storage).unwrap(), game);
+        assert!(listed_games_store().contains(&deps.storage, &game_id));
+        assert!(LEGACY_TURN_STATE_STORE.add_suffix(game_id.as_bytes()).may_load(&deps.storage).unwrap().is_none());
+        assert!(LEGACY_INITIATOR_HOME_STORE.add_suffix(game_id.as_bytes()).may_load(&deps.storage).unwrap().is_none());
+
+        // the game can be played on after migrating
+        let [first, _] = turn_order(&game);
+        attack(&mut deps, first.0, first.1, &game_id, 0).unwrap();
+    }
+
+    #[test]
+    fn test_legacy_listed_games() {
+        let mut deps = mock_dependencies();
+        let game_id = start_game(&mut deps, 0);
+        let game = load_game(&deps.storage, &game_id).unwrap();
+        let legacy_id = "legacy".to_string();
+        save_as_legacy_game(&mut deps.storage, &legacy_id, &game);
+        for token_id in ["NFT1", "NFT2"] {
+            ACTIVE_GAMES_STORE.add_suffix(token_id.as_bytes()).insert(&mut deps.storage, &legacy_id).unwrap();
+        }
+
+        // games that have not been migrated are listed after the migrated ones
+        let alice_raw = deps.api.addr_canonicalize("alice").unwrap();
+        let list_page = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, page: u32| {
+            let list = query_list_games(deps.as_ref(), "NFT1".to_string(), Some(page), Some(1), &alice_raw).unwrap();
+            match from_binary(&list).unwrap() {
+                QueryAnswer::ListGames { games } => games.into_iter().map(|game| game.game_id).collect::<Vec<String>>(),
+                _ => panic!("unexpected"),
+            }
+        };
+        assert_eq!(list_page(&deps, 0), vec![game_id.clone()]);
+        assert_eq!(list_page(&deps, 1), vec![legacy_id.clone()]);
+        assert!(list_page(&deps, 2).is_empty());
+
+        // and are migrated as they are refunded
+        let execute_msg = ExecuteMsg::SetContractStatus {
+            level: ContractStatus::StopTransactions,
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), execute_msg).unwrap();
+        let refund = || ExecuteMsg::EmergencyRefundGames { limit: Some(1), padding: None };
+        for expected_remaining in [1, 0] {
+            let response = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), refund()).unwrap();
+            match from_binary(response.data.as_ref().unwrap()).unwrap() {
+                ExecuteAnswer::EmergencyRefundGames { refunded, remaining } => {
+                    assert_eq!(refunded, 1);
+                    assert_eq!(remaining, expected_remaining);
+                }
+                _ => panic!("unexpected"),
+            }
+        }
+        assert!(legacy_listed_games_store().is_empty(&deps.storage).unwrap());
+        assert_eq!(load_game(&deps.storage, &legacy_id).unwrap().turn, TurnState::GameAborted as u8);
+    }
+
+    #[test]
+    fn test_game_record_gas() {
+        let mut deps = gas_metered_dependencies();
+        let game_id = start_game(&mut deps, 0);
+        let game = load_game(&deps.storage, &game_id).unwrap();
+        let [first, _] = turn_order(&game);
+        // the same game as it would be stored before game records
+        let legacy_id = "legacy".to_string();
+        save_as_legacy_game(&mut deps.storage, &legacy_id, &game);
+
+        // before: a move in a game that is still in the legacy stores
+        deps.storage.take_gas();
+        attack(&mut deps, first.0, first.1, &legacy_id, 0).unwrap();
+        let legacy_gas = deps.storage.take_gas();
+
+        // after: the same move in a game record
+        attack(&mut deps, first.0, first.1, &game_id, 0).unwrap();
+        let record_gas = deps.storage.take_gas();
+        assert!(
+            record_gas < legacy_gas,
+            "game record move used {} gas, legacy move {}", record_gas, legacy_gas
+        );
+    }
+
+    #[test]
+    fn test_player_notifications() {
+        let mut deps = mock_dependencies();
+        let game_id = start_game(&mut deps, 0);
+        let [first, second] = turn_order(&load_game(&deps.storage, &game_id).unwrap());
+        let player_joined = PLAYER_JOINED_CHANNEL_ID.to_string();
+        let opponent_attacked = OPPONENT_ATTACKED_CHANNEL_ID.to_string();
+        let alice_raw = deps.api.addr_canonicalize("alice").unwrap();
+        let bob_raw = deps.api.addr_canonicalize("bob").unwrap();
+
+        // only the initiator hears that someone joined
+        assert_eq!(get_count(&deps.storage, &player_joined, &alice_raw), 1);
+        assert_eq!(get_count(&deps.storage, &player_joined, &bob_raw), 0);
+
+        // the attacked player hears which cell was attacked
+        let second_raw = deps.api.addr_canonicalize(second.1).unwrap();
+        let id = notification_id(&deps.storage, &second_raw, &opponent_attacked).unwrap();
+        let response = attack(&mut deps, first.0, first.1, &game_id, 0).unwrap();
+        assert!(response.attributes.iter().any(|attr| attr.key == id.to_base64()));
+        assert_eq!(get_count(&deps.storage, &opponent_attacked, &second_raw), 1);
+        let first_raw = deps.api.addr_canonicalize(first.1).unwrap();
+        assert_eq!(get_count(&deps.storage, &opponent_attacked, &first_raw), 0);
+    }
+
+    #[test]
+    fn test_notification_delegates() {
+        let mut deps = mock_dependencies();
+        let game_id = start_game(&mut deps, 0);
+        let [first, second] = turn_order(&load_game(&deps.storage, &game_id).unwrap());
+        // alice's opponent attacks her whenever it is their turn
+        let (attacker, alice_token) = if first.1 == "alice" { (second, first.0) } else { (first, second.0) };
+        if first.1 == "alice" {
+            attack(&mut deps, alice_token, "alice", &game_id, 90).unwrap();
+        }
+        let opponent_attacked = OPPONENT_ATTACKED_CHANNEL_ID.to_string();
+        let set_delegates = |delegates: &[&str]| ExecuteMsg::SetNotificationDelegates {
+            token_id: alice_token.to_string(),
+            delegates: delegates.iter().map(|delegate| delegate.to_string()).collect(),
+            padding: None,
+        };
+        let approve_owner_delegate = |address: &str| ExecuteMsg::ApproveOwnerDelegate {
+            address: address.to_string(),
+            padding: None,
+        };
+        let notified = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, address: &str| {
+            get_count(&deps.storage, &opponent_attacked, &deps.api.addr_canonicalize(address).unwrap())
+        };
+
+        let execute_msg = ExecuteMsg::ApproveTokenDelegate {
+            address: "carol".to_string(),
+            token_ids: vec![alice_token.to_string()],
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), execute_msg).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), approve_owner_delegate("dave")).unwrap();
+        let execute_msg = ExecuteMsg::ApproveTokenDelegate {
+            address: "dave".to_string(),
+            token_ids: vec![alice_token.to_string()],
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), execute_msg).unwrap();
+
+        // only the owner may set the delegates, and only to their delegates
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), set_delegates(&["carol"]));
+        assert_eq!(extract_error_msg(exec_result), "Unauthorized");
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), set_delegates(&["erin"]));
+        assert!(extract_error_msg(exec_result).contains("is not a delegate"));
+        for address in ["delegate1", "delegate2", "delegate3"] {
+            execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), approve_owner_delegate(address)).unwrap();
+        }
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), set_delegates(&["dave", "delegate1", "delegate2", "delegate3"]));
+        assert!(extract_error_msg(exec_result).contains("at most 3"));
+        let response = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), set_delegates(&["carol", "dave", "carol"])).unwrap();
+        match from_binary(&response.data.unwrap()).unwrap() {
+            ExecuteAnswer::SetNotificationDelegates { delegates } => {
+                assert_eq!(delegates, vec![Addr::unchecked("carol"), Addr::unchecked("dave")]);
+            }
+            _ => panic!("unexpected"),
+        }
+
+        // the delegates hear about the attack along with the owner
+        attack(&mut deps, attacker.0, attacker.1, &game_id, 0).unwrap();
+        assert_eq!(notified(&deps, "alice"), 1);
+        assert_eq!(notified(&deps, "carol"), 1);
+        assert_eq!(notified(&deps, "dave"), 1);
+
+        // a revoked delegate stops hearing about it
+        let execute_msg = ExecuteMsg::RevokeDelegate {
+            address: "dave".to_string(),
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), execute_msg).unwrap();
+        attack(&mut deps, alice_token, "alice", &game_id, 91).unwrap();
+        attack(&mut deps, attacker.0, attacker.1, &game_id, 1).unwrap();
+        assert_eq!(notified(&deps, "carol"), 2);
+        assert_eq!(notified(&deps, "dave"), 1);
+
+        // delegates are cleared when the token changes hands
+        let execute_msg = ExecuteMsg::TransferNft {
+            recipient: "frank".to_string(),
+            token_id: alice_token.to_string(),
+            memo: None,
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), execute_msg).unwrap();
+        attack(&mut deps, alice_token, "frank", &game_id, 92).unwrap();
+        attack(&mut deps, attacker.0, attacker.1, &game_id, 2).unwrap();
+        assert_eq!(notified(&deps, "frank"), 1);
+        assert_eq!(notified(&deps, "carol"), 2);
+    }
+
+    #[test]
+    fn test_lobby_subscriptions() {
+        let mut deps = mock_dependencies();
+        start_game(&mut deps, 0);
+        let subscribe = |token_id: &str, tiers: &[u128]| ExecuteMsg::SubscribeLobby {
+            token_id: token_id.to_string(),
+            tiers: tiers.iter().map(|wager| Uint128::from(*wager)).collect(),
+            padding: None,
+        };
+        let new_game = || ExecuteMsg::NewGame {
+            token_id: "NFT1".to_string(),
+            title: "game 2".to_string(),
+            padding: None,
+        };
+        // game ids come from the block's randomness, so each new game needs its own
+        let env_with_random = |seed: u8| {
+            let mut env = mock_env();
+            env.block.random = Some(Binary::from(vec![seed; 32]));
+            env
+        };
+        let game_listed = GAME_LISTED_CHANNEL_ID.to_string();
+        let alice_raw = deps.api.addr_canonicalize("alice").unwrap();
+        let bob_raw = deps.api.addr_canonicalize("bob").unwrap();
+
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), subscribe("NFT2", &[3]));
+        assert_eq!(extract_error_msg(exec_result), "Invalid wager amount");
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), subscribe("NFT2", &[1000000, 1000000, 0]));
+        match from_binary(&exec_result.unwrap().data.unwrap()).unwrap() {
+            ExecuteAnswer::SubscribeLobby { tiers, expires } => {
+                assert_eq!(tiers, vec![Uint128::zero(), Uint128::from(1000000_u128)]);
+                assert_eq!(expires, Some(mock_env().block.time.seconds() + LOBBY_SUBSCRIPTION_SEC));
+            }
+            _ => panic!("unexpected"),
+        }
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), subscribe("NFT1", &[1000000])).unwrap();
+
+        // subscribers other than the initiator hear about the new game
+        let id = notification_id(&deps.storage, &bob_raw, &game_listed).unwrap();
+        let response = execute(deps.as_mut(), env_with_random(1), mock_info("alice", &uscrt(1000000)), new_game()).unwrap();
+        assert!(response.attributes.iter().any(|attr| attr.key == id.to_base64()));
+        assert_eq!(get_count(&deps.storage, &game_listed, &bob_raw), 1);
+        assert_eq!(get_count(&deps.storage, &game_listed, &alice_raw), 0);
+
+        // only a batch of the tier is notified of each game, and tokens that no longer exist
+        // are dropped from it along the way
+        // keysets cache their length, so each check loads the tier afresh
+        let tier_store = || LOBBY_SUBSCRIBERS_STORE.add_suffix(&0_u128.to_be_bytes());
+        for i in 0..LOBBY_NOTIFY_BATCH {
+            tier_store().insert(&mut deps.storage, &format!("burned{}", i)).unwrap();
+        }
+        execute(deps.as_mut(), env_with_random(2), mock_info("alice", &[]), new_game()).unwrap();
+        assert_eq!(get_count(&deps.storage, &game_listed, &bob_raw), 2);
+        assert_eq!(tier_store().get_len(&deps.storage).unwrap(), 2);
+        // the next game picks up where the last one left off
+        execute(deps.as_mut(), env_with_random(3), mock_info("alice", &[]), new_game()).unwrap();
+        assert_eq!(get_count(&deps.storage, &game_listed, &bob_raw), 3);
+        assert_eq!(tier_store().get_len(&deps.storage).unwrap(), 1);
+
+        // subscriptions do not pass to a new owner
+        let execute_msg = ExecuteMsg::TransferNft {
+            recipient: "charlie".to_string(),
+            token_id: "NFT2".to_string(),
+            memo: None,
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), execute_msg).unwrap();
+        assert_eq!(tier_store().get_len(&deps.storage).unwrap(), 0);
+        let query_msg = QueryMsg::LobbyTiers {};
+        match from_binary(&query(deps.as_ref(), mock_env(), query_msg).unwrap()).unwrap() {
+            QueryAnswer::LobbyTiers { tiers } => {
+                assert_eq!(tiers.len(), VALID_WAGERS.len());
+                assert!(tiers.iter().all(|tier| tier.subscribers == 0 || tier.wager.amount.u128() == 1000000));
+                assert_eq!(tiers[1].subscribers, 1);
+                assert_eq!(tiers[1].notified_per_game, LOBBY_NOTIFY_BATCH);
+            }
+            _ => panic!("unexpected"),
+        }
+
+        // expired subscriptions are dropped instead of notified
+        let charlie_raw = deps.api.addr_canonicalize("charlie").unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("charlie", &[]), subscribe("NFT2", &[0])).unwrap();
+        let mut env = env_with_random(4);
+        env.block.time = env.block.time.plus_seconds(LOBBY_SUBSCRIPTION_SEC);
+        execute(deps.as_mut(), env, mock_info("alice", &[]), new_game()).unwrap();
+        assert_eq!(get_count(&deps.storage, &game_listed, &charlie_raw), 0);
+        assert_eq!(tier_store().get_len(&deps.storage).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_game_delta_payload() {
+        let game_id = "oRyNqDs1c8J4q3j0Fm1nsTh3qXwVZb3mfzxl0oYj8hE=";
+        let attack = AttackResult {
+            cell: 57,
+            result: CellValue::Hit as u8,
+            sunk: Some(CellValue::Cruiser as u8),
+        };
+        let data = encode_game_delta(game_id, 12, Some(&attack), TurnState::JoinersTurn as u8).unwrap();
+        // leaves most of the 256 byte budget unused, unlike the full board
+        assert!(data.len() < 64, "game_delta payload is {} bytes", data.len());
+
+        let decoded: (u8, String, u32, Option<u8>, Option<u8>, Option<u8>, u8) =
+            minicbor_ser::from_slice(&data).unwrap();
+        assert_eq!(
+            decoded,
+            (1, game_id.to_string(), 12, Some(57), Some(0x80), Some(4), TurnState::JoinersTurn as u8)
+        );
+
+        // events that are not attacks leave the attack fields null
+        let data = encode_game_delta(game_id, 1, None, TurnState::WaitingForBothPlayersSetup as u8).unwrap();
+        let decoded: (u8, String, u32, Option<u8>, Option<u8>, Option<u8>, u8) =
+            minicbor_ser::from_slice(&data).unwrap();
+        assert_eq!(decoded.3, None);
+        assert_eq!(decoded.5, None);
+    }
+}
@@ -0,0 +1,291 @@
+//! SNIP-52 helpers for off-chain clients such as backend services and bots.
+//!
+//! These mirror what the contract does when it renders a notification, without needing
+//! the contract's storage: the seed comes from the `channel_info` query and the counter
+//! is tracked by the client.
+use chacha20poly1305::{
+    aead::{AeadInPlace, KeyInit},
+    ChaCha20Poly1305,
+};
+use hkdf::hmac::Mac;
+use minicbor_ser as cbor;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use crate::snip52_crypto::{decode_hex, HmacSha256};
+
+/// An error decrypting or decoding a notification
+#[derive(Error, Clone, PartialEq, Eq, Debug)]
+pub enum ClientError {
+    #[error("Seed must be 32 bytes")]
+    InvalidSeed,
+    #[error("Transaction hash must be a hex string of at least 12 bytes")]
+    InvalidTxHash,
+    #[error("Notification could not be decrypted")]
+    DecryptionFailed,
+    #[error("Notification data could not be decoded: {0}")]
+    InvalidData(String),
+}
+
+/// Payload of the `game_updated` channel
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct GameUpdated {
+    pub game_id: String,
+    /// the recipient's home board
+    pub home: Vec<u8>,
+    pub turn: u8,
+}
+
+/// Payload of the `game_delta` channel
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct GameDelta {
+    /// payload layout version
+    pub version: u8,
+    pub game_id: String,
+    /// number of game updates sent to the recipient for this game
+    pub seq: u32,
+    pub cell: Option<u8>,
+    pub result: Option<u8>,
+    pub sunk: Option<u8>,
+    pub turn: u8,
+}
+
+/// Returns the id of the notification sent with the given counter on a counter mode channel
+pub fn notification_id(seed: &[u8], channel: &str, counter: u64) -> Result<[u8; 32], ClientError> {
+    hmac_id(seed, &format!("{}:{}", channel, counter))
+}
+
+/// Returns the id of the notification sent by the given transaction on a txhash mode channel
+pub fn txhash_notification_id(seed: &[u8], channel: &str, tx_hash: &str) -> Result<[u8; 32], ClientError> {
+    hmac_id(seed, &format!("{}:{}", channel, tx_hash))
+}
+
+/// Returns the nonce of the notification sent with the given counter on a counter mode channel
+pub fn notification_nonce(channel: &str, counter: u64) -> [u8; 12] {
+    let mut nonce = channel_id_bytes(channel);
+    for (byte, counter_byte) in nonce[4..].iter_mut().zip(counter.to_be_bytes()) {
+        *byte ^= counter_byte;
+    }
+    nonce
+}
+
+/// Returns the nonce of the notification sent by the given transaction on a txhash mode channel
+pub fn txhash_notification_nonce(channel: &str, tx_hash: &str) -> Result<[u8; 12], ClientError> {
+    let tx_hash_bytes = decode_hex(tx_hash).map_err(|_| ClientError::InvalidTxHash)?;
+    if tx_hash_bytes.len() < 12 {
+        return Err(ClientError::InvalidTxHash);
+    }
+    let mut nonce = channel_id_bytes(channel);
+    for (byte, tx_hash_byte) in nonce.iter_mut().zip(tx_hash_bytes) {
+        *byte ^= tx_hash_byte;
+    }
+    Ok(nonce)
+}
+
+/// Returns the additional authenticated data of a counter mode notification, made from the
+/// height of the block and the address that executed the notifying message
+pub fn notification_aad(block_height: u64, sender: &str) -> String {
+    format!("{}:{}", block_height, sender)
+}
+
+/// Returns the additional authenticated data of a txhash mode notification
+pub fn txhash_notification_aad(block_height: u64, tx_hash: &str) -> String {
+    format!("{}:{}", block_height, tx_hash)
+}
+
+/// Returns the zero padded plaintext of an encrypted notification
+pub fn decrypt_notification(
+    seed: &[u8],
+    nonce: &[u8; 12],
+    aad: &str,
+    encrypted_data: &[u8],
+) -> Result<Vec<u8>, ClientError> {
+    let cipher = ChaCha20Poly1305::new_from_slice(seed).map_err(|_| ClientError::InvalidSeed)?;
+    let mut buffer = encrypted_data.to_vec();
+    cipher
+        .decrypt_in_place(nonce.into(), aad.as_bytes(), &mut buffer)
+        .map_err(|_| ClientError::DecryptionFailed)?;
+    Ok(buffer)
+}
+
+/// Decodes the plaintext of a `game_updated` notification
+pub fn decode_game_updated(plaintext: &[u8]) -> Result<GameUpdated, ClientError> {
+    let (game_id, home, turn): (String, Vec<u8>, u8) = decode(plaintext)?;
+    Ok(GameUpdated { game_id, home, turn })
+}
+
+/// Decodes the plaintext of a `game_delta` notification
+pub fn decode_game_delta(plaintext: &[u8]) -> Result<GameDelta, ClientError> {
+    let (version, game_id, seq, cell, result, sunk, turn) = decode(plaintext)?;
+    Ok(GameDelta { version, game_id, seq, cell, result, sunk, turn })
+}
+
+/// `game_delta` payload as it is encoded
+type EncodedGameDelta = (u8, String, u32, Option<u8>, Option<u8>, Option<u8>, u8);
+
+/// Decodes the plaintext of a `game_updated` notification on a txhash mode channel, which
+/// holds every update the transaction sent to the recipient
+pub fn decode_txhash_game_updates(plaintext: &[u8]) -> Result<Vec<GameUpdated>, ClientError> {
+    let updates: Vec<(String, Vec<u8>, u8)> = decode(plaintext)?;
+    Ok(updates.into_iter().map(|(game_id, home, turn)| GameUpdated { game_id, home, turn }).collect())
+}
+
+/// Decodes the plaintext of a `game_delta` notification on a txhash mode channel, which
+/// holds every delta the transaction sent to the recipient
+pub fn decode_txhash_game_deltas(plaintext: &[u8]) -> Result<Vec<GameDelta>, ClientError> {
+    let deltas: Vec<EncodedGameDelta> = decode(plaintext)?;
+    Ok(deltas
+        .into_iter()
+        .map(|(version, game_id, seq, cell, result, sunk, turn)| {
+            GameDelta { version, game_id, seq, cell, result, sunk, turn }
+        })
+        .collect())
+}
+
+fn decode<'a, T: Deserialize<'a>>(plaintext: &'a [u8]) -> Result<T, ClientError> {
+    // the zero padding after the CBOR array is ignored
+    cbor::from_slice(plaintext).map_err(|e| ClientError::InvalidData(format!("{:?}", e)))
+}
+
+fn hmac_id(seed: &[u8], material: &str) -> Result<[u8; 32], ClientError> {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(seed).map_err(|_| ClientError::InvalidSeed)?;
+    mac.update(material.as_bytes());
+    Ok(mac.finalize().into_bytes().into())
+}
+
+fn channel_id_bytes(channel: &str) -> [u8; 12] {
+    let mut bytes = [0u8; 12];
+    bytes.copy_from_slice(&Sha256::digest(channel.as_bytes())[..12]);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{Addr, Api};
+    use crate::battleship::{encode_game_delta, encode_game_updated, AttackResult};
+    use crate::snip52_exec_query::render_notification;
+    use crate::snip52_state::{get_seed, INTERNAL_SECRET};
+
+    // pinned vectors, so that a change to the wire format on either side fails loudly
+    const SEED: [u8; 32] = [7; 32];
+    const COUNTER_ID: &str = "6628815dc96e64ccd16b22f1cfe2afb00ea2061eb49a2d2aac44923c340c1f61";
+    const COUNTER_NONCE: &str = "a952801b30bfe8c2576958b0";
+    const TXHASH_ID: &str = "1dceb332ef77f5566254ecc82bd90e5db9b7e323427431019e5a5c06744684f9";
+    const TXHASH_NONCE: &str = "4ae24459a843f4d6cd92ac7b";
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn test_vectors() {
+        assert_eq!(hex(&notification_id(&SEED, "game_delta", 3).unwrap()), COUNTER_ID);
+        assert_eq!(hex(&notification_nonce("game_delta", 3)), COUNTER_NONCE);
+        let tx_hash = mock_env().transaction.unwrap().hash;
+        assert_eq!(hex(&txhash_notification_id(&SEED, "game_delta", &tx_hash).unwrap()), TXHASH_ID);
+        assert_eq!(hex(&txhash_notification_nonce("game_delta", &tx_hash).unwrap()), TXHASH_NONCE);
+
+        assert_eq!(txhash_notification_nonce("game_delta", "abc"), Err(ClientError::InvalidTxHash));
+        assert_eq!(txhash_notification_nonce("game_delta", "e3b0"), Err(ClientError::InvalidTxHash));
+        let wrong_seed = decrypt_notification(&[7; 31], &[0; 12], "", &[0; 16]);
+        assert_eq!(wrong_seed, Err(ClientError::InvalidSeed));
+    }
+
+    #[test]
+    fn test_matches_contract() {
+        let mut deps = mock_dependencies();
+        INTERNAL_SECRET.save(&mut deps.storage, &vec![9; 32]).unwrap();
+        let env = mock_env();
+        let sender = Addr::unchecked("alice");
+        let recipient = deps.api.addr_canonicalize("bob").unwrap();
+        let seed = get_seed(&deps.storage, &recipient).unwrap();
+        let attack = AttackResult { cell: 57, result: 3, sunk: None };
+        let delta = encode_game_delta("game1", 4, Some(&attack), 2).unwrap();
+
+        // counter mode, for the first two notifications on the channel
+        for counter in 0..2 {
+            let (id, encrypted_data) = render_notification(
+                &mut deps.storage, &env, &sender, &recipient, "game_delta", delta.clone(),
+            ).unwrap();
+            assert_eq!(id.as_slice(), notification_id(seed.as_slice(), "game_delta", counter).unwrap());
+            let plaintext = decrypt_notification(
+                seed.as_slice(),
+                &notification_nonce("game_delta", counter),
+                &notification_aad(env.block.height, sender.as_str()),
+                encrypted_data.as_slice(),
+            ).unwrap();
+            assert_eq!(plaintext.len(), 256);
+            assert_eq!(
+                decode_game_delta(&plaintext).unwrap(),
+                GameDelta {
+                    version: 1,
+                    game_id: "game1".to_string(),
+                    seq: 4,
+                    cell: Some(57),
+                    result: Some(3),
+                    sunk: None,
+                    turn: 2,
+                }
+            );
+        }
+
+        // the full board payload
+        let home = vec![0_u8; 100];
+        let updated = encode_game_updated("game1", &home, 1).unwrap();
+        let (_, encrypted_data) = render_notification(
+            &mut deps.storage, &env, &sender, &recipient, "game_updated", updated,
+        ).unwrap();
+        let plaintext = decrypt_notification(
+            seed.as_slice(),
+            &notification_nonce("game_updated", 0),
+            &notification_aad(env.block.height, sender.as_str()),
+            encrypted_data.as_slice(),
+        ).unwrap();
+        assert_eq!(
+            decode_game_updated(&plaintext).unwrap(),
+            GameUpdated { game_id: "game1".to_string(), home, turn: 1 }
+        );
+
+        // the wrong aad does not authenticate
+        let wrong = decrypt_notification(
+            seed.as_slice(),
+            &notification_nonce("game_updated", 0),
+            &notification_aad(env.block.height + 1, sender.as_str()),
+            encrypted_data.as_slice(),
+        );
+        assert_eq!(wrong, Err(ClientError::DecryptionFailed));
+    }
+
+    #[test]
+    fn test_txhash_matches_contract() {
+        let mut deps = mock_dependencies();
+        INTERNAL_SECRET.save(&mut deps.storage, &vec![9; 32]).unwrap();
+        crate::snip52_channel::CHANNEL_MODE
+            .add_suffix(b"game_delta")
+            .save(&mut deps.storage, &crate::msg::ChannelMode::Txhash)
+            .unwrap();
+        let env = mock_env();
+        let tx_hash = env.transaction.clone().unwrap().hash;
+        let recipient = deps.api.addr_canonicalize("bob").unwrap();
+        let seed = get_seed(&deps.storage, &recipient).unwrap();
+
+        // each delta of the tx is rendered with those before it
+        for seq in 1..3_u32 {
+            let delta = encode_game_delta("game1", seq, None, 1).unwrap();
+            let (id, encrypted_data) = render_notification(
+                &mut deps.storage, &env, &Addr::unchecked("alice"), &recipient, "game_delta", delta,
+            ).unwrap();
+            assert_eq!(id.as_slice(), txhash_notification_id(seed.as_slice(), "game_delta", &tx_hash).unwrap());
+            let plaintext = decrypt_notification(
+                seed.as_slice(),
+                &txhash_notification_nonce("game_delta", &tx_hash).unwrap(),
+                &txhash_notification_aad(env.block.height, &tx_hash),
+                encrypted_data.as_slice(),
+            ).unwrap();
+            let deltas = decode_txhash_game_deltas(&plaintext).unwrap();
+            assert_eq!(deltas.iter().map(|delta| delta.seq).collect::<Vec<_>>(), (1..=seq).collect::<Vec<_>>());
+        }
+    }
+}
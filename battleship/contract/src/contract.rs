@@ -1,5 +1,6 @@
 /// This contract implements SNIP-821 standard
 use std::collections::HashSet;
+use minicbor_ser as cbor;
 use base64::{engine::general_purpose, Engine};
 use cosmwasm_std::{
     attr, entry_point, to_binary, Addr, Api, Binary, BlockInfo, CanonicalAddr, CosmosMsg, Deps,
@@ -15,28 +16,55 @@ use secret_toolkit::{
 };
 
 use crate::{expiration::Expiration, battleship::SVG_TEMPLATE, token::Extension};
-use crate::snip52_exec_query::{query_channel_info, query_list_channels};
+use crate::snip52_exec_query::{query_channel_info, query_list_channels, query_notification_inbox};
 use crate::snip52_crypto::hkdf_sha_256;
-use crate::snip52_state::INTERNAL_SECRET;
-use crate::snip52_channel::{Channel, GAME_UPDATED_CHANNEL_ID, GAME_UPDATED_CHANNEL_SCHEMA};
-use crate::battleship::{new_game, query_active_games};
-use crate::snip52_exec_query::update_seed;
+use crate::snip52_state::{BECH32_HRP, INTERNAL_SECRET};
+use crate::snip52_channel::{
+    Channel, GAME_UPDATED_CHANNEL_ID, GAME_UPDATED_CHANNEL_SCHEMA, GAME_UPDATED_CHANNEL_DATA_LEN,
+    GAME_DELTA_CHANNEL_ID, GAME_DELTA_CHANNEL_SCHEMA, GAME_DELTA_CHANNEL_DATA_LEN,
+    PLAYER_JOINED_CHANNEL_ID, PLAYER_JOINED_CHANNEL_SCHEMA, PLAYER_JOINED_CHANNEL_DATA_LEN,
+    OPPONENT_ATTACKED_CHANNEL_ID, OPPONENT_ATTACKED_CHANNEL_SCHEMA, OPPONENT_ATTACKED_CHANNEL_DATA_LEN,
+    GAME_LISTED_CHANNEL_ID, GAME_LISTED_CHANNEL_SCHEMA, GAME_LISTED_CHANNEL_DATA_LEN,
+    NFT_RECEIVED_CHANNEL_ID, NFT_RECEIVED_CHANNEL_SCHEMA, NFT_RECEIVED_CHANNEL_DATA_LEN,
+    NFT_SENT_CHANNEL_ID, NFT_SENT_CHANNEL_SCHEMA, NFT_SENT_CHANNEL_DATA_LEN,
+    APPROVAL_CHANGED_CHANNEL_ID, APPROVAL_CHANGED_CHANNEL_SCHEMA, APPROVAL_CHANGED_CHANNEL_DATA_LEN,
+    channels_store,
+};
 use crate::battleship::{
-    join_game, submit_setup, attack_cell, claim_victory, query_list_games, 
+    new_game, query_active_games, check_wager_lock, update_active_game_owners, subscribe_lobby,
+    clear_lobby_subscriptions, query_lobby_tiers, set_notification_delegates,
+    clear_notification_delegates,
+};
+use crate::snip52_exec_query::{
+    add_channel, add_notifications, remove_channel, render_notification, reset_seed, rotate_secret,
+    set_channel_mode, update_channel_schema, update_seed,
+};
+use crate::seasons::{close_season, create_season, query_season_standings, query_seasons};
+use crate::achievements::{
+    achievements_store, default_achievements, query_achievements, query_token_achievements,
+    remove_achievement, set_achievement, BADGE_TOKEN_STORE,
+};
+use crate::battleship::{
+    join_game, submit_setup, attack_cell, claim_victory, force_unlock_game, emergency_refund_games,
+    migrate_games, query_list_games, query_finished_games, 
     query_game_state,
 };
 use crate::nfp::{
     add_any_delegate, add_token_delegate, remove_any_delegate, remove_token_delegate, remove_all_any_delegates, remove_all_token_delegates, 
     ANY_DELEGATES, TOKEN_DELEGATES, TOKEN_DELEGATES_INVERSE, ACCESS_PUBLIC_STRING, ACCESS_OWNERS_STRING, ACCESS_CLEARED_STRING, KEY_CLEARED_PACKAGES, 
     add_tags_to_package_version, PackageVersion, get_package_version_by_tag, get_package_version_by_index, get_package_versions_info, 
-    RawData, PREFIX_STORAGE_OWNER, PREFIX_STORAGE_TOKEN, PREFIX_STORAGE_GLOBAL, store_package_version,};
+    RawData, PREFIX_STORAGE_OWNER, PREFIX_STORAGE_TOKEN, PREFIX_STORAGE_GLOBAL, store_package_version,
+    check_package_dependencies, find_package_index, resolve_package, PackageManifest,
+    assemble_package_upload, may_load_package_upload, remove_package_upload, save_package_upload,
+    stage_package_chunk, StoredPackageUpload, move_package_tag, remove_package_tag, get_protected_tags,
+    PROTECTED_TAGS,};
 use crate::inventory::{Inventory, InventoryIter};
 use crate::mint_run::{SerialNumber, StoredMintRunInfo};
 use crate::msg::{
     AccessLevel, BatchNftDossierElement, ContractStatus, Cw721Approval, Cw721OwnerOfResponse,
     ExecuteAnswer, ExecuteMsg, InstantiateMsg, Mint, QueryAnswer, QueryMsg, QueryWithPermit,
     ReceiverInfo, ResponseStatus::Success, Send, Snip721Approval, Transfer, ViewerInfo,
-    KeyValuePair,
+    KeyValuePair, TxActionFilter,
 };
 use crate::receiver::{batch_receive_nft_msg, receive_nft_msg};
 use crate::royalties::{RoyaltyInfo, StoredRoyaltyInfo};
@@ -114,6 +142,42 @@ pub fn instantiate(
         Channel {
             id: GAME_UPDATED_CHANNEL_ID.to_string(),
             schema: Some(GAME_UPDATED_CHANNEL_SCHEMA.to_string()),
+            data_len: Some(GAME_UPDATED_CHANNEL_DATA_LEN),
+        },
+        Channel {
+            id: GAME_DELTA_CHANNEL_ID.to_string(),
+            schema: Some(GAME_DELTA_CHANNEL_SCHEMA.to_string()),
+            data_len: Some(GAME_DELTA_CHANNEL_DATA_LEN),
+        },
+        Channel {
+            id: GAME_LISTED_CHANNEL_ID.to_string(),
+            schema: Some(GAME_LISTED_CHANNEL_SCHEMA.to_string()),
+            data_len: Some(GAME_LISTED_CHANNEL_DATA_LEN),
+        },
+        Channel {
+            id: PLAYER_JOINED_CHANNEL_ID.to_string(),
+            schema: Some(PLAYER_JOINED_CHANNEL_SCHEMA.to_string()),
+            data_len: Some(PLAYER_JOINED_CHANNEL_DATA_LEN),
+        },
+        Channel {
+            id: OPPONENT_ATTACKED_CHANNEL_ID.to_string(),
+            schema: Some(OPPONENT_ATTACKED_CHANNEL_SCHEMA.to_string()),
+            data_len: Some(OPPONENT_ATTACKED_CHANNEL_DATA_LEN),
+        },
+        Channel {
+            id: NFT_RECEIVED_CHANNEL_ID.to_string(),
+            schema: Some(NFT_RECEIVED_CHANNEL_SCHEMA.to_string()),
+            data_len: Some(NFT_RECEIVED_CHANNEL_DATA_LEN),
+        },
+        Channel {
+            id: NFT_SENT_CHANNEL_ID.to_string(),
+            schema: Some(NFT_SENT_CHANNEL_SCHEMA.to_string()),
+            data_len: Some(NFT_SENT_CHANNEL_DATA_LEN),
+        },
+        Channel {
+            id: APPROVAL_CHANGED_CHANNEL_ID.to_string(),
+            schema: Some(APPROVAL_CHANGED_CHANNEL_SCHEMA.to_string()),
+            data_len: Some(APPROVAL_CHANGED_CHANNEL_DATA_LEN),
         },
     ];
 
@@ -128,6 +192,10 @@ pub fn instantiate(
         SVG_TEMPLATE.save(deps.storage, &template)?;
     }
 
+    for achievement in default_achievements() {
+        achievements_store().insert(deps.storage, &achievement.achievement_id, &achievement)?;
+    }
+
     // updated snip721 prng_seed calculation to use hkdf- BA
 
     let prng_seed = hkdf_sha_256(
@@ -163,6 +231,17 @@ pub fn instantiate(
     save(deps.storage, CONFIG_KEY, &config)?;
     save(deps.storage, MINTERS_KEY, &minters)?;
 
+    //SNIP-52
+    if let Some(hrp) = init_config.bech32_hrp {
+        // make sure accounts can be encoded with the prefix, and match the lowercase addresses
+        if hrp != hrp.to_lowercase()
+            || bech32::encode(&hrp, Vec::<bech32::u5>::new(), bech32::Variant::Bech32).is_err()
+        {
+            return Err(StdError::generic_err("Invalid bech32 prefix"));
+        }
+        BECH32_HRP.save(deps.storage, &hrp)?;
+    }
+
     if msg.royalty_info.is_some() {
         store_royalties(
             deps.storage,
@@ -446,16 +525,16 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             storage_owner_put(deps, &info.sender, &config, data, owner, token_id)
         },
         ExecuteMsg::ApproveOwnerDelegate { address, .. } => {
-            approve_owner_delegate(deps, &info.sender, address)
+            approve_owner_delegate(deps, &env, &info.sender, address)
         },
         ExecuteMsg::ApproveTokenDelegate { address, token_ids, .. } => {
-            approve_token_delegate(deps, &info.sender, address, token_ids)
+            approve_token_delegate(deps, &env, &info.sender, address, token_ids)
         },
         ExecuteMsg::RevokeDelegate { address, .. } => {
-            revoke_delegate(deps, &info.sender, address)
+            revoke_delegate(deps, &env, &info.sender, address)
         },
         ExecuteMsg::RevokeAllDelegates { .. } => {
-            revoke_all_delegates(deps, &info.sender)
+            revoke_all_delegates(deps, &env, &info.sender)
         },
         ExecuteMsg::StorageTokenPut { data, token_id, .. } => {
             if !config.minter_may_put_token_storage {
@@ -475,6 +554,7 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             tags, 
             metadata,
             access,
+            manifest,
             .. 
         } => upload_package_version(
             deps,
@@ -486,6 +566,47 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             tags,
             metadata,
             access,
+            manifest,
+        ),
+        ExecuteMsg::BeginPackageUpload {
+            package_id,
+            data,
+            total_len,
+            sha256,
+            tags,
+            metadata,
+            access,
+            manifest,
+            ..
+        } => begin_package_upload(
+            deps,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            package_id,
+            data,
+            total_len,
+            sha256,
+            tags,
+            metadata,
+            access,
+            manifest,
+        ),
+        ExecuteMsg::AppendPackageChunk { package_id, index, chunk, .. } => append_package_chunk(
+            deps,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            package_id,
+            index,
+            chunk,
+        ),
+        ExecuteMsg::FinalizePackageUpload { package_id, .. } => finalize_package_upload(
+            deps,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            package_id,
         ),
         ExecuteMsg::AddPackageTags { package_id, index, tags, .. } => add_package_tags(
             deps,
@@ -496,6 +617,30 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             index,
             tags,
         ),
+        ExecuteMsg::MovePackageTag { package_id, tag, index, .. } => move_tag(
+            deps,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            package_id,
+            tag,
+            index,
+        ),
+        ExecuteMsg::RemovePackageTag { package_id, tag, .. } => remove_tag(
+            deps,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            package_id,
+            tag,
+        ),
+        ExecuteMsg::SetProtectedPackageTags { tags, .. } => set_protected_package_tags(
+            deps,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            tags,
+        ),
 
         // Battleship
         ExecuteMsg::NewGame { token_id, title, .. } => new_game(
@@ -503,14 +648,33 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             env,
             info,
             &config,
+            ContractStatus::Normal.to_u8(),
             token_id,
             title,
         ),
+        ExecuteMsg::SubscribeLobby { token_id, tiers, .. } => subscribe_lobby(
+            deps,
+            &env,
+            &info.sender,
+            &config,
+            ContractStatus::Normal.to_u8(),
+            token_id,
+            tiers,
+        ),
+        ExecuteMsg::SetNotificationDelegates { token_id, delegates, .. } => set_notification_delegates(
+            deps,
+            &info.sender,
+            &config,
+            ContractStatus::Normal.to_u8(),
+            token_id,
+            delegates,
+        ),
         ExecuteMsg::JoinGame { token_id, game_id, .. } => join_game(
             deps,
             env,
             info,
-            &config,
+            &mut config,
+            ContractStatus::Normal.to_u8(),
             token_id,
             game_id,
         ),
@@ -519,6 +683,7 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             &info.sender,
             env,
             &config,
+            ContractStatus::Normal.to_u8(),
             token_id,
             game_id,
             cells,
@@ -527,7 +692,8 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             deps,
             env,
             &info.sender,
-            &config,
+            &mut config,
+            ContractStatus::Normal.to_u8(),
             token_id,
             game_id,
             cell,
@@ -536,10 +702,74 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             deps,
             env,
             &info.sender,
-            &config,
+            &mut config,
+            ContractStatus::Normal.to_u8(),
             token_id,
             game_id,
         ),
+        ExecuteMsg::ForceUnlockGame { game_id, .. } => force_unlock_game(
+            deps,
+            env,
+            &info.sender,
+            &mut config,
+            ContractStatus::StopTransactions.to_u8(),
+            game_id,
+        ),
+        ExecuteMsg::EmergencyRefundGames { limit, .. } => emergency_refund_games(
+            deps,
+            env,
+            &info.sender,
+            &mut config,
+            ContractStatus::StopAll.to_u8(),
+            limit,
+        ),
+        ExecuteMsg::CreateSeason {
+            name,
+            start,
+            end,
+            fee_percent,
+            prize_split,
+            ..
+        } => create_season(
+            deps,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            name,
+            start,
+            end,
+            fee_percent,
+            prize_split,
+        ),
+        ExecuteMsg::CloseSeason { season_id, .. } => close_season(
+            deps,
+            env,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            season_id,
+        ),
+        ExecuteMsg::SetAchievement { achievement, .. } => set_achievement(
+            deps,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            achievement,
+        ),
+        ExecuteMsg::RemoveAchievement { achievement_id, .. } => remove_achievement(
+            deps,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            achievement_id,
+        ),
+        ExecuteMsg::MigrateGames { limit, .. } => migrate_games(
+            deps,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            limit,
+        ),
         ExecuteMsg::MintPublic {
             ..
         } => mint(
@@ -566,6 +796,47 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             &info.sender, 
             signed_doc
         ),
+        ExecuteMsg::AddChannel { channel, cddl, data_len, .. } => add_channel(
+            deps,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            channel,
+            cddl,
+            data_len,
+        ),
+        ExecuteMsg::UpdateChannelSchema { channel, cddl, .. } => update_channel_schema(
+            deps,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            channel,
+            cddl,
+        ),
+        ExecuteMsg::RemoveChannel { channel, .. } => remove_channel(
+            deps,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            channel,
+        ),
+        ExecuteMsg::SetChannelMode { channel, mode, .. } => set_channel_mode(
+            deps,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            channel,
+            mode,
+        ),
+        ExecuteMsg::RotateSecret { entropy, .. } => rotate_secret(
+            deps,
+            &env,
+            &info.sender,
+            &config,
+            ContractStatus::StopTransactions.to_u8(),
+            entropy,
+        ),
+        ExecuteMsg::ResetSeed { .. } => reset_seed(deps, &info.sender),
     };
     response
     //pad_handle_result(response, BLOCK_SIZE)
@@ -629,7 +900,7 @@ pub fn mint(
         transferable,
         memo,
     }];
-    let mut minted = mint_list(deps, env, config, &sender_raw, mints)?;
+    let mut minted = mint_list(deps, env, config, &sender_raw, mints, false)?;
     let minted_str = minted.pop().unwrap_or_default();
     Ok(Response::new()
         .add_attributes(vec![attr("minted", &minted_str)])
@@ -703,7 +974,7 @@ pub fn set_metadata(
 /// * `is_approve` - true if this is an Approve call
 #[allow(clippy::too_many_arguments)]
 pub fn approve_revoke(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: &Env,
     sender: &Addr,
     config: &Config,
@@ -782,7 +1053,16 @@ pub fn approve_revoke(
         &mut proc_info,
         all_perm,
     )?;
-    let res = Response::new().set_data(to_binary(&response)?);
+    let change = ApprovalChange {
+        kind: APPROVAL_KIND_TRANSFER,
+        token_ids: vec![token_id.to_string()],
+        owner: &owner,
+        spender: &address_raw,
+        approved: is_approve,
+    };
+    let notifications =
+        approval_changed_notifications(&mut deps, env, sender, &change, &[&owner, &address_raw])?;
+    let res = add_notifications(Response::new(), notifications).set_data(to_binary(&response)?);
     Ok(res)
 }
 
@@ -935,11 +1215,11 @@ pub fn batch_transfer_nft(
     transfers: Vec<Transfer>,
 ) -> StdResult<Response> {
     check_status(config.status, priority)?;
-    let _m = send_list(deps, env, sender, config, Some(transfers), None)?;
+    let (_m, notifications) = send_list(deps, env, sender, config, Some(transfers), None)?;
 
-    let res = Response::new().set_data(to_binary(&ExecuteAnswer::BatchTransferNft {
-        status: Success,
-    })?);
+    let res = add_notifications(Response::new(), notifications).set_data(to_binary(
+        &ExecuteAnswer::BatchTransferNft { status: Success },
+    )?);
     Ok(res)
 }
 
@@ -974,9 +1254,10 @@ pub fn transfer_nft(
         token_ids: vec![token_id],
         memo,
     }]);
-    let _m = send_list(deps, env, sender, config, transfers, None)?;
+    let (_m, notifications) = send_list(deps, env, sender, config, transfers, None)?;
 
-    let res = Response::new().set_data(to_binary(&ExecuteAnswer::TransferNft { status: Success })?);
+    let res = add_notifications(Response::new(), notifications)
+        .set_data(to_binary(&ExecuteAnswer::TransferNft { status: Success })?);
     Ok(res)
 }
 
@@ -1002,9 +1283,9 @@ fn batch_send_nft(
     sends: Vec<Send>,
 ) -> StdResult<Response> {
     check_status(config.status, priority)?;
-    let messages = send_list(deps, env, sender, config, None, Some(sends))?;
+    let (messages, notifications) = send_list(deps, env, sender, config, None, Some(sends))?;
 
-    let res = Response::new()
+    let res = add_notifications(Response::new(), notifications)
         .add_messages(messages)
         .set_data(to_binary(&ExecuteAnswer::BatchSendNft { status: Success })?);
     Ok(res)
@@ -1049,9 +1330,9 @@ fn send_nft(
         msg,
         memo,
     }]);
-    let messages = send_list(deps, env, sender, config, None, sends)?;
+    let (messages, notifications) = send_list(deps, env, sender, config, None, sends)?;
 
-    let res = Response::new()
+    let res = add_notifications(Response::new(), notifications)
         .add_messages(messages)
         .set_data(to_binary(&ExecuteAnswer::SendNft { status: Success })?);
     Ok(res)
@@ -1485,19 +1766,31 @@ pub fn storage_owner_put(
 /// # Arguments
 ///
 /// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `env` - a reference to the Env of contract's environment
 /// * `sender` - addr of sender (owner)
 /// * `address` - addr of the account being delegated approval
 fn approve_owner_delegate(
-    deps: DepsMut,
+    mut deps: DepsMut,
+    env: &Env,
     sender: &Addr,
     address: String,
 ) -> StdResult<Response> {
     let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
     let address_raw = deps.api.addr_canonicalize(&address)?;
 
-    add_any_delegate(deps.storage, sender_raw, address_raw)?;
+    add_any_delegate(deps.storage, sender_raw.clone(), address_raw.clone())?;
 
-    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::ApproveOwnerDelegate { status: Success })?))
+    let change = ApprovalChange {
+        kind: APPROVAL_KIND_STORAGE_DELEGATE,
+        token_ids: vec![],
+        owner: &sender_raw,
+        spender: &address_raw,
+        approved: true,
+    };
+    let notifications =
+        approval_changed_notifications(&mut deps, env, sender, &change, &[&address_raw])?;
+    Ok(add_notifications(Response::new(), notifications)
+        .set_data(to_binary(&ExecuteAnswer::ApproveOwnerDelegate { status: Success })?))
 }
 
 /// Returns StdResult<Response>
@@ -1507,11 +1800,13 @@ fn approve_owner_delegate(
 /// # Arguments
 ///
 /// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `env` - a reference to the Env of contract's environment
 /// * `sender` - addr of sender (owner)
 /// * `address` - addr of the account being delegated approval
 /// * `token_id` - token id
 fn approve_token_delegate(
-    deps: DepsMut,
+    mut deps: DepsMut,
+    env: &Env,
     sender: &Addr,
     address: String,
     token_ids: Vec<String>,
@@ -1519,9 +1814,19 @@ fn approve_token_delegate(
     let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
     let address_raw = deps.api.addr_canonicalize(&address)?;
 
-    add_token_delegate(deps.storage, sender_raw, address_raw, token_ids)?;
+    add_token_delegate(deps.storage, sender_raw.clone(), address_raw.clone(), token_ids.clone())?;
 
-    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::ApproveTokenDelegate { status: Success })?))
+    let change = ApprovalChange {
+        kind: APPROVAL_KIND_STORAGE_DELEGATE,
+        token_ids,
+        owner: &sender_raw,
+        spender: &address_raw,
+        approved: true,
+    };
+    let notifications =
+        approval_changed_notifications(&mut deps, env, sender, &change, &[&address_raw])?;
+    Ok(add_notifications(Response::new(), notifications)
+        .set_data(to_binary(&ExecuteAnswer::ApproveTokenDelegate { status: Success })?))
 }
 
 /// Returns StdResult<Response>
@@ -1531,10 +1836,12 @@ fn approve_token_delegate(
 /// # Arguments
 ///
 /// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `env` - a reference to the Env of contract's environment
 /// * `sender` - addr of sender (owner)
 /// * `address` - addr of the account being delegated approval
 fn revoke_delegate(
-    deps: DepsMut,
+    mut deps: DepsMut,
+    env: &Env,
     sender: &Addr,
     address: String,
 ) -> StdResult<Response> {
@@ -1542,9 +1849,19 @@ fn revoke_delegate(
     let address_raw = deps.api.addr_canonicalize(&address)?;
 
     remove_any_delegate(deps.storage, sender_raw.clone(), address_raw.clone())?;
-    remove_token_delegate(deps.storage, sender_raw, address_raw)?;
-
-    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::RevokeDelegate { status: Success })?))
+    remove_token_delegate(deps.storage, sender_raw.clone(), address_raw.clone())?;
+
+    let change = ApprovalChange {
+        kind: APPROVAL_KIND_STORAGE_DELEGATE,
+        token_ids: vec![],
+        owner: &sender_raw,
+        spender: &address_raw,
+        approved: false,
+    };
+    let notifications =
+        approval_changed_notifications(&mut deps, env, sender, &change, &[&address_raw])?;
+    Ok(add_notifications(Response::new(), notifications)
+        .set_data(to_binary(&ExecuteAnswer::RevokeDelegate { status: Success })?))
 }
 
 /// Returns StdResult<Response>
@@ -1554,18 +1871,46 @@ fn revoke_delegate(
 /// # Arguments
 ///
 /// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `env` - a reference to the Env of contract's environment
 /// * `sender` - addr of sender (owner)
-/// * `address` - addr of the account being delegated approval
 fn revoke_all_delegates(
-    deps: DepsMut,
+    mut deps: DepsMut,
+    env: &Env,
     sender: &Addr,
 ) -> StdResult<Response> {
     let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
 
-    remove_all_any_delegates(deps.storage, sender_raw.clone())?;
-    remove_all_token_delegates(deps.storage, sender_raw)?;
+    // collect the delegates before they are removed so each can be notified
+    let mut delegates: Vec<CanonicalAddr> = ANY_DELEGATES
+        .add_suffix(sender_raw.as_slice())
+        .iter(deps.storage)?
+        .collect::<StdResult<Vec<CanonicalAddr>>>()?;
+    for delegate in TOKEN_DELEGATES
+        .add_suffix(sender_raw.as_slice())
+        .iter_keys(deps.storage)?
+    {
+        let delegate = delegate?;
+        if !delegates.contains(&delegate) {
+            delegates.push(delegate);
+        }
+    }
 
-    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::RevokeAllDelegates { status: Success })?))
+    remove_all_any_delegates(deps.storage, sender_raw.clone())?;
+    remove_all_token_delegates(deps.storage, sender_raw.clone())?;
+
+    let mut notifications = vec![];
+    for delegate in delegates.iter() {
+        let change = ApprovalChange {
+            kind: APPROVAL_KIND_STORAGE_DELEGATE,
+            token_ids: vec![],
+            owner: &sender_raw,
+            spender: delegate,
+            approved: false,
+        };
+        notifications.extend(approval_changed_notifications(&mut deps, env, sender, &change, &[delegate])?);
+    }
+    Ok(add_notifications(Response::new(), notifications)
+        .set_data(to_binary(&ExecuteAnswer::RevokeAllDelegates { status: Success })?))
 }
 
 /// Returns StdResult<Response>
@@ -1621,125 +1966,444 @@ fn storage_token_put(
 
 /// Returns StdResult<Response>
 ///
-/// puts a set of key-value pairs in global storage
+/// puts a set of key-value pairs in global storage
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `sender` - addr of sender 
+/// * `config` - config 
+/// * `data` - vector of key-value pairs to put in global storage
+fn storage_global_put(
+    deps: DepsMut,
+    sender: &Addr,
+    data: Vec<KeyValuePair>, 
+) -> StdResult<Response> {
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    let minters: Vec<CanonicalAddr> = may_load(deps.storage, MINTERS_KEY)?.unwrap_or_default();
+    if !minters.contains(&sender_raw) {
+        return Err(StdError::generic_err(
+            "Only designated minters can upload package versions",
+        ));
+    }
+    //if config.admin != sender_raw {
+    //    return Err(StdError::generic_err(
+    //        "This is an admin command and can only be run from the admin address",
+    //    ));
+    //}
+
+    data.into_iter().try_for_each(|pair| {
+        if pair.key.len() == 0 {
+            return Err(StdError::generic_err("Empty key string in key value pair is invalid"));
+        }
+
+        let mut key_value_store = PrefixedStorage::new(
+            deps.storage, 
+            PREFIX_STORAGE_GLOBAL
+        );
+
+        if pair.value.is_some() {
+            let value = pair.value.unwrap();
+            save(&mut key_value_store, pair.key.as_bytes(), &value)?;
+        } else {
+            remove(&mut key_value_store, pair.key.as_bytes());
+        }
+        Ok(())
+    })?;
+
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::StorageGlobalPut { status: Success })?))
+}
+
+/// Returns StdResult<Response>
+///
+/// stores a new version of a package
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `sender` - addr of sender 
+/// * `config` - config 
+/// * `priority` - u8 representation of highest ContractStatus level this action is permitted
+/// * `package_id` - id of the package
+/// * `data` - data for the script as RawData
+/// * `tags` - optional vec of tags
+/// * `metadata` - optional metadata string
+/// * `access` - access type of the package
+/// * `manifest` - optional manifest with the entry point, dependencies and exports
+#[allow(clippy::too_many_arguments)]
+fn upload_package_version(
+    deps: DepsMut,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    package_id: String,
+    data: RawData,
+    tags: Option<Vec<String>>,
+    metadata: Option<String>,
+    access: String,
+    manifest: Option<PackageManifest>,
+) -> StdResult<Response> {
+    check_package_upload(
+        deps.as_ref(), sender, config, priority, &package_id, &access, manifest.as_ref(), tags.as_ref(),
+    )?;
+
+    let index = store_package_version(deps.storage, package_id, data, tags, metadata, access, manifest)?;
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::UploadPackageVersion { index })?))
+}
+
+/// Returns StdResult<CanonicalAddr> which is the sender's address, after checking that a
+/// minter is uploading a package version with a valid access type, manifest and tags
+///
+/// # Arguments
+///
+/// * `deps` - a reference to Extern containing all the contract's external dependencies
+/// * `sender` - addr of sender
+/// * `config` - config
+/// * `priority` - u8 representation of highest ContractStatus level this action is permitted
+/// * `package_id` - id of the package
+/// * `access` - access type of the package
+/// * `manifest` - optional manifest of the version
+/// * `tags` - optional tags of the version
+#[allow(clippy::too_many_arguments)]
+fn check_package_upload(
+    deps: Deps,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    package_id: &str,
+    access: &String,
+    manifest: Option<&PackageManifest>,
+    tags: Option<&Vec<String>>,
+) -> StdResult<CanonicalAddr> {
+    check_status(config.status, priority)?;
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    let minters: Vec<CanonicalAddr> = may_load(deps.storage, MINTERS_KEY)?.unwrap_or_default();
+    if !minters.contains(&sender_raw) {
+        return Err(StdError::generic_err(
+            "Only designated minters can upload package versions",
+        ));
+    }
+    if let Some(tags) = tags {
+        check_protected_tags(deps.storage, &sender_raw, config, tags)?;
+    }
+
+    let valid_access: Vec<String> = vec![ACCESS_PUBLIC_STRING, ACCESS_OWNERS_STRING, ACCESS_CLEARED_STRING]
+        .into_iter()
+        .map(|s| String::from(s))
+        .collect();
+    if !valid_access.contains(access) {
+        return Err(StdError::generic_err(format!("{} is not a valid access option", access)));
+    }
+    //if config.admin != sender_raw {
+    //    return Err(StdError::generic_err(
+    //        "This is an admin command and can only be run from the admin address",
+    //    ));
+    //}
+
+    if let Some(manifest) = manifest {
+        check_package_dependencies(deps.storage, package_id, manifest)?;
+    }
+    Ok(sender_raw)
+}
+
+/// Returns StdResult<Response>
+///
+/// begins uploading a new version of a package in chunks.  an upload already in progress for
+/// the package is replaced if the sender started it or is the admin, otherwise this fails
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `sender` - addr of sender
+/// * `config` - config
+/// * `priority` - u8 representation of highest ContractStatus level this action is permitted
+/// * `package_id` - id of the package
+/// * `data` - data attributes for the script, with the first chunk as its bytes
+/// * `total_len` - length of the complete data
+/// * `sha256` - sha256 of the complete data
+/// * `tags` - optional vec of tags
+/// * `metadata` - optional metadata string
+/// * `access` - access type of the package
+/// * `manifest` - optional manifest with the entry point, dependencies and exports
+#[allow(clippy::too_many_arguments)]
+fn begin_package_upload(
+    deps: DepsMut,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    package_id: String,
+    data: RawData,
+    total_len: u32,
+    sha256: Binary,
+    tags: Option<Vec<String>>,
+    metadata: Option<String>,
+    access: String,
+    manifest: Option<PackageManifest>,
+) -> StdResult<Response> {
+    let sender_raw = check_package_upload(
+        deps.as_ref(), sender, config, priority, &package_id, &access, manifest.as_ref(), tags.as_ref(),
+    )?;
+    if sha256.len() != 32 {
+        return Err(StdError::generic_err("sha256 must be 32 bytes"));
+    }
+    if data.bytes.len() > total_len as usize {
+        return Err(StdError::generic_err("Chunk exceeds the declared length"));
+    }
+    // only the uploader, or the admin for an abandoned upload, can start over
+    if let Some(pending) = may_load_package_upload(deps.storage, &package_id)? {
+        if pending.uploader != sender_raw && config.admin != sender_raw {
+            return Err(StdError::generic_err(format!(
+                "Package {} has an upload in progress by another minter", package_id
+            )));
+        }
+    }
+
+    remove_package_upload(deps.storage, &package_id)?;
+    let mut data = data.into_stored()?;
+    let first_chunk = std::mem::take(&mut data.bytes);
+    let mut upload = StoredPackageUpload {
+        uploader: sender_raw,
+        data,
+        tags,
+        metadata,
+        access,
+        manifest,
+        total_len,
+        sha256: sha256.0,
+        chunk_count: 0,
+        received_len: 0,
+    };
+    if first_chunk.is_empty() {
+        save_package_upload(deps.storage, &package_id, &upload)?;
+    } else {
+        stage_package_chunk(deps.storage, &package_id, &mut upload, &first_chunk)?;
+    }
+
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::BeginPackageUpload {
+        chunk_count: upload.chunk_count,
+        received_len: upload.received_len,
+    })?))
+}
+
+/// Returns StdResult<Response>
+///
+/// stages the next chunk of a package upload
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `sender` - addr of sender
+/// * `config` - config
+/// * `priority` - u8 representation of highest ContractStatus level this action is permitted
+/// * `package_id` - id of the package
+/// * `index` - index of the chunk
+/// * `chunk` - chunk bytes
+fn append_package_chunk(
+    deps: DepsMut,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    package_id: String,
+    index: u32,
+    chunk: Binary,
+) -> StdResult<Response> {
+    let mut upload = load_package_upload(deps.as_ref(), sender, config, priority, &package_id)?;
+    if index != upload.chunk_count {
+        return Err(StdError::generic_err(format!(
+            "Expected chunk {}, got chunk {}",
+            upload.chunk_count, index
+        )));
+    }
+    if chunk.is_empty() {
+        return Err(StdError::generic_err("Chunk can not be empty"));
+    }
+    if upload.received_len as usize + chunk.len() > upload.total_len as usize {
+        return Err(StdError::generic_err("Chunk exceeds the declared length"));
+    }
+    stage_package_chunk(deps.storage, &package_id, &mut upload, chunk.as_slice())?;
+
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::AppendPackageChunk {
+        chunk_count: upload.chunk_count,
+        received_len: upload.received_len,
+    })?))
+}
+
+/// Returns StdResult<Response>
+///
+/// verifies the length and sha256 of a package upload and stores it as a new version
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `sender` - addr of sender
+/// * `config` - config
+/// * `priority` - u8 representation of highest ContractStatus level this action is permitted
+/// * `package_id` - id of the package
+fn finalize_package_upload(
+    deps: DepsMut,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    package_id: String,
+) -> StdResult<Response> {
+    let upload = load_package_upload(deps.as_ref(), sender, config, priority, &package_id)?;
+    let bytes = assemble_package_upload(deps.storage, &package_id, &upload)?;
+    // dependencies may have changed since the upload began
+    check_package_upload(
+        deps.as_ref(), sender, config, priority, &package_id, &upload.access, upload.manifest.as_ref(),
+        upload.tags.as_ref(),
+    )?;
+
+    let mut data = upload.data.into_humanized()?;
+    data.bytes = Binary::from(bytes);
+    let index = store_package_version(
+        deps.storage,
+        package_id.clone(),
+        data,
+        upload.tags,
+        upload.metadata,
+        upload.access,
+        upload.manifest,
+    )?;
+    remove_package_upload(deps.storage, &package_id)?;
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::FinalizePackageUpload { index })?))
+}
+
+/// Returns StdResult<StoredPackageUpload> which is the upload in progress for a package, after
+/// checking that the sender began it
+///
+/// # Arguments
+///
+/// * `deps` - a reference to Extern containing all the contract's external dependencies
+/// * `sender` - addr of sender
+/// * `config` - config
+/// * `priority` - u8 representation of highest ContractStatus level this action is permitted
+/// * `package_id` - id of the package
+fn load_package_upload(
+    deps: Deps,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    package_id: &str,
+) -> StdResult<StoredPackageUpload> {
+    check_status(config.status, priority)?;
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    match may_load_package_upload(deps.storage, package_id)? {
+        Some(upload) if upload.uploader == sender_raw => Ok(upload),
+        _ => Err(StdError::generic_err(format!(
+            "You have no upload in progress for package {}",
+            package_id
+        ))),
+    }
+}
+
+/// Returns StdResult<Response>
+///
+/// stores a new version of a package
 ///
 /// # Arguments
 ///
 /// * `deps` - mutable reference to Extern containing all the contract's external dependencies
 /// * `sender` - addr of sender 
 /// * `config` - config 
-/// * `data` - vector of key-value pairs to put in global storage
-fn storage_global_put(
+/// * `priority` - u8 representation of highest ContractStatus level this action is permitted
+/// * `package_id` - id of the package
+/// * `version` - index of the version
+/// * `tags` - optional vec of tags
+fn add_package_tags(
     deps: DepsMut,
     sender: &Addr,
-    data: Vec<KeyValuePair>, 
+    config: &Config,
+    priority: u8,
+    package_id: String,
+    index: u32,
+    tags: Vec<String>,
 ) -> StdResult<Response> {
+    check_status(config.status, priority)?;
     let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
-    let minters: Vec<CanonicalAddr> = may_load(deps.storage, MINTERS_KEY)?.unwrap_or_default();
-    if !minters.contains(&sender_raw) {
+    if config.admin != sender_raw {
         return Err(StdError::generic_err(
-            "Only designated minters can upload package versions",
+            "This is an admin command and can only be run from the admin address",
         ));
     }
-    //if config.admin != sender_raw {
-    //    return Err(StdError::generic_err(
-    //        "This is an admin command and can only be run from the admin address",
-    //    ));
-    //}
-
-    data.into_iter().try_for_each(|pair| {
-        if pair.key.len() == 0 {
-            return Err(StdError::generic_err("Empty key string in key value pair is invalid"));
-        }
-
-        let mut key_value_store = PrefixedStorage::new(
-            deps.storage, 
-            PREFIX_STORAGE_GLOBAL
-        );
 
-        if pair.value.is_some() {
-            let value = pair.value.unwrap();
-            save(&mut key_value_store, pair.key.as_bytes(), &value)?;
-        } else {
-            remove(&mut key_value_store, pair.key.as_bytes());
-        }
-        Ok(())
-    })?;
+    add_tags_to_package_version(deps.storage, package_id, index, tags)?;
 
-    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::StorageGlobalPut { status: Success })?))
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::AddPackageTags { status: Success })?))
 }
 
 /// Returns StdResult<Response>
 ///
-/// stores a new version of a package
+/// moves an existing tag of a package to another version
 ///
 /// # Arguments
 ///
 /// * `deps` - mutable reference to Extern containing all the contract's external dependencies
-/// * `sender` - addr of sender 
-/// * `config` - config 
+/// * `sender` - addr of sender
+/// * `config` - config
 /// * `priority` - u8 representation of highest ContractStatus level this action is permitted
 /// * `package_id` - id of the package
-/// * `data` - data for the script as RawData
-/// * `tags` - optional vec of tags
-/// * `metadata` - optional metadata string
-fn upload_package_version(
+/// * `tag` - tag to move
+/// * `index` - index of the version the tag should refer to
+fn move_tag(
     deps: DepsMut,
     sender: &Addr,
     config: &Config,
     priority: u8,
     package_id: String,
-    data: RawData,
-    tags: Option<Vec<String>>,
-    metadata: Option<String>,
-    access: String,
+    tag: String,
+    index: u32,
 ) -> StdResult<Response> {
-    check_status(config.status, priority)?;
-    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
-    let minters: Vec<CanonicalAddr> = may_load(deps.storage, MINTERS_KEY)?.unwrap_or_default();
-    if !minters.contains(&sender_raw) {
-        return Err(StdError::generic_err(
-            "Only designated minters can upload package versions",
-        ));
-    }
+    check_package_tagger(deps.as_ref(), sender, config, priority, &tag)?;
 
-    let valid_access: Vec<String> = vec![ACCESS_PUBLIC_STRING, ACCESS_OWNERS_STRING, ACCESS_CLEARED_STRING]
-        .into_iter()
-        .map(|s| String::from(s))
-        .collect();
-    if !valid_access.contains(&access) {
-        return Err(StdError::generic_err(format!("{} is not a valid access option", access)));
-    }
-    //if config.admin != sender_raw {
-    //    return Err(StdError::generic_err(
-    //        "This is an admin command and can only be run from the admin address",
-    //    ));
-    //}
+    move_package_tag(deps.storage, package_id, tag, index)?;
 
-    let index = store_package_version(deps.storage, package_id, data, tags, metadata, access)?;
-    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::UploadPackageVersion { index })?))
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::MovePackageTag { status: Success })?))
 }
 
 /// Returns StdResult<Response>
 ///
-/// stores a new version of a package
+/// removes a tag from a package
 ///
 /// # Arguments
 ///
 /// * `deps` - mutable reference to Extern containing all the contract's external dependencies
-/// * `sender` - addr of sender 
-/// * `config` - config 
+/// * `sender` - addr of sender
+/// * `config` - config
 /// * `priority` - u8 representation of highest ContractStatus level this action is permitted
 /// * `package_id` - id of the package
-/// * `version` - index of the version
-/// * `tags` - optional vec of tags
-fn add_package_tags(
+/// * `tag` - tag to remove
+fn remove_tag(
     deps: DepsMut,
     sender: &Addr,
     config: &Config,
     priority: u8,
     package_id: String,
-    index: u32,
+    tag: String,
+) -> StdResult<Response> {
+    check_package_tagger(deps.as_ref(), sender, config, priority, &tag)?;
+
+    remove_package_tag(deps.storage, package_id, tag)?;
+
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::RemovePackageTag { status: Success })?))
+}
+
+/// Returns StdResult<Response>
+///
+/// sets the tags that only the admin can set, move or remove
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `sender` - addr of sender
+/// * `config` - config
+/// * `priority` - u8 representation of highest ContractStatus level this action is permitted
+/// * `tags` - protected tags
+fn set_protected_package_tags(
+    deps: DepsMut,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
     tags: Vec<String>,
 ) -> StdResult<Response> {
     check_status(config.status, priority)?;
@@ -1750,9 +2414,66 @@ fn add_package_tags(
         ));
     }
 
-    add_tags_to_package_version(deps.storage, package_id, index, tags)?;
+    PROTECTED_TAGS.save(deps.storage, &tags)?;
 
-    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::AddPackageTags { status: Success })?))
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::SetProtectedPackageTags { status: Success })?))
+}
+
+/// Returns StdResult<()> after checking that the sender may move or remove a package tag.
+/// The admin can change any tag, minters can change tags that are not protected
+///
+/// # Arguments
+///
+/// * `deps` - a reference to Extern containing all the contract's external dependencies
+/// * `sender` - addr of sender
+/// * `config` - config
+/// * `priority` - u8 representation of highest ContractStatus level this action is permitted
+/// * `tag` - tag being changed
+fn check_package_tagger(
+    deps: Deps,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    tag: &String,
+) -> StdResult<()> {
+    check_status(config.status, priority)?;
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    if config.admin == sender_raw {
+        return Ok(());
+    }
+    let minters: Vec<CanonicalAddr> = may_load(deps.storage, MINTERS_KEY)?.unwrap_or_default();
+    if !minters.contains(&sender_raw) {
+        return Err(StdError::generic_err(
+            "Only designated minters can change package tags",
+        ));
+    }
+    check_protected_tags(deps.storage, &sender_raw, config, std::slice::from_ref(tag))
+}
+
+/// Returns StdResult<()> after checking that only the admin is setting protected tags
+///
+/// # Arguments
+///
+/// * `storage` - a reference to the contract's storage
+/// * `sender_raw` - canonical addr of sender
+/// * `config` - config
+/// * `tags` - tags being set
+fn check_protected_tags(
+    storage: &dyn Storage,
+    sender_raw: &CanonicalAddr,
+    config: &Config,
+    tags: &[String],
+) -> StdResult<()> {
+    if config.admin == *sender_raw {
+        return Ok(());
+    }
+    let protected_tags = get_protected_tags(storage)?;
+    if let Some(tag) = tags.iter().find(|tag| protected_tags.contains(tag)) {
+        return Err(StdError::generic_err(format!(
+            "`{}` is a protected tag that only the admin can set", tag
+        )));
+    }
+    Ok(())
 }
 
 /////////////////////////////////////// Query /////////////////////////////////////
@@ -1868,12 +2589,13 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             viewing_key,
             page,
             page_size,
+            filter,
         } => {
             let viewer = Some(ViewerInfo {
                 address,
                 viewing_key,
             });
-            query_transactions(deps, viewer, page, page_size, None)
+            query_transactions(deps, viewer, page, page_size, filter, None)
         }
         QueryMsg::RegisteredCodeHash { contract } => query_code_hash(deps, &contract),
         // NFP
@@ -1922,6 +2644,7 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             index, 
             token_id, 
             viewer,
+            if_none_match,
         } => {
             let viewer_with_address: Option<ViewerInfo>;
             if let Some(viewer) = viewer {
@@ -1940,7 +2663,9 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
                 viewer_with_address = None;
             }
             
-            query_package_version(deps, &env.block, viewer_with_address, None, &token_id, package_id, tag, index)
+            query_package_version(
+                deps, &env.block, viewer_with_address, None, &token_id, package_id, tag, index, if_none_match,
+            )
         }
         QueryMsg::PackageInfo { package_id, page, page_size, viewer} => {
             if let Some(viewer) = viewer.as_ref() {
@@ -1948,6 +2673,10 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             }
             query_package_info(deps, package_id, page, page_size, viewer, None)
         }
+        QueryMsg::ResolvePackage { package_id, tag, index } => {
+            query_resolve_package(deps, package_id, tag, index)
+        }
+        QueryMsg::ProtectedPackageTags {} => query_protected_package_tags(deps.storage),
         // Battleship
         QueryMsg::ListGames { 
             page_size, 
@@ -1964,6 +2693,16 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             let address_raw = deps.api.addr_canonicalize(viewer.address.as_str())?;
             query_active_games(deps, token_id, &address_raw)
         }
+        QueryMsg::FinishedGames {
+            page_size,
+            page,
+            token_id,
+            viewer,
+        } => {
+            ViewingKey::check(deps.storage, &viewer.address, &viewer.viewing_key)?;
+            let address_raw = deps.api.addr_canonicalize(viewer.address.as_str())?;
+            query_finished_games(deps, token_id, page, page_size, &address_raw)
+        }
         QueryMsg::GameState { 
             token_id,
             game_id, 
@@ -1974,6 +2713,20 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             query_game_state(deps, token_id, game_id, &address_raw)
         }
 
+        QueryMsg::LobbyTiers {} => query_lobby_tiers(deps),
+        QueryMsg::Seasons { page_size, page } => query_seasons(deps, page, page_size),
+        QueryMsg::SeasonStandings {
+            season_id,
+            page_size,
+            page,
+        } => query_season_standings(deps, season_id, page, page_size),
+        QueryMsg::Achievements {} => query_achievements(deps),
+        QueryMsg::TokenAchievements { token_id, viewer } => {
+            ViewingKey::check(deps.storage, &viewer.address, &viewer.viewing_key)?;
+            let address_raw = deps.api.addr_canonicalize(viewer.address.as_str())?;
+            query_token_achievements(deps, token_id, &address_raw)
+        }
+
         // SNIP-52
         QueryMsg::ListChannels{} => query_list_channels(deps),
         QueryMsg::ChannelInfo { channels, viewer } => {
@@ -1982,6 +2735,11 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             let sender_raw = deps.api.addr_canonicalize(viewer.address.as_str())?;
             query_channel_info(deps, &env, channels, sender_raw)
         },
+        QueryMsg::NotificationInbox { channel, since_counter, viewer } => {
+            ViewingKey::check(deps.storage, &viewer.address, &viewer.viewing_key)?;
+            let sender_raw = deps.api.addr_canonicalize(viewer.address.as_str())?;
+            query_notification_inbox(deps, channel, since_counter, sender_raw)
+        },
 
         QueryMsg::WithPermit { permit, query } => permit_queries(deps, &env, permit, query),
     };
@@ -2045,8 +2803,8 @@ pub fn permit_queries(
         QueryWithPermit::InventoryApprovals { include_expired } => {
             query_inventory_approvals(deps, block, None, include_expired, Some(querier))
         }
-        QueryWithPermit::TransactionHistory { page, page_size } => {
-            query_transactions(deps, None, page, page_size, Some(querier))
+        QueryWithPermit::TransactionHistory { page, page_size, filter } => {
+            query_transactions(deps, None, page, page_size, filter, Some(querier))
         }
         QueryWithPermit::NumTokens {} => query_num_tokens(deps, None, Some(querier)),
         QueryWithPermit::AllTokens { start_after, limit } => {
@@ -2089,8 +2847,10 @@ pub fn permit_queries(
         QueryWithPermit::TokenDelegateApprovals { token_id } => {
             query_token_delegate_approvals(deps, None, Some(querier), token_id)
         }
-        QueryWithPermit::PackageVersion { package_id, tag, index, token_id } => {
-            query_package_version(deps, &env.block, None, Some(querier), &token_id, package_id, tag, index)
+        QueryWithPermit::PackageVersion { package_id, tag, index, token_id, if_none_match } => {
+            query_package_version(
+                deps, &env.block, None, Some(querier), &token_id, package_id, tag, index, if_none_match,
+            )
         }
         QueryWithPermit::PackageInfo { package_id, page, page_size } => {
             query_package_info(deps, package_id, page, page_size, None, Some(querier))
@@ -2101,11 +2861,20 @@ pub fn permit_queries(
         QueryWithPermit::ActiveGames { token_id } => {
             query_active_games(deps, token_id, &querier)
         }
+        QueryWithPermit::FinishedGames { token_id, page_size, page } => {
+            query_finished_games(deps, token_id, page, page_size, &querier)
+        }
         QueryWithPermit::GameState { token_id, game_id } => {
             query_game_state(deps, token_id, game_id, &querier)
         }
+        QueryWithPermit::TokenAchievements { token_id } => {
+            query_token_achievements(deps, token_id, &querier)
+        }
         // SNIP-52
-        QueryWithPermit::ChannelInfo { channels } => query_channel_info(deps, &env, channels, querier)
+        QueryWithPermit::ChannelInfo { channels } => query_channel_info(deps, env, channels, querier),
+        QueryWithPermit::NotificationInbox { channel, since_counter } => {
+            query_notification_inbox(deps, channel, since_counter, querier)
+        }
     }
 }
 
@@ -2992,12 +3761,14 @@ pub fn query_num_owner_tokens(
 /// * `page` - an optional page number.  If given, the most recent `page` times `page_size`
 ///            transactions will be skipped
 /// * `page_size` - optional max number of transactions to display
+/// * `filter` - optional list of transaction types to display
 /// * `from_permit` - address derived from an Owner permit, if applicable
 pub fn query_transactions(
     deps: Deps,
     viewer: Option<ViewerInfo>,
     page: Option<u32>,
     page_size: Option<u32>,
+    filter: Option<Vec<TxActionFilter>>,
     from_permit: Option<CanonicalAddr>,
 ) -> StdResult<Binary> {
     let address_raw = get_querier(deps, viewer, from_permit)?.ok_or_else(|| {
@@ -3009,6 +3780,7 @@ pub fn query_transactions(
         &address_raw,
         page.unwrap_or(0),
         page_size.unwrap_or(30),
+        filter.as_deref(),
     )?;
     to_binary(&QueryAnswer::TransactionHistory { total, txs })
 }
@@ -3302,6 +4074,7 @@ pub fn query_token_delegate_approvals(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn query_package_version(
     deps: Deps,
     block: &BlockInfo,
@@ -3311,6 +4084,7 @@ pub fn query_package_version(
     package_id: String,
     tag: Option<String>,
     index: Option<u32>,
+    if_none_match: Option<Binary>,
 ) -> StdResult<Binary> {
     if tag.is_some() && index.is_some() {
         return Err(StdError::generic_err("Must have only one of `tag` or `index`"));
@@ -3402,6 +4176,14 @@ pub fn query_package_version(
         return Err(StdError::generic_err("type4 + ".to_owned() + no_permission_err));
     }
 
+    // only after the access checks, so the hash does not leak
+    let hash = package.as_ref().and_then(|package| package.hash.clone());
+    if let (Some(hash), Some(cached)) = (hash, if_none_match) {
+        if hash == cached {
+            return to_binary(&QueryAnswer::PackageVersionNotModified { hash });
+        }
+    }
+
     to_binary(&QueryAnswer::PackageVersion { package })
 }
 
@@ -3466,6 +4248,37 @@ pub fn query_package_info(
     to_binary(&QueryAnswer::PackageInfo { info, version_count })
 }
 
+/// Returns StdResult<Binary> displaying the dependency closure of a package version in load
+/// order.  Only ids, indices and manifest entries are returned, the package data still has to
+/// be queried with PackageVersion
+///
+/// # Arguments
+///
+/// * `deps` - a reference to Extern containing all the contract's external dependencies
+/// * `package_id` - id of the package
+/// * `tag` - optional tag of the version
+/// * `index` - optional index of the version
+pub fn query_resolve_package(
+    deps: Deps,
+    package_id: String,
+    tag: Option<String>,
+    index: Option<u32>,
+) -> StdResult<Binary> {
+    let index = find_package_index(deps.storage, &package_id, tag, index)?;
+    let packages = resolve_package(deps.storage, &package_id, index)?;
+    to_binary(&QueryAnswer::ResolvePackage { packages })
+}
+
+/// Returns StdResult<Binary> displaying the tags only the admin can set, move or remove
+///
+/// # Arguments
+///
+/// * `storage` - a reference to the contract's storage
+pub fn query_protected_package_tags(storage: &dyn Storage) -> StdResult<Binary> {
+    let tags = get_protected_tags(storage)?;
+    to_binary(&QueryAnswer::ProtectedPackageTags { tags })
+}
+
 /// Returns StdResult<(Option<Addr>, Vec<Cw721Approval>, u32)> which is the owner, list of transfer
 /// approvals, and token index of the request token
 ///
@@ -3952,7 +4765,7 @@ pub fn get_token(
 ///
 /// * `contract_status` - u8 representation of the current contract status
 /// * `priority` - u8 representing the highest status level this action may execute at
-fn check_status(contract_status: u8, priority: u8) -> StdResult<()> {
+pub(crate) fn check_status(contract_status: u8, priority: u8) -> StdResult<()> {
     if priority < contract_status {
         return Err(StdError::generic_err(
             "The contract admin has temporarily disabled this action",
@@ -4620,6 +5433,7 @@ fn transfer_impl(
             token_id
         )));
     }
+    check_wager_lock(deps.storage, &token_id)?;
     let old_owner = token.owner;
     // throw error if ownership would not change
     if old_owner == recipient {
@@ -4630,6 +5444,12 @@ fn transfer_impl(
     }
     token.owner = recipient.clone();
     token.permissions.clear();
+    // games the token is playing now belong to the recipient
+    update_active_game_owners(deps.storage, &token_id, &recipient)?;
+    // lobby subscriptions were made by the old owner
+    clear_lobby_subscriptions(deps.storage, &token_id)?;
+    // as were the notification delegates
+    clear_notification_delegates(deps.storage, &token_id);
 
     let update_addrs = vec![recipient.clone(), old_owner.clone()];
     // save updated token info
@@ -4688,9 +5508,13 @@ pub struct SendFrom {
     pub token_ids: Vec<String>,
 }
 
-/// Returns StdResult<Vec<CosmosMsg>>
+// ReceiveNft callbacks and notification (id, encrypted data) pairs of a send_list
+type SendListOutput = (Vec<CosmosMsg>, Vec<(Binary, Binary)>);
+
+/// Returns StdResult<(Vec<CosmosMsg>, Vec<(Binary, Binary)>)>
 ///
-/// transfer or sends a list of tokens and returns a list of ReceiveNft callbacks if applicable
+/// transfer or sends a list of tokens and returns a list of ReceiveNft callbacks if applicable,
+/// along with the notifications of the transfers
 ///
 /// # Arguments
 ///
@@ -4707,8 +5531,9 @@ fn send_list(
     config: &mut Config,
     transfers: Option<Vec<Transfer>>,
     sends: Option<Vec<Send>>,
-) -> StdResult<Vec<CosmosMsg>> {
+) -> StdResult<SendListOutput> {
     let mut messages: Vec<CosmosMsg> = Vec::new();
+    let mut transferred: Vec<TransferredToken> = Vec::new();
     let mut oper_for: Vec<CanonicalAddr> = Vec::new();
     let mut inv_updates: Vec<InventoryUpdate> = Vec::new();
     let num_perm_types = PermissionType::ViewOwner.num_types();
@@ -4719,17 +5544,22 @@ fn send_list(
                 .api
                 .addr_canonicalize(deps.api.addr_validate(&xfer.recipient)?.as_str())?;
             for token_id in xfer.token_ids.into_iter() {
-                let _o = transfer_impl(
+                let owner_raw = transfer_impl(
                     &mut deps,
                     &env.block,
                     config,
                     &sender,
-                    token_id,
+                    token_id.clone(),
                     recipient_raw.clone(),
                     &mut oper_for,
                     &mut inv_updates,
                     xfer.memo.clone(),
                 )?;
+                transferred.push(TransferredToken {
+                    token_id,
+                    from: owner_raw,
+                    to: recipient_raw.clone(),
+                });
             }
         }
     } else if let Some(snds) = sends {
@@ -4751,6 +5581,11 @@ fn send_list(
                     &mut inv_updates,
                     send.memo.clone(),
                 )?;
+                transferred.push(TransferredToken {
+                    token_id: token_id.clone(),
+                    from: owner_raw.clone(),
+                    to: contract_raw.clone(),
+                });
                 // compile list of all tokens being sent from each owner in this Send
                 if let Some(sd_fm) = send_from_list.iter_mut().find(|s| s.owner == owner_raw) {
                     sd_fm.token_ids.push(token_id.clone());
@@ -4777,7 +5612,138 @@ fn send_list(
     }
     save(deps.storage, CONFIG_KEY, &config)?;
     update_owner_inventory(deps.storage, &inv_updates, num_perm_types)?;
-    Ok(messages)
+    let notifications = transfer_notifications(&mut deps, env, msg_sender, &transferred)?;
+    Ok((messages, notifications))
+}
+
+// a token that changed owners in this tx
+pub struct TransferredToken {
+    // the token's id
+    pub token_id: String,
+    // the previous owner
+    pub from: CanonicalAddr,
+    // the new owner
+    pub to: CanonicalAddr,
+}
+
+/// Returns StdResult<Vec<(Binary, Binary)>>
+///
+/// renders the `nft_received` notification to the new owner and the `nft_sent` notification
+/// to the previous owner of each transferred token, for whichever of those channels are
+/// registered
+///
+/// # Arguments
+///
+/// * `deps` - a mutable reference to Extern containing all the contract's external dependencies
+/// * `env` - a reference to the Env of the contract's environment
+/// * `sender` - a reference to the message sender's address
+/// * `transferred` - the tokens that changed owners
+fn transfer_notifications(
+    deps: &mut DepsMut,
+    env: &Env,
+    sender: &Addr,
+    transferred: &[TransferredToken],
+) -> StdResult<Vec<(Binary, Binary)>> {
+    let received = channels_store().contains(deps.storage, &NFT_RECEIVED_CHANNEL_ID.to_string());
+    let sent = channels_store().contains(deps.storage, &NFT_SENT_CHANNEL_ID.to_string());
+    let mut notifications = vec![];
+    for xfer in transferred.iter() {
+        let from = deps.api.addr_humanize(&xfer.from)?.into_string();
+        let to = deps.api.addr_humanize(&xfer.to)?.into_string();
+        if received {
+            let data = cbor::to_vec(&(&xfer.token_id, &from))
+                .map_err(|e| StdError::generic_err(format!("{:?}", e)))?;
+            notifications.push(render_notification(
+                deps.storage,
+                env,
+                sender,
+                &xfer.to,
+                NFT_RECEIVED_CHANNEL_ID,
+                data,
+            )?);
+        }
+        if sent {
+            let data = cbor::to_vec(&(&xfer.token_id, &to))
+                .map_err(|e| StdError::generic_err(format!("{:?}", e)))?;
+            notifications.push(render_notification(
+                deps.storage,
+                env,
+                sender,
+                &xfer.from,
+                NFT_SENT_CHANNEL_ID,
+                data,
+            )?);
+        }
+    }
+    Ok(notifications)
+}
+
+/// `approval_changed` kind for SNIP-721 transfer approvals
+pub const APPROVAL_KIND_TRANSFER: &str = "transfer";
+/// `approval_changed` kind for NFP storage delegation
+pub const APPROVAL_KIND_STORAGE_DELEGATE: &str = "storage_delegate";
+
+// an approval that was granted or revoked in this tx
+pub struct ApprovalChange<'a> {
+    // either APPROVAL_KIND_TRANSFER or APPROVAL_KIND_STORAGE_DELEGATE
+    pub kind: &'a str,
+    // the tokens it applies to, or empty for all the owner's tokens
+    pub token_ids: Vec<String>,
+    // the owner granting or revoking the approval
+    pub owner: &'a CanonicalAddr,
+    // the address being approved or revoked
+    pub spender: &'a CanonicalAddr,
+    // true if the approval was granted
+    pub approved: bool,
+}
+
+/// Returns StdResult<Vec<(Binary, Binary)>>
+///
+/// renders the `approval_changed` notification to each recipient, if that channel is
+/// registered
+///
+/// # Arguments
+///
+/// * `deps` - a mutable reference to Extern containing all the contract's external dependencies
+/// * `env` - a reference to the Env of the contract's environment
+/// * `sender` - a reference to the message sender's address
+/// * `change` - a reference to the approval that changed
+/// * `recipients` - the addresses to notify.  Repeated addresses are only notified once
+fn approval_changed_notifications(
+    deps: &mut DepsMut,
+    env: &Env,
+    sender: &Addr,
+    change: &ApprovalChange,
+    recipients: &[&CanonicalAddr],
+) -> StdResult<Vec<(Binary, Binary)>> {
+    if !channels_store().contains(deps.storage, &APPROVAL_CHANGED_CHANNEL_ID.to_string()) {
+        return Ok(vec![]);
+    }
+    let data = cbor::to_vec(&(
+        change.kind,
+        &change.token_ids,
+        deps.api.addr_humanize(change.owner)?.into_string(),
+        deps.api.addr_humanize(change.spender)?.into_string(),
+        change.approved,
+    ))
+    .map_err(|e| StdError::generic_err(format!("{:?}", e)))?;
+    let mut notifications = vec![];
+    let mut notified: Vec<&CanonicalAddr> = vec![];
+    for recipient in recipients.iter() {
+        if notified.contains(recipient) {
+            continue;
+        }
+        notifications.push(render_notification(
+            deps.storage,
+            env,
+            sender,
+            recipient,
+            APPROVAL_CHANGED_CHANNEL_ID,
+            data.clone(),
+        )?);
+        notified.push(recipient);
+    }
+    Ok(notifications)
 }
 
 /// Returns <Vec<String>>
@@ -4791,12 +5757,14 @@ fn send_list(
 /// * `config` - a mutable reference to the Config
 /// * `sender_raw` - a reference to the message sender address
 /// * `mints` - list of mints to perform
-fn mint_list(
+/// * `badges` - true if the tokens are achievement badges, which get no battleship board
+pub(crate) fn mint_list(
     deps: DepsMut,
     env: &Env,
     config: &mut Config,
     sender_raw: &CanonicalAddr,
     mints: Vec<Mint>,
+    badges: bool,
 ) -> StdResult<Vec<String>> {
     let mut inventories: Vec<Inventory> = Vec::new();
     let mut minted: Vec<String> = Vec::new();
@@ -4856,6 +5824,10 @@ fn mint_list(
         // If you wanted to store an additional data struct for each NFT, you would create
         // a new prefix and store with the `token_key` like below
         //
+        // badges do not get a battleship board, and are marked so they can not play
+        if badges {
+            BADGE_TOKEN_STORE.add_suffix(id.as_bytes()).save(deps.storage, &true)?;
+        }
         // save the metadata
         if let Some(pub_meta) = mint.public_metadata {
             enforce_metadata_field_exclusion(&pub_meta)?;
@@ -4910,7 +5882,8 @@ fn mint_list(
 
             // add battleship raw data from template
             if let Some(mut extension) = priv_meta.extension {
-                if let Some(template) = SVG_TEMPLATE.may_load(deps.storage)? {
+                let template = if badges { None } else { SVG_TEMPLATE.may_load(deps.storage)? };
+                if let Some(template) = template {
                     let template = template.replace("@{TOKEN_ID}", id.as_str());
                     let svg = RawData {
                         bytes: Binary::from(template.as_bytes()),
@@ -5311,3 +6284,104 @@ pub fn dossier_list(
     }
     Ok(dossiers)
 }
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::*;
+    use cosmwasm_std::from_binary;
+    use crate::snip52_exec_query::{notification_id, query_channel_info};
+    use crate::snip52_state::get_count;
+    use crate::test_helpers::init_helper_default;
+    use super::*;
+
+    #[test]
+    fn test_nft_notifications() {
+        let (init_result, mut deps) = init_helper_default();
+        init_result.unwrap();
+        let execute_msg = ExecuteMsg::MintNft {
+            token_id: Some("NFT1".to_string()),
+            owner: Some("alice".to_string()),
+            public_metadata: None,
+            private_metadata: None,
+            royalty_info: None,
+            serial_number: None,
+            transferable: None,
+            memo: None,
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), execute_msg).unwrap();
+        let nft_received = NFT_RECEIVED_CHANNEL_ID.to_string();
+        let nft_sent = NFT_SENT_CHANNEL_ID.to_string();
+        let approval_changed = APPROVAL_CHANGED_CHANNEL_ID.to_string();
+        let alice_raw = deps.api.addr_canonicalize("alice").unwrap();
+        let bob_raw = deps.api.addr_canonicalize("bob").unwrap();
+        let carol_raw = deps.api.addr_canonicalize("carol").unwrap();
+        let dave_raw = deps.api.addr_canonicalize("dave").unwrap();
+
+        // the owner and the spender hear about an approval
+        let execute_msg = ExecuteMsg::Approve {
+            spender: "bob".to_string(),
+            token_id: "NFT1".to_string(),
+            expires: None,
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), execute_msg).unwrap();
+        assert_eq!(get_count(&deps.storage, &approval_changed, &alice_raw), 1);
+        assert_eq!(get_count(&deps.storage, &approval_changed, &bob_raw), 1);
+
+        // a transfer by the spender notifies the new and previous owners
+        let received_id = notification_id(&deps.storage, &carol_raw, &nft_received).unwrap();
+        let sent_id = notification_id(&deps.storage, &alice_raw, &nft_sent).unwrap();
+        let execute_msg = ExecuteMsg::TransferNft {
+            recipient: "carol".to_string(),
+            token_id: "NFT1".to_string(),
+            memo: None,
+            padding: None,
+        };
+        let response = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), execute_msg).unwrap();
+        assert!(response.attributes.iter().any(|attr| attr.key == received_id.to_base64()));
+        assert!(response.attributes.iter().any(|attr| attr.key == sent_id.to_base64()));
+        assert_eq!(get_count(&deps.storage, &nft_received, &bob_raw), 0);
+
+        // storage delegates hear when they are approved and revoked
+        let execute_msg = ExecuteMsg::ApproveOwnerDelegate {
+            address: "dave".to_string(),
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), execute_msg).unwrap();
+        assert_eq!(get_count(&deps.storage, &approval_changed, &dave_raw), 1);
+        let execute_msg = ExecuteMsg::RevokeAllDelegates { padding: None };
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), execute_msg).unwrap();
+        assert_eq!(get_count(&deps.storage, &approval_changed, &dave_raw), 2);
+        assert_eq!(get_count(&deps.storage, &approval_changed, &carol_raw), 0);
+    }
+
+    #[test]
+    fn test_channel_data_len() {
+        let (init_result, deps) = init_helper_default();
+        init_result.unwrap();
+        let channels = [
+            (GAME_UPDATED_CHANNEL_ID, GAME_UPDATED_CHANNEL_DATA_LEN),
+            (GAME_DELTA_CHANNEL_ID, GAME_DELTA_CHANNEL_DATA_LEN),
+            (GAME_LISTED_CHANNEL_ID, GAME_LISTED_CHANNEL_DATA_LEN),
+            (PLAYER_JOINED_CHANNEL_ID, PLAYER_JOINED_CHANNEL_DATA_LEN),
+            (OPPONENT_ATTACKED_CHANNEL_ID, OPPONENT_ATTACKED_CHANNEL_DATA_LEN),
+            (NFT_RECEIVED_CHANNEL_ID, NFT_RECEIVED_CHANNEL_DATA_LEN),
+            (NFT_SENT_CHANNEL_ID, NFT_SENT_CHANNEL_DATA_LEN),
+            (APPROVAL_CHANGED_CHANNEL_ID, APPROVAL_CHANGED_CHANNEL_DATA_LEN),
+        ];
+        let alice_raw = deps.api.addr_canonicalize("alice").unwrap();
+        let ids = channels.iter().map(|(id, _)| id.to_string()).collect();
+        let info = query_channel_info(deps.as_ref(), &mock_env(), ids, alice_raw).unwrap();
+        match from_binary(&info).unwrap() {
+            QueryAnswer::ChannelInfo { channels: infos, .. } => {
+                assert_eq!(infos.len(), channels.len());
+                for (info, (id, data_len)) in infos.iter().zip(channels) {
+                    assert_eq!(info.channel, id);
+                    assert_eq!(info.data_len, data_len);
+                }
+            }
+            _ => panic!("unexpected"),
+        }
+    }
+}
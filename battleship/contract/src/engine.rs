@@ -0,0 +1,606 @@
+use std::convert::TryFrom;
+use thiserror::Error;
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use cosmwasm_std::StdError;
+
+pub const BOARD_SIZE: usize = 100; // 100
+pub const CARRIER_SIZE: u8 = 5;
+pub const BATTLESHIP_SIZE: u8 = 4;
+pub const CRUISER_SIZE: u8 = 3;
+pub const SUBMARINE_SIZE: u8 = 3;
+pub const DESTROYER_SIZE: u8 = 2;
+
+/// number of cells in a row of the board
+pub const BOARD_WIDTH: usize = 10;
+/// every ship in a fleet with its size
+pub const FLEET: [(CellValue, u8); 5] = [
+    (CellValue::Carrier, CARRIER_SIZE),
+    (CellValue::Battleship, BATTLESHIP_SIZE),
+    (CellValue::Cruiser, CRUISER_SIZE),
+    (CellValue::Submarine, SUBMARINE_SIZE),
+    (CellValue::Destroyer, DESTROYER_SIZE),
+];
+/// number of hits needed to sink a whole fleet
+pub const FLEET_CELLS: u8 = CARRIER_SIZE + BATTLESHIP_SIZE + CRUISER_SIZE + SUBMARINE_SIZE + DESTROYER_SIZE;
+
+/// Distinguishes to a player which role they fulfil
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug, JsonSchema)]
+#[repr(u8)]
+pub enum PlayerRole {
+    /// this player initiated the game
+    Initiator = 0,
+    /// this player joined the game
+    Joiner = 1,
+}
+
+/// Describes the state of an initiated game (fits into u8)
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug, JsonSchema)]
+#[repr(u8)]
+pub enum TurnState {
+    /// waiting for another player to join
+    WaitingForPlayer = 0,
+    /// waiting for both players to submit their setups
+    WaitingForBothPlayersSetup = 1,
+    /// waiting for only the initiator to submit their setup
+    WaitingForInitiatorSetup = 2,
+    /// waiting for only the joiner to submit their setup
+    WaitingForJoinerSetup = 3,
+    /// waiting for the player who initiated to submit their move
+    InitiatorsTurn = 4,
+    /// waiting for the player who joined to submit their move
+    JoinersTurn = 5,
+    /// player who initiated won
+    GameOverInitiatorWon = 6,
+    /// player who joined won
+    GameOverJoinerWon = 7,
+    /// game was aborted by the admin and the wagers refunded
+    GameAborted = 8,
+}
+
+/// Describes the occupancy of a cell (fits into u8)
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, JsonSchema)]
+#[repr(u8)]
+pub enum CellValue {
+    /// nothing occupies the cell. used for both `home` and `away` grids
+    Empty = 0,
+    /// for `away` grid only: player missed the cell
+    Miss = 1,
+    /// part of the "Carrier" vessel occupies the cell
+    Carrier = 2,
+    /// part of the "Battleship" vessel occupies the cell
+    Battleship = 3,
+    /// part of the "Cruiser" vessel occupies the cell
+    Cruiser = 4,
+    /// part of the "Submarine" vessel occupies the cell
+    Submarine = 5,
+    /// part of the "Destroyer" vessel occupies the cell
+    Destroyer = 6,
+    /// bitmask: the cell contains a vessel that has been it
+    Hit = 0x80,
+}
+
+// Implement TryFrom<u8> for CellValue (fallible)
+impl TryFrom<u8> for CellValue {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(CellValue::Empty),
+            1 => Ok(CellValue::Miss),
+            2 => Ok(CellValue::Carrier),
+            3 => Ok(CellValue::Battleship),
+            4 => Ok(CellValue::Cruiser),
+            5 => Ok(CellValue::Submarine),
+            6 => Ok(CellValue::Destroyer),
+            0x80 => Ok(CellValue::Hit),
+            _ => Err(()), // Return an error for invalid values
+        }
+    }
+}
+
+/// what an attack did to the recipient's home board
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AttackResult {
+    pub cell: u8,
+    /// CellValue::Miss or CellValue::Hit
+    pub result: u8,
+    /// the type of ship sunk by this attack, if any
+    pub sunk: Option<u8>,
+}
+
+/// A move that breaks the rules of the game
+#[derive(Error, Clone, PartialEq, Eq, Debug)]
+pub enum RuleError {
+    #[error("Not a valid battleship setup")]
+    InvalidSetup,
+    #[error("You already submitted a setup")]
+    SetupAlreadySubmitted,
+    #[error("Cell index is out of bounds")]
+    CellOutOfBounds,
+    #[error("Not your turn to attack")]
+    NotYourTurn,
+    #[error("Opponent has not submitted a setup")]
+    MissingSetup,
+    #[error("You have already attacked this cell")]
+    CellAlreadyAttacked,
+    #[error("Invalid cell value")]
+    InvalidCellValue,
+}
+
+impl From<RuleError> for StdError {
+    fn from(err: RuleError) -> Self {
+        StdError::generic_err(err.to_string())
+    }
+}
+
+/// The part of a game the rules act on
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GameState {
+    /// TurnState
+    pub turn: u8,
+    pub initiator_goes_first: bool,
+    /// None until the initiator submits their setup
+    pub initiator_home: Option<Vec<u8>>,
+    /// None until the joiner submits their setup
+    pub joiner_home: Option<Vec<u8>>,
+}
+
+impl GameState {
+    /// returns the state of a game both players have just joined
+    pub fn new(initiator_goes_first: bool) -> Self {
+        GameState {
+            turn: TurnState::WaitingForBothPlayersSetup as u8,
+            initiator_goes_first,
+            initiator_home: None,
+            joiner_home: None,
+        }
+    }
+
+    /// returns the home board of the player with the given role
+    pub fn home(&self, role: &PlayerRole) -> Option<&[u8]> {
+        match role {
+            PlayerRole::Initiator => self.initiator_home.as_deref(),
+            PlayerRole::Joiner => self.joiner_home.as_deref(),
+        }
+    }
+
+    fn home_mut(&mut self, role: &PlayerRole) -> &mut Option<Vec<u8>> {
+        match role {
+            PlayerRole::Initiator => &mut self.initiator_home,
+            PlayerRole::Joiner => &mut self.joiner_home,
+        }
+    }
+}
+
+/// What an attack did to the game
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AttackOutcome {
+    pub attack: AttackResult,
+    /// true if the attack sank the last ship
+    pub won: bool,
+}
+
+/// returns the opponent of a player with the given role
+pub fn opponent_of(role: &PlayerRole) -> PlayerRole {
+    match role {
+        PlayerRole::Initiator => PlayerRole::Joiner,
+        PlayerRole::Joiner => PlayerRole::Initiator,
+    }
+}
+
+/// returns true if the cells hold exactly one of every ship, each laid out in a single
+/// straight row or column, and nothing else
+pub fn valid_setup(cells: &[u8]) -> bool {
+    if cells.len() != BOARD_SIZE {
+        return false;
+    }
+    if cells
+        .iter()
+        .any(|value| *value != CellValue::Empty as u8 && !FLEET.iter().any(|(ship, _)| *value == *ship as u8))
+    {
+        return false;
+    }
+    FLEET.iter().all(|(ship, size)| {
+        let positions: Vec<usize> = cells
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| **value == *ship as u8)
+            .map(|(i, _)| i)
+            .collect();
+        if positions.len() != *size as usize {
+            return false;
+        }
+        let first = positions[0];
+        let horizontal = first % BOARD_WIDTH + positions.len() <= BOARD_WIDTH
+            && positions.iter().enumerate().all(|(j, i)| *i == first + j);
+        let vertical = positions.iter().enumerate().all(|(j, i)| *i == first + j * BOARD_WIDTH);
+        horizontal || vertical
+    })
+}
+
+/// returns the size of the ship occupying a home cell, if any
+pub fn ship_size(value: u8) -> Option<u8> {
+    match CellValue::try_from(value & 0x0F) {
+        Ok(CellValue::Carrier) => Some(CARRIER_SIZE),
+        Ok(CellValue::Battleship) => Some(BATTLESHIP_SIZE),
+        Ok(CellValue::Cruiser) => Some(CRUISER_SIZE),
+        Ok(CellValue::Submarine) => Some(SUBMARINE_SIZE),
+        Ok(CellValue::Destroyer) => Some(DESTROYER_SIZE),
+        _ => None,
+    }
+}
+
+/// derives the opponent's view (`away` grid) of a home board. attacked cells are
+/// marked as Miss or Hit, and the type of a ship is revealed once it has been sunk
+pub fn away_view(home: &[u8]) -> Vec<u8> {
+    let mut hits = [0_u8; 7];
+    for value in home.iter().filter(|value| *value & CellValue::Hit as u8 != 0) {
+        hits[(value & 0x0F) as usize] += 1;
+    }
+    home.iter()
+        .map(|value| {
+            if *value == CellValue::Miss as u8 {
+                CellValue::Miss as u8
+            } else if value & CellValue::Hit as u8 != 0 {
+                let ship_type = value & 0x0F;
+                if Some(hits[ship_type as usize]) == ship_size(ship_type) {
+                    CellValue::Hit as u8 | ship_type
+                } else {
+                    CellValue::Hit as u8
+                }
+            } else {
+                CellValue::Empty as u8
+            }
+        })
+        .collect()
+}
+
+/// returns true once every ship on the home board has been sunk
+pub fn has_won(opponent_home: &[u8]) -> bool {
+    let total_hits = opponent_home
+        .iter()
+        .filter(|value| *value & CellValue::Hit as u8 != 0)
+        .count() as u8;
+    total_hits >= FLEET_CELLS
+}
+
+/// records a player's setup, starting the game once both players have submitted one
+///
+/// # Arguments
+///
+/// * `state` - the game to update
+/// * `role` - role of the player submitting the setup
+/// * `cells` - the player's home board
+pub fn apply_setup(state: &mut GameState, role: &PlayerRole, cells: Vec<u8>) -> Result<(), RuleError> {
+    if !valid_setup(&cells) {
+        return Err(RuleError::InvalidSetup);
+    }
+    let first_mover_turn = match state.initiator_goes_first {
+        true => TurnState::InitiatorsTurn,
+        false => TurnState::JoinersTurn,
+    };
+    let (waiting_for_you, waiting_for_opponent) = match role {
+        PlayerRole::Initiator => (TurnState::WaitingForInitiatorSetup, TurnState::WaitingForJoinerSetup),
+        PlayerRole::Joiner => (TurnState::WaitingForJoinerSetup, TurnState::WaitingForInitiatorSetup),
+    };
+    if state.turn == TurnState::WaitingForBothPlayersSetup as u8 {
+        state.turn = waiting_for_opponent as u8;
+    } else if state.turn == waiting_for_you as u8 {
+        state.turn = first_mover_turn as u8;
+    } else {
+        return Err(RuleError::SetupAlreadySubmitted);
+    }
+    *state.home_mut(role) = Some(cells);
+    Ok(())
+}
+
+/// attacks a cell of the opponent's home board, passing the turn to the opponent or
+/// ending the game if the last ship was sunk
+///
+/// # Arguments
+///
+/// * `state` - the game to update
+/// * `role` - role of the attacking player
+/// * `cell` - index of the attacked cell
+pub fn apply_attack(state: &mut GameState, role: &PlayerRole, cell: u8) -> Result<AttackOutcome, RuleError> {
+    let index = cell as usize;
+    if index >= BOARD_SIZE {
+        return Err(RuleError::CellOutOfBounds);
+    }
+    let (your_turn, opponents_turn, you_won) = match role {
+        PlayerRole::Initiator => (
+            TurnState::InitiatorsTurn,
+            TurnState::JoinersTurn,
+            TurnState::GameOverInitiatorWon,
+        ),
+        PlayerRole::Joiner => (
+            TurnState::JoinersTurn,
+            TurnState::InitiatorsTurn,
+            TurnState::GameOverJoinerWon,
+        ),
+    };
+    if state.turn != your_turn as u8 {
+        return Err(RuleError::NotYourTurn);
+    }
+
+    let opponent_home = state
+        .home_mut(&opponent_of(role))
+        .as_mut()
+        .ok_or(RuleError::MissingSetup)?;
+    let value = opponent_home[index];
+    if value == CellValue::Miss as u8 || value & CellValue::Hit as u8 != 0 {
+        return Err(RuleError::CellAlreadyAttacked);
+    }
+    let attack = if value == CellValue::Empty as u8 {
+        opponent_home[index] = CellValue::Miss as u8;
+        AttackResult { cell, result: CellValue::Miss as u8, sunk: None }
+    } else if let Some(size) = ship_size(value) {
+        opponent_home[index] |= CellValue::Hit as u8;
+        let hits = opponent_home
+            .iter()
+            .filter(|other| **other == value | CellValue::Hit as u8)
+            .count() as u8;
+        AttackResult {
+            cell,
+            result: CellValue::Hit as u8,
+            sunk: if hits == size { Some(value) } else { None },
+        }
+    } else {
+        return Err(RuleError::InvalidCellValue);
+    };
+
+    let won = has_won(opponent_home);
+    state.turn = if won { you_won as u8 } else { opponents_turn as u8 };
+    Ok(AttackOutcome { attack, won })
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use proptest::sample::subsequence;
+    use super::*;
+
+    const SETUP: [u8; BOARD_SIZE] = [
+        0,0,0,0,0,2,2,2,2,2,
+        0,0,0,0,0,0,0,0,0,3,
+        0,0,0,0,0,0,0,0,0,3,
+        0,0,0,0,0,0,0,0,0,3,
+        0,0,0,0,0,0,0,0,0,3,
+        4,4,4,0,0,0,0,0,0,0,
+        0,0,0,0,0,0,0,0,0,0,
+        0,0,5,0,0,0,0,0,0,0,
+        0,0,5,0,0,0,0,0,0,0,
+        0,0,5,0,0,0,0,0,6,6,
+    ];
+
+    /// places each ship of the fleet in turn at (start, vertical) if it fits, returning
+    /// None if a ship would leave the board or overlap another
+    fn place_fleet(placements: &[(usize, bool)]) -> Option<Vec<u8>> {
+        let mut cells = vec![CellValue::Empty as u8; BOARD_SIZE];
+        for ((ship, size), (start, vertical)) in FLEET.iter().zip(placements) {
+            let size = *size as usize;
+            let (row, column) = (start / BOARD_WIDTH, start % BOARD_WIDTH);
+            if (*vertical && row + size > BOARD_WIDTH) || (!*vertical && column + size > BOARD_WIDTH) {
+                return None;
+            }
+            for j in 0..size {
+                let i = if *vertical { start + j * BOARD_WIDTH } else { start + j };
+                if cells[i] != CellValue::Empty as u8 {
+                    return None;
+                }
+                cells[i] = *ship as u8;
+            }
+        }
+        Some(cells)
+    }
+
+    fn fleet() -> impl Strategy<Value = Vec<u8>> {
+        prop::collection::vec((0..BOARD_SIZE, any::<bool>()), FLEET.len())
+            .prop_filter_map("ships overlap or leave the board", |placements| place_fleet(&placements))
+    }
+
+    /// a game waiting for the initiator's first attack
+    fn started_game(initiator_home: Vec<u8>, joiner_home: Vec<u8>) -> GameState {
+        let mut state = GameState::new(true);
+        apply_setup(&mut state, &PlayerRole::Joiner, joiner_home).unwrap();
+        apply_setup(&mut state, &PlayerRole::Initiator, initiator_home).unwrap();
+        state
+    }
+
+    #[test]
+    fn test_setup_turns() {
+        for (first, second, initiator_goes_first, turn) in [
+            (PlayerRole::Initiator, PlayerRole::Joiner, true, TurnState::InitiatorsTurn),
+            (PlayerRole::Joiner, PlayerRole::Initiator, false, TurnState::JoinersTurn),
+        ] {
+            let mut state = GameState::new(initiator_goes_first);
+            apply_setup(&mut state, &first, SETUP.to_vec()).unwrap();
+            assert_eq!(
+                apply_setup(&mut state, &first, SETUP.to_vec()),
+                Err(RuleError::SetupAlreadySubmitted)
+            );
+            assert_eq!(apply_attack(&mut state, &first, 0), Err(RuleError::NotYourTurn));
+            apply_setup(&mut state, &second, SETUP.to_vec()).unwrap();
+            assert_eq!(state.turn, turn as u8);
+            assert_eq!(
+                apply_setup(&mut state, &second, SETUP.to_vec()),
+                Err(RuleError::SetupAlreadySubmitted)
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_ship_placement() {
+        // move each ship of a valid fleet to every position and orientation on the board.
+        // the setup stays valid exactly when the ship fits on the board without overlapping
+        for (ship, size) in FLEET {
+            let size = size as usize;
+            let mut without_ship = SETUP.to_vec();
+            without_ship.iter_mut().filter(|value| **value == ship as u8).for_each(|value| *value = 0);
+            for start in 0..BOARD_SIZE {
+                let (row, column) = (start / BOARD_WIDTH, start % BOARD_WIDTH);
+                for vertical in [false, true] {
+                    let step = if vertical { BOARD_WIDTH } else { 1 };
+                    let on_board = if vertical { row + size <= BOARD_WIDTH } else { column + size <= BOARD_WIDTH };
+                    let mut cells = without_ship.clone();
+                    let mut overlaps = false;
+                    for j in 0..size {
+                        // a ship running off the board is cut off or wraps into the next row
+                        if let Some(value) = cells.get_mut(start + j * step) {
+                            overlaps |= *value != 0;
+                            *value = ship as u8;
+                        }
+                    }
+                    assert_eq!(
+                        valid_setup(&cells),
+                        on_board && !overlaps,
+                        "ship {} at {} vertical {}", ship as u8, start, vertical
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_setups() {
+        assert!(valid_setup(&SETUP));
+        // wrong board size
+        assert!(!valid_setup(&SETUP[..BOARD_SIZE - 1]));
+        // unknown and already attacked values
+        for value in [CellValue::Miss as u8, 7, CellValue::Hit as u8 | CellValue::Carrier as u8] {
+            let mut cells = SETUP.to_vec();
+            cells[0] = value;
+            assert!(!valid_setup(&cells));
+        }
+        // destroyer wrapped around the edge of the board
+        let mut cells = SETUP.to_vec();
+        cells[98] = 0;
+        cells[99] = 0;
+        cells[69] = CellValue::Destroyer as u8;
+        cells[70] = CellValue::Destroyer as u8;
+        assert!(!valid_setup(&cells));
+        // destroyer broken in two
+        cells[70] = 0;
+        cells[71] = CellValue::Destroyer as u8;
+        assert!(!valid_setup(&cells));
+        // destroyer laid diagonally
+        cells[71] = 0;
+        cells[80] = CellValue::Destroyer as u8;
+        assert!(!valid_setup(&cells));
+        cells[80] = 0;
+        cells[79] = CellValue::Destroyer as u8;
+        assert!(valid_setup(&cells));
+        // a single destroyer cell left where a ship used to be
+        let mut cells = SETUP.to_vec();
+        cells[99] = 0;
+        assert!(!valid_setup(&cells));
+        // a ship at the bottom edge of the board that would run off it
+        let mut cells = SETUP.to_vec();
+        cells[98] = 0;
+        cells[99] = 0;
+        cells[94] = CellValue::Destroyer as u8;
+        assert!(!valid_setup(&cells));
+    }
+
+    #[test]
+    fn test_illegal_attacks() {
+        let mut state = started_game(SETUP.to_vec(), SETUP.to_vec());
+        assert_eq!(apply_attack(&mut state, &PlayerRole::Initiator, BOARD_SIZE as u8), Err(RuleError::CellOutOfBounds));
+        assert_eq!(apply_attack(&mut state, &PlayerRole::Joiner, 0), Err(RuleError::NotYourTurn));
+        apply_attack(&mut state, &PlayerRole::Initiator, 0).unwrap();
+        apply_attack(&mut state, &PlayerRole::Joiner, 0).unwrap();
+        assert_eq!(apply_attack(&mut state, &PlayerRole::Initiator, 0), Err(RuleError::CellAlreadyAttacked));
+        apply_attack(&mut state, &PlayerRole::Initiator, 5).unwrap();
+        apply_attack(&mut state, &PlayerRole::Joiner, 1).unwrap();
+        assert_eq!(apply_attack(&mut state, &PlayerRole::Initiator, 5), Err(RuleError::CellAlreadyAttacked));
+
+        // a corrupted board is rejected rather than scored
+        state.joiner_home.as_mut().unwrap()[10] = 7;
+        assert_eq!(apply_attack(&mut state, &PlayerRole::Initiator, 10), Err(RuleError::InvalidCellValue));
+    }
+
+    proptest! {
+        #[test]
+        fn random_fleets_are_valid(cells in fleet()) {
+            prop_assert!(valid_setup(&cells));
+        }
+
+        #[test]
+        fn adding_or_removing_a_ship_cell_breaks_the_setup(cells in fleet(), cell in 0..BOARD_SIZE) {
+            let mut changed = cells.clone();
+            if cells[cell] == CellValue::Empty as u8 {
+                for (ship, _) in FLEET {
+                    changed[cell] = ship as u8;
+                    prop_assert!(!valid_setup(&changed));
+                }
+            } else {
+                changed[cell] = CellValue::Empty as u8;
+                prop_assert!(!valid_setup(&changed));
+            }
+        }
+
+        #[test]
+        fn sinking_every_ship_wins(
+            initiator_home in fleet(),
+            joiner_home in fleet(),
+            order in Just((0..BOARD_SIZE as u8).collect::<Vec<u8>>()).prop_shuffle(),
+        ) {
+            let mut state = started_game(initiator_home, joiner_home.clone());
+            let mut sunk = vec![];
+            let mut hits = 0;
+            for cell in order {
+                let outcome = apply_attack(&mut state, &PlayerRole::Initiator, cell).unwrap();
+                let value = joiner_home[cell as usize];
+                if value == CellValue::Empty as u8 {
+                    prop_assert_eq!(outcome.attack.result, CellValue::Miss as u8);
+                } else {
+                    prop_assert_eq!(outcome.attack.result, CellValue::Hit as u8);
+                    hits += 1;
+                }
+                if let Some(ship) = outcome.attack.sunk {
+                    prop_assert_eq!(ship, value);
+                    prop_assert!(!sunk.contains(&ship));
+                    sunk.push(ship);
+                }
+                prop_assert_eq!(outcome.won, hits == FLEET_CELLS);
+                if outcome.won {
+                    prop_assert_eq!(state.turn, TurnState::GameOverInitiatorWon as u8);
+                    prop_assert_eq!(sunk.len(), FLEET.len());
+                    // nobody can move once the game is over
+                    prop_assert_eq!(apply_attack(&mut state, &PlayerRole::Joiner, 0), Err(RuleError::NotYourTurn));
+                    break;
+                }
+                prop_assert_eq!(state.turn, TurnState::JoinersTurn as u8);
+                // skip the joiner's move
+                state.turn = TurnState::InitiatorsTurn as u8;
+            }
+            // the joiner's board only reveals sunk ships
+            let away = away_view(state.home(&PlayerRole::Joiner).unwrap());
+            for (i, value) in away.iter().enumerate() {
+                if *value & CellValue::Hit as u8 != 0 {
+                    prop_assert_eq!(*value, CellValue::Hit as u8 | joiner_home[i]);
+                }
+            }
+        }
+
+        #[test]
+        fn attacking_a_cell_twice_is_rejected(
+            cells in fleet(),
+            // too few attacks to sink the whole fleet
+            attacked in subsequence((0..BOARD_SIZE as u8).collect::<Vec<u8>>(), 1..FLEET_CELLS as usize),
+        ) {
+            let mut state = started_game(SETUP.to_vec(), cells);
+            for cell in attacked {
+                apply_attack(&mut state, &PlayerRole::Initiator, cell).unwrap();
+                state.turn = TurnState::InitiatorsTurn as u8;
+                let before = state.clone();
+                prop_assert_eq!(
+                    apply_attack(&mut state, &PlayerRole::Initiator, cell),
+                    Err(RuleError::CellAlreadyAttacked)
+                );
+                prop_assert_eq!(&state, &before);
+            }
+        }
+    }
+}
@@ -11,9 +11,18 @@ pub mod token;
 pub mod nfp;
 
 pub mod battleship;
+pub mod engine;
+pub mod seasons;
+pub mod achievements;
 
 pub mod snip52_signed_doc;
 pub mod snip52_crypto;
 pub mod snip52_channel;
 pub mod snip52_state;
-pub mod snip52_exec_query;
\ No newline at end of file
+pub mod snip52_exec_query;
+
+#[cfg(any(feature = "client", test))]
+pub mod client;
+
+#[cfg(test)]
+mod test_helpers;
@@ -1,16 +1,18 @@
 #![allow(clippy::large_enum_variant)]
 
-use cosmwasm_std::{Addr, Binary, Coin, Timestamp, Uint64};
+use cosmwasm_std::{Addr, Binary, Coin, Timestamp, Uint64, Uint128};
 use schemars::JsonSchema;
 use secret_toolkit::permit::Permit;
 use serde::{Deserialize, Serialize};
 
-use crate::battleship::ListedGame;
+use crate::battleship::{ActiveGameSummary, FinishedGame, ListedGame, LobbyTier};
+use crate::seasons::{Season, SeasonStanding};
+use crate::achievements::{Achievement, UnlockedAchievement};
 use crate::expiration::Expiration;
 use crate::mint_run::{MintRunInfo, SerialNumber};
 use crate::nfp::RawData;
 use crate::royalties::{DisplayRoyaltyInfo, RoyaltyInfo};
-use crate::nfp::{PackageVersion, PackageVersionInfo};
+use crate::nfp::{PackageManifest, PackageVersion, PackageVersionInfo, ResolvedPackage};
 use crate::snip52_signed_doc::SignedDocument;
 use crate::token::{Extension, Metadata};
 
@@ -85,6 +87,11 @@ pub struct InstantiateConfig {
     /// Indicates whether minter can modify global storage with `storage_global_put`
     /// default: False
     pub minter_may_put_global_storage: Option<bool>,
+
+    /// SNIP-52
+    /// bech32 prefix of the accounts that sign UpdateSeed documents
+    /// default: "secret"
+    pub bech32_hrp: Option<String>,
 }
 
 impl Default for InstantiateConfig {
@@ -101,6 +108,9 @@ impl Default for InstantiateConfig {
             //NFP
             minter_may_put_token_storage: Some(false),
             minter_may_put_global_storage: Some(false),
+
+            //SNIP-52
+            bech32_hrp: None,
         }
     }
 }
@@ -416,6 +426,54 @@ pub enum ExecuteMsg {
         metadata: Option<String>,
         /// indicates if package version access is public, owners, or cleared
         access: String,
+        /// optional manifest with the entry point, dependencies and exports of the version.
+        ///   dependencies must already be uploaded and must not depend on this package
+        manifest: Option<PackageManifest>,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+    /// begins uploading a code version for a package in chunks, for data too large for a
+    ///   single transaction. replaces the sender's upload in progress for the package, or
+    ///   anyone's if the sender is the admin
+    ///   minter-only function
+    BeginPackageUpload {
+        /// package id
+        ///   this is the id shared by all versions
+        package_id: String,
+        /// script data attributes. its bytes are staged as the first chunk and may be empty
+        data: RawData,
+        /// length of the complete data
+        total_len: u32,
+        /// sha256 of the complete data
+        sha256: Binary,
+        /// tags to set to this package, must not already be in use for the package
+        tags: Option<Vec<String>>,
+        /// optional metadata
+        metadata: Option<String>,
+        /// indicates if package version access is public, owners, or cleared
+        access: String,
+        /// optional manifest with the entry point, dependencies and exports of the version
+        manifest: Option<PackageManifest>,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+    /// stages the next chunk of a package upload
+    ///   only callable by the minter that began the upload
+    AppendPackageChunk {
+        /// package id
+        package_id: String,
+        /// index of the chunk, which must be the number of chunks staged so far
+        index: u32,
+        /// chunk bytes
+        chunk: Binary,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+    /// verifies the length and sha256 of a package upload and publishes it as a new version
+    ///   only callable by the minter that began the upload
+    FinalizePackageUpload {
+        /// package id
+        package_id: String,
         /// optional message length padding
         padding: Option<String>,
     },
@@ -431,6 +489,36 @@ pub enum ExecuteMsg {
         /// optional message length padding
         padding: Option<String>,
     },
+    /// moves an existing tag to another version of a package
+    ///   only callable by minters, and only by the admin for protected tags
+    MovePackageTag {
+        /// package id
+        package_id: String,
+        /// tag to move
+        tag: String,
+        /// index of the version the tag should refer to
+        index: u32,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+    /// removes a tag from a package
+    ///   only callable by minters, and only by the admin for protected tags
+    RemovePackageTag {
+        /// package id
+        package_id: String,
+        /// tag to remove
+        tag: String,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+    /// sets the tags that only the admin can set, move or remove on any package
+    ///   only callable by the admin
+    SetProtectedPackageTags {
+        /// protected tags, replacing the current list
+        tags: Vec<String>,
+        /// optional message length padding
+        padding: Option<String>,
+    },
 
     ///
     /// Battleship functions
@@ -442,6 +530,25 @@ pub enum ExecuteMsg {
         padding: Option<String>,
     },
     
+    /// Subscribes a token to the lobby tiers whose newly listed games its owner wants to be
+    /// notified of, replacing its previous subscriptions.  Subscriptions expire after a week
+    /// and have to be renewed.  An empty list unsubscribes it
+    SubscribeLobby {
+        token_id: String,
+        /// wagers of the tiers to subscribe to
+        tiers: Vec<Uint128>,
+        padding: Option<String>,
+    },
+
+    /// Sets which of the owner's delegates also receive the token's game notifications,
+    /// replacing the previous list.  An empty list leaves only the owner notified
+    SetNotificationDelegates {
+        token_id: String,
+        /// addresses approved through ApproveOwnerDelegate or ApproveTokenDelegate
+        delegates: Vec<String>,
+        padding: Option<String>,
+    },
+
     /// Joins a new game that is currently waiting for another player
     JoinGame {
         token_id: String,
@@ -472,6 +579,75 @@ pub enum ExecuteMsg {
         padding: Option<String>,
     },
 
+    /// Aborts a stuck game and refunds each player's wager to the current owner of their token
+    ///   admin-only function
+    ForceUnlockGame {
+        game_id: String,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+
+    /// Aborts unfinished games while the contract is stopped, refunding each player's wager
+    /// to the current owner of their token
+    ///   admin-only function
+    EmergencyRefundGames {
+        /// optional max number of games to abort in this call, default 30 and at most 50
+        limit: Option<u32>,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+
+    /// Schedules a leaderboard season after any existing ones
+    ///   admin-only function
+    CreateSeason {
+        name: String,
+        /// time (in seconds since 01/01/1970) the season starts
+        start: u64,
+        /// time (in seconds since 01/01/1970) the season ends
+        end: u64,
+        /// percent of each house fee collected during the season that funds the prize pool
+        fee_percent: u8,
+        /// percent of the prize pool paid to each place, first place first, at most 50 places
+        prize_split: Vec<u8>,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+
+    /// Pays an ended season's prize pool to the owners of its top tokens and archives
+    /// the standings
+    ///   admin-only function
+    CloseSeason {
+        season_id: u32,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+
+    /// Adds an achievement that mints a badge when a token reaches its milestone, or
+    /// replaces the one with the same id
+    ///   admin-only function
+    SetAchievement {
+        achievement: Achievement,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+
+    /// Stops an achievement from being unlocked.  Badges already minted are kept
+    ///   admin-only function
+    RemoveAchievement {
+        achievement_id: String,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+
+    /// Moves games created before game records were introduced out of the legacy stores
+    ///   admin-only function
+    MigrateGames {
+        /// optional max number of games to migrate in this call
+        limit: Option<u32>,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+
     /// Public mint for battleship
     MintPublic {
         /// optional message length padding
@@ -486,6 +662,64 @@ pub enum ExecuteMsg {
         /// optional message length padding
         padding: Option<String>,
     },
+    /// SNIP-52
+    /// Registers a new notification channel
+    ///   admin-only function
+    AddChannel {
+        channel: String,
+        /// optional CDDL schema definition string for the notification data
+        cddl: Option<String>,
+        /// length the notification data is padded to, at most 4096
+        /// default: 256
+        data_len: Option<u32>,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+
+    /// SNIP-52
+    /// Replaces the CDDL schema of a notification channel, bumping its schema version
+    ///   admin-only function
+    UpdateChannelSchema {
+        channel: String,
+        /// new CDDL schema definition string, or None to clear it
+        cddl: Option<String>,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+
+    /// SNIP-52
+    /// Retires a notification channel
+    ///   admin-only function
+    RemoveChannel {
+        channel: String,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+
+    /// SNIP-52
+    /// Switches a notification channel between counter and txhash mode
+    ///   admin-only function
+    SetChannelMode {
+        channel: String,
+        mode: ChannelMode,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+    /// SNIP-52
+    /// Rotates the internal secret that seeds are derived from, starting a new epoch
+    ///   admin-only function
+    RotateSecret {
+        /// entropy mixed into the new secret
+        entropy: String,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+    /// SNIP-52
+    /// Removes the seed set with UpdateSeed, going back to the derived seed
+    ResetSeed {
+        /// optional message length padding
+        padding: Option<String>,
+    },
 }
 
 /// permission access level
@@ -639,9 +873,29 @@ pub enum ExecuteAnswer {
     UploadPackageVersion {
         index: u32,
     },
+    BeginPackageUpload {
+        chunk_count: u32,
+        received_len: u32,
+    },
+    AppendPackageChunk {
+        chunk_count: u32,
+        received_len: u32,
+    },
+    FinalizePackageUpload {
+        index: u32,
+    },
     AddPackageTags {
         status: ResponseStatus,
     },
+    MovePackageTag {
+        status: ResponseStatus,
+    },
+    RemovePackageTag {
+        status: ResponseStatus,
+    },
+    SetProtectedPackageTags {
+        status: ResponseStatus,
+    },
     ApproveOwnerDelegate {
         status: ResponseStatus,
     },
@@ -661,6 +915,19 @@ pub enum ExecuteAnswer {
         game: ListedGame,
     },
     
+    /// Subscribes a token to lobby tiers
+    SubscribeLobby {
+        /// wagers of the subscribed tiers
+        tiers: Vec<Uint128>,
+        /// time (in seconds since 01/01/1970) the subscription ends, if any tiers are subscribed
+        expires: Option<u64>,
+    },
+
+    /// Sets a token's notification delegates
+    SetNotificationDelegates {
+        delegates: Vec<Addr>,
+    },
+
     /// Joins a new game that is currently waiting for another player
     JoinGame {
         status: ResponseStatus,
@@ -682,10 +949,67 @@ pub enum ExecuteAnswer {
         status: ResponseStatus,
     },
 
+    /// Aborts a stuck game and refunds each player's wager to the current owner of their token
+    ForceUnlockGame {
+        status: ResponseStatus,
+    },
+
+    /// Aborts unfinished games while the contract is stopped
+    EmergencyRefundGames {
+        /// number of games aborted by this call
+        refunded: u32,
+        /// number of unfinished games left
+        remaining: u32,
+    },
+
+    /// Schedules a leaderboard season after any existing ones
+    CreateSeason {
+        season: Season,
+    },
+
+    /// Pays an ended season's prize pool to the owners of its top tokens
+    CloseSeason {
+        /// the paid places
+        winners: Vec<SeasonStanding>,
+    },
+
+    SetAchievement {
+        status: ResponseStatus,
+    },
+    RemoveAchievement {
+        status: ResponseStatus,
+    },
+
+    /// Moves games created before game records were introduced out of the legacy stores
+    MigrateGames {
+        /// number of games migrated by this call
+        migrated: u32,
+        /// number of games still in the legacy stores
+        remaining: u32,
+    },
+
     ///SNIP-52
     UpdateSeed {
         seed: Binary,
     },
+    AddChannel {
+        status: ResponseStatus,
+    },
+    UpdateChannelSchema {
+        schema_version: u32,
+    },
+    RemoveChannel {
+        status: ResponseStatus,
+    },
+    SetChannelMode {
+        status: ResponseStatus,
+    },
+    RotateSecret {
+        epoch: u32,
+    },
+    ResetSeed {
+        seed: Binary,
+    },
 }
 
 /// the address and viewing key making an authenticated query request
@@ -743,6 +1067,57 @@ pub enum TxAction {
         /// burner's address if not owner
         burner: Option<Addr>,
     },
+    /// token started a battleship game
+    GameStarted {
+        /// id of the game
+        game_id: String,
+        /// token's owner at the time
+        owner: Addr,
+        /// token id of the opponent
+        opponent_token_id: String,
+        /// amount of uscrt each player wagered
+        wager: Uint128,
+    },
+    /// token won a battleship game
+    GameWon {
+        /// id of the game
+        game_id: String,
+        /// token's owner at the time
+        owner: Addr,
+        /// token id of the opponent
+        opponent_token_id: String,
+    },
+    /// token lost a battleship game
+    GameLost {
+        /// id of the game
+        game_id: String,
+        /// token's owner at the time
+        owner: Addr,
+        /// token id of the opponent
+        opponent_token_id: String,
+    },
+    /// winnings or a refunded wager were paid to the token's owner
+    WagerPaid {
+        /// id of the game
+        game_id: String,
+        /// address that was paid
+        owner: Addr,
+        /// amount of uscrt paid
+        amount: Uint128,
+    },
+}
+
+/// tx types that can be used to filter the transaction history
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TxActionFilter {
+    Transfer,
+    Mint,
+    Burn,
+    GameStarted,
+    GameWon,
+    GameLost,
+    WagerPaid,
 }
 
 /// tx for display
@@ -908,6 +1283,8 @@ pub enum QueryMsg {
         page: Option<u32>,
         /// optional number of transactions per page
         page_size: Option<u32>,
+        /// optional list of transaction types to display
+        filter: Option<Vec<TxActionFilter>>,
     },
     /// display the code hash a contract has registered with the token contract and whether
     /// the contract implements BatchReceivenft
@@ -962,6 +1339,9 @@ pub enum QueryMsg {
         token_id: String,
         /// if address is not given in viewer, it will default to token_id's owner
         viewer: Option<ViewerInfoAddrOpt>,
+        /// optional sha256 of a cached copy. if it matches the version's hash, only the hash
+        ///   is returned instead of the package
+        if_none_match: Option<Binary>,
     },
     /// get paginated info about the versions of a package
     PackageInfo {
@@ -974,6 +1354,17 @@ pub enum QueryMsg {
         /// optional viewer
         viewer: Option<ViewerInfo>,
     },
+    /// get the dependency closure of a package version in the order the packages must be loaded
+    ResolvePackage {
+        /// package id
+        package_id: String,
+        /// optional tag reference (must have tag or version index but not both)
+        tag: Option<String>,
+        /// index of the version
+        index: Option<u32>,
+    },
+    /// lists the tags that only the admin can set, move or remove
+    ProtectedPackageTags {},
 
     ///
     /// Battleship queries
@@ -991,6 +1382,14 @@ pub enum QueryMsg {
         token_id: String,
         viewer: ViewerInfo,
     },
+
+    /// Gets the token's past games, most recent first
+    FinishedGames {
+        page_size: Option<u32>,
+        page: Option<u32>,
+        token_id: String,
+        viewer: ViewerInfo,
+    },
     
     /// Fetches the current game state
     GameState {
@@ -999,6 +1398,32 @@ pub enum QueryMsg {
         viewer: ViewerInfo,
     },
 
+    /// Lists the lobby tiers with their number of subscribers
+    LobbyTiers {},
+
+    /// Lists leaderboard seasons, most recent first
+    Seasons {
+        page_size: Option<u32>,
+        page: Option<u32>,
+    },
+
+    /// Fetches a season's leaderboard of its top 50 tokens: the final one once it is closed,
+    /// the current one while it is open
+    SeasonStandings {
+        season_id: u32,
+        page_size: Option<u32>,
+        page: Option<u32>,
+    },
+
+    /// Lists the achievements that mint badges
+    Achievements {},
+
+    /// Lists the achievements a token has unlocked and the badges minted for them
+    TokenAchievements {
+        token_id: String,
+        viewer: ViewerInfo,
+    },
+
     /// SNIP-52
     /// Public query to list all notification channels
     ListChannels {},
@@ -1008,6 +1433,15 @@ pub enum QueryMsg {
         channels: Vec<String>,
         viewer: ViewerInfo,
     },
+    /// Authenticated query returning the notifications kept for the viewer on a channel,
+    ///   for clients that missed them
+    NotificationInbox {
+        channel: String,
+        /// inbox counter of the first notification to return
+        /// default: 0
+        since_counter: Option<Uint64>,
+        viewer: ViewerInfo,
+    },
 
     /// perform queries by passing permits instead of viewing keys
     WithPermit {
@@ -1082,6 +1516,8 @@ pub struct TokenDelegateApproval {
 #[serde(rename_all = "snake_case")]
 pub enum ChannelMode {
     Counter,
+    /// ids and nonces come from the tx hash, and the data is a CBOR array of every payload
+    /// the tx sent to the recipient on the channel
     Txhash,
 }
 
@@ -1094,12 +1530,30 @@ pub struct ChannelInfo {
     pub channel: String,
     /// either "counter" or "txhash"
     pub mode: ChannelMode,
-    /// current counter value
-    pub counter: Uint64,
-    /// the next Notification ID
-    pub next_id: Binary,
+    /// current counter value, in counter mode only
+    pub counter: Option<Uint64>,
+    /// the next Notification ID, in counter mode only
+    pub next_id: Option<Binary>,
     /// optional CDDL schema definition string for the CBOR-encoded notification data
     pub cddl: Option<String>,
+    /// version of the schema, increased every time the admin updates it
+    pub schema_version: u32,
+    /// length of the notification data before encryption adds its 16 byte tag
+    pub data_len: u32,
+}
+
+/// A notification kept in an inbox
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct InboxNotification {
+    /// position in the inbox
+    pub counter: Uint64,
+    /// notification id
+    pub id: Binary,
+    /// nonce and additional authenticated data to decrypt `data` with the seed
+    pub nonce: Binary,
+    pub aad: String,
+    /// encrypted notification data
+    pub data: Binary,
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug)]
@@ -1212,10 +1666,21 @@ pub enum QueryAnswer {
     PackageVersion {
         package: Option<PackageVersion>,
     },
+    /// the package version matches the `if_none_match` hash
+    PackageVersionNotModified {
+        hash: Binary,
+    },
     PackageInfo {
         info: Vec<PackageVersionInfo>,
         version_count: u32,
     },
+    ResolvePackage {
+        /// dependencies first, the requested package version last
+        packages: Vec<ResolvedPackage>,
+    },
+    ProtectedPackageTags {
+        tags: Vec<String>,
+    },
 
     // Battleship query answers
     /// Fetches a list of active games in the lobby
@@ -1225,7 +1690,46 @@ pub enum QueryAnswer {
 
     /// Gets the list of active games the token is party to
     ActiveGames {
-        game_ids: Vec<String>,
+        games: Vec<ActiveGameSummary>,
+    },
+
+    /// Gets the token's past games, most recent first
+    FinishedGames {
+        games: Vec<FinishedGame>,
+        /// total number of games the token has finished
+        total: u32,
+    },
+
+    /// Lists the lobby tiers with their number of subscribers
+    LobbyTiers {
+        tiers: Vec<LobbyTier>,
+    },
+
+    /// Lists leaderboard seasons, most recent first
+    Seasons {
+        seasons: Vec<Season>,
+        /// total number of seasons
+        count: u32,
+    },
+
+    /// Fetches a season's standings
+    SeasonStandings {
+        season: Season,
+        standings: Vec<SeasonStanding>,
+        /// total number of tokens on the leaderboard
+        total: u32,
+    },
+
+    /// Lists the achievements that mint badges
+    Achievements {
+        achievements: Vec<Achievement>,
+    },
+
+    /// Lists the achievements a token has unlocked
+    TokenAchievements {
+        /// number of games the token has won
+        wins: u32,
+        unlocked: Vec<UnlockedAchievement>,
     },
     
     /// Fetches the current game state
@@ -1248,8 +1752,16 @@ pub enum QueryAnswer {
     ChannelInfo {
         /// scopes validity of this response
         as_of_block: Uint64,
+        /// epoch of the internal secret; derived seeds change when it does
+        epoch: u32,
         channels: Vec<ChannelInfo>,
     },
+    NotificationInbox {
+        channel: String,
+        /// number of notifications ever kept in the inbox, i.e. the next `since_counter`
+        counter: Uint64,
+        notifications: Vec<InboxNotification>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema, Debug)]
@@ -1334,6 +1846,8 @@ pub enum QueryWithPermit {
         page: Option<u32>,
         /// optional number of transactions per page
         page_size: Option<u32>,
+        /// optional list of transaction types to display
+        filter: Option<Vec<TxActionFilter>>,
     },
     /// display the number of tokens controlled by the contract.  The token supply must
     /// either be public, or the querier must be an authenticated minter
@@ -1408,6 +1922,9 @@ pub enum QueryWithPermit {
         index: Option<u32>,
         /// token id that address is owner of
         token_id: String,
+        /// optional sha256 of a cached copy. if it matches the version's hash, only the hash
+        ///   is returned instead of the package
+        if_none_match: Option<Binary>,
     },
     /// get paginated info about the versions of a package
     PackageInfo {
@@ -1433,6 +1950,13 @@ pub enum QueryWithPermit {
     ActiveGames {
         token_id: String,
     },
+
+    /// Gets the token's past games, most recent first
+    FinishedGames {
+        page_size: Option<u32>,
+        page: Option<u32>,
+        token_id: String,
+    },
     
     /// Fetches the current game state
     GameState {
@@ -1440,8 +1964,17 @@ pub enum QueryWithPermit {
         game_id: String,
     },
 
+    /// Lists the achievements a token has unlocked and the badges minted for them
+    TokenAchievements {
+        token_id: String,
+    },
+
     /// SNIP-52
     ChannelInfo {
         channels: Vec<String>,
     },
+    NotificationInbox {
+        channel: String,
+        since_counter: Option<Uint64>,
+    },
 }
@@ -1,6 +1,8 @@
 use std::collections::HashSet;
 use schemars::JsonSchema;
-use secret_toolkit::storage::{AppendStore, Keymap, Keyset};
+use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
+use secret_toolkit::crypto::sha_256;
+use secret_toolkit::storage::{AppendStore, Item, Keymap, Keyset};
 use serde::{Deserialize, Serialize};
 use cosmwasm_std::{StdResult, StdError, Storage, CanonicalAddr, Binary};
 
@@ -17,6 +19,14 @@ pub const PREFIX_STORAGE_GLOBAL: &[u8] = b"storageglobal";
 pub const PREFIX_PACKAGE_MANAGER: &[u8] = b"packagemanager";
 /// prefix for package tags
 pub const PREFIX_PACKAGE_TAGS: &[u8] = b"packagetags";
+/// prefix for package manifests
+pub const PREFIX_PACKAGE_MANIFESTS: &[u8] = b"packagemanifests";
+/// prefix for package content hashes
+pub const PREFIX_PACKAGE_HASHES: &[u8] = b"packagehashes";
+/// prefix for tags only the admin can set, move or remove
+pub const PREFIX_PROTECTED_TAGS: &[u8] = b"protectedtags";
+/// prefix for package uploads in progress
+pub const PREFIX_PACKAGE_UPLOADS: &[u8] = b"packageuploads";
 /// prefix for set of delegates that have ANY token delegation (for an owner)
 pub const PREFIX_ANY_DELEGATES: &[u8] = b"anydel";
 /// prefix for set of delegate address to owner address mappings
@@ -125,6 +135,8 @@ impl StoredPackageVersion {
             tags: self.tags, 
             metadata: self.metadata,
             access: access_val_to_string(self.access)?,
+            manifest: None,
+            hash: None,
         })
     }
 }
@@ -139,6 +151,10 @@ pub struct PackageVersion {
     pub metadata: Option<String>,
     /// indicates access type for package: public, owners, cleared
     pub access: String,
+    /// optional manifest
+    pub manifest: Option<PackageManifest>,
+    /// sha256 of the data bytes
+    pub hash: Option<Binary>,
 }
 
 impl PackageVersion {
@@ -152,6 +168,43 @@ impl PackageVersion {
     }
 }
 
+/// a package that a package version needs loaded before it
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Debug)]
+pub struct PackageDependency {
+    /// package id
+    pub package_id: String,
+    /// optional tag reference (must have tag or version index but not both)
+    pub tag: Option<String>,
+    /// index of the version
+    pub index: Option<u32>,
+}
+
+/// structured description of a package version for the nfpx module loader
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Debug, Default)]
+pub struct PackageManifest {
+    /// optional entry point of the package, e.g. the name of its main module
+    pub entry_point: Option<String>,
+    /// packages that must be loaded first
+    #[serde(default)]
+    pub dependencies: Vec<PackageDependency>,
+    /// names of the items the package exports
+    #[serde(default)]
+    pub exports: Vec<String>,
+}
+
+/// a package version in a resolved load order
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Debug)]
+pub struct ResolvedPackage {
+    /// package id
+    pub package_id: String,
+    /// package version index in package appendstore
+    pub index: u32,
+    /// entry point from the version's manifest
+    pub entry_point: Option<String>,
+    /// exports from the version's manifest
+    pub exports: Vec<String>,
+}
+
 /// package version info without data
 #[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
 pub struct PackageVersionInfo {
@@ -163,12 +216,24 @@ pub struct PackageVersionInfo {
     pub metadata: Option<String>,
     /// indicates if package version is public
     pub access: String,
+    /// sha256 of the data bytes
+    pub hash: Binary,
 }
 
 /// packagemanager:[package-id] -> AppendStore<StoredRawData>
 /// packagetags:[package-id] [tag] -> index of package in above AppendStore 
 pub static PACKAGE_MANAGER_STORE: AppendStore<StoredPackageVersion> = AppendStore::new(PREFIX_PACKAGE_MANAGER);
 pub static PACKAGE_TAGS_MAP: Keymap<String, u32> = Keymap::new(PREFIX_PACKAGE_TAGS);
+/// packagemanifests:[package-id] [index] -> manifest of the version, if it has one
+pub static PACKAGE_MANIFESTS_MAP: Keymap<u32, PackageManifest> = Keymap::new(PREFIX_PACKAGE_MANIFESTS);
+/// packagehashes:[package-id] [index] -> sha256 of the version's data bytes
+pub static PACKAGE_HASHES_MAP: Keymap<u32, Vec<u8>> = Keymap::new(PREFIX_PACKAGE_HASHES);
+
+/// tags, shared by all packages, that only the admin can set, move or remove
+pub static PROTECTED_TAGS: Item<Vec<String>> = Item::new(PREFIX_PROTECTED_TAGS);
+
+/// most package versions a dependency closure can hold
+pub const MAX_RESOLVED_PACKAGES: usize = 64;
 
 pub fn store_package_version(
     storage: &mut dyn Storage,
@@ -177,6 +242,7 @@ pub fn store_package_version(
     tags: Option<Vec<String>>,
     metadata: Option<String>,
     access: String,
+    manifest: Option<PackageManifest>,
 ) -> StdResult<u32> {
     let package_store = PACKAGE_MANAGER_STORE.add_suffix(package_id.as_bytes());
 
@@ -189,21 +255,25 @@ pub fn store_package_version(
         }
     }
 
+    let hash = sha_256(raw_data.bytes.as_slice()).to_vec();
     package_store.push(storage, &PackageVersion {
         data: raw_data,
-        tags: tags.clone(),
+        tags: None,
         metadata,
         access,
+        manifest: None,
+        hash: None,
     }.into_stored()?)?;
     let last = package_store.get_len(storage)? - 1;
+    PACKAGE_HASHES_MAP.add_suffix(package_id.as_bytes()).insert(storage, &last, &hash)?;
+    if let Some(manifest) = manifest {
+        PACKAGE_MANIFESTS_MAP.add_suffix(package_id.as_bytes()).insert(storage, &last, &manifest)?;
+    }
 
+    // tags of a new version move from whichever version held them
     if let Some(tags) = tags {
         tags.into_iter().try_for_each(|tag| {
-            //if get_package_index_from_tag(storage, package_id.clone(), tag.clone()).is_some() {
-            //    return Err(StdError::generic_err(format!("`{}` is an existing tag for package id {}", tag, package_id)));
-            //}
-            set_tag(storage, package_id.clone(), tag, last)?;
-            Ok::<(), StdError>(())
+            set_tag(storage, package_id.clone(), tag, last)
         })?;
     }
     Ok(last)
@@ -216,19 +286,53 @@ pub fn add_tags_to_package_version(
     tags: Vec<String>,
 ) -> StdResult<()> {
     let package_store = PACKAGE_MANAGER_STORE.add_suffix(package_id.as_bytes());
-    let mut package_version = package_store.get_at(storage, index)?;
-    let old_tags = package_version.tags.unwrap_or(vec![]);
-    package_version.tags = Some(old_tags.into_iter().chain(tags.clone().into_iter()).collect());
-    package_store.set_at(storage, index, &package_version)?;
+    if index >= package_store.get_len(storage)? {
+        return Err(StdError::generic_err(format!("Package {} has no version {}", package_id, index)));
+    }
+    // check every tag before storing any, use MovePackageTag to move an existing tag
+    for tag in tags.iter() {
+        if get_package_index_from_tag(storage, package_id.clone(), tag.clone()).is_some() {
+            return Err(StdError::generic_err(format!("`{}` is an existing tag for package id {}", tag, package_id)));
+        }
+    }
 
     tags.into_iter().try_for_each(|tag| {
-        //if get_package_index_from_tag(storage, package_id.clone(), tag.clone()).is_some() {
-        //    return Err(StdError::generic_err(format!("`{}` is an existing tag for package id {}", tag, package_id)));
-        //}
-        set_tag(storage, package_id.clone(), tag, index)?;
-        Ok::<(), StdError>(())
-    })?;
-    Ok(())
+        set_tag(storage, package_id.clone(), tag, index)
+    })
+}
+
+/// moves an existing tag to another version of the package
+pub fn move_package_tag(
+    storage: &mut dyn Storage,
+    package_id: String,
+    tag: String,
+    index: u32,
+) -> StdResult<()> {
+    let package_store = PACKAGE_MANAGER_STORE.add_suffix(package_id.as_bytes());
+    if index >= package_store.get_len(storage)? {
+        return Err(StdError::generic_err(format!("Package {} has no version {}", package_id, index)));
+    }
+    if get_package_index_from_tag(storage, package_id.clone(), tag.clone()).is_none() {
+        return Err(StdError::generic_err(format!("`{}` is not a tag for package id {}", tag, package_id)));
+    }
+    set_tag(storage, package_id, tag, index)
+}
+
+/// removes a tag from the package
+pub fn remove_package_tag(
+    storage: &mut dyn Storage,
+    package_id: String,
+    tag: String,
+) -> StdResult<()> {
+    let index = get_package_index_from_tag(storage, package_id.clone(), tag.clone())
+        .ok_or_else(|| StdError::generic_err(format!("`{}` is not a tag for package id {}", tag, package_id)))?;
+    remove_tag_from_version(storage, &package_id, &tag, index)?;
+    PACKAGE_TAGS_MAP.add_suffix(package_id.as_bytes()).remove(storage, &tag)
+}
+
+/// returns the tags only the admin can set, move or remove
+pub fn get_protected_tags(storage: &dyn Storage) -> StdResult<Vec<String>> {
+    Ok(PROTECTED_TAGS.may_load(storage)?.unwrap_or_default())
 }
 
 pub fn get_package_versions_info(
@@ -241,29 +345,61 @@ pub fn get_package_versions_info(
     let package_versions = package_store.paging(storage, page, page_size)?
         .into_iter()
         .enumerate()
-        .map(|(idx, package)| 
+        .map(|(idx, package)| {
+            let index = page * page_size + idx as u32;
             PackageVersionInfo {
-                index: page * page_size + idx as u32,
+                index,
+                hash: package_hash(storage, &package_id, index, &package.data.bytes),
                 tags: package.tags,
                 metadata: package.metadata,
                 access: access_val_to_string(package.access).unwrap(),
             }
-        )
+        })
         .collect();
     let len = package_store.get_len(storage)?;
     Ok((package_versions, len))
 }
 
+/// points a tag at a version, taking it off the version that held it so the tags listed
+/// with each version stay in sync with the tag index
 pub fn set_tag(
     storage: &mut dyn Storage,
     package_id: String,
     tag: String,
     version: u32,
 ) -> StdResult<()> {
+    let package_store = PACKAGE_MANAGER_STORE.add_suffix(package_id.as_bytes());
+    if let Some(old_version) = get_package_index_from_tag(storage, package_id.clone(), tag.clone()) {
+        if old_version != version {
+            remove_tag_from_version(storage, &package_id, &tag, old_version)?;
+        }
+    }
+    let mut package_version = package_store.get_at(storage, version)?;
+    let mut tags = package_version.tags.unwrap_or_default();
+    if !tags.contains(&tag) {
+        tags.push(tag.clone());
+    }
+    package_version.tags = Some(tags);
+    package_store.set_at(storage, version, &package_version)?;
+
     let tags_store = PACKAGE_TAGS_MAP.add_suffix(package_id.as_bytes());
     tags_store.insert(storage, &tag, &version)
 }
 
+fn remove_tag_from_version(
+    storage: &mut dyn Storage,
+    package_id: &str,
+    tag: &String,
+    version: u32,
+) -> StdResult<()> {
+    let package_store = PACKAGE_MANAGER_STORE.add_suffix(package_id.as_bytes());
+    let mut package_version = package_store.get_at(storage, version)?;
+    if let Some(tags) = package_version.tags.as_mut() {
+        tags.retain(|t| t != tag);
+    }
+    package_store.set_at(storage, version, &package_version)
+}
+
 pub fn get_package_index_from_tag(
     storage: &dyn Storage,
     package_id: String,
@@ -279,7 +415,33 @@ pub fn get_package_version_by_index(
     index: u32,
 ) -> StdResult<PackageVersion> {
     let package_store = PACKAGE_MANAGER_STORE.add_suffix(package_id.as_bytes());
-    package_store.get_at(storage, index)?.into_humanized()
+    let mut package_version = package_store.get_at(storage, index)?.into_humanized()?;
+    package_version.manifest = get_package_manifest(storage, &package_id, index);
+    package_version.hash = Some(package_hash(storage, &package_id, index, &package_version.data.bytes));
+    Ok(package_version)
+}
+
+/// returns the sha256 of a version's data bytes.  versions stored before hashes were kept
+/// have theirs computed from the bytes
+fn package_hash(
+    storage: &dyn Storage,
+    package_id: &str,
+    index: u32,
+    bytes: &[u8],
+) -> Binary {
+    let hash = PACKAGE_HASHES_MAP
+        .add_suffix(package_id.as_bytes())
+        .get(storage, &index)
+        .unwrap_or_else(|| sha_256(bytes).to_vec());
+    Binary::from(hash)
+}
+
+pub fn get_package_manifest(
+    storage: &dyn Storage,
+    package_id: &str,
+    index: u32,
+) -> Option<PackageManifest> {
+    PACKAGE_MANIFESTS_MAP.add_suffix(package_id.as_bytes()).get(storage, &index)
 }
 
 pub fn get_latest_package_version(
@@ -299,14 +461,219 @@ pub fn get_package_version_by_tag(
     package_id: String,
     tag: String,
 ) -> StdResult<Option<PackageVersion>> {
-    let package_store = PACKAGE_MANAGER_STORE.add_suffix(package_id.as_bytes());
-    if let Some(index) = get_package_index_from_tag(storage, package_id, tag) {
-        let package_version = package_store.get_at(storage, index)?.into_humanized()?;
-        return Ok(Some(package_version));
+    if let Some(index) = get_package_index_from_tag(storage, package_id.clone(), tag) {
+        return Ok(Some(get_package_version_by_index(storage, package_id, index)?));
     }
     Ok(None)
 }
 
+/// returns the index of the version a tag or index refers to
+pub fn find_package_index(
+    storage: &dyn Storage,
+    package_id: &str,
+    tag: Option<String>,
+    index: Option<u32>,
+) -> StdResult<u32> {
+    let found = match (tag, index) {
+        (Some(tag), None) => get_package_index_from_tag(storage, package_id.to_string(), tag),
+        (None, Some(index)) => {
+            let len = PACKAGE_MANAGER_STORE.add_suffix(package_id.as_bytes()).get_len(storage)?;
+            Some(index).filter(|index| *index < len)
+        }
+        (Some(_), Some(_)) => {
+            return Err(StdError::generic_err("Must have only one of `tag` or `index`"));
+        }
+        (None, None) => return Err(StdError::generic_err("Must include either `tag` or `index`")),
+    };
+    found.ok_or_else(|| StdError::generic_err(format!("Package version of {} not found", package_id)))
+}
+
+/// checks that the dependencies of a new version of a package resolve, without cycles or
+/// two versions of the same package
+pub fn check_package_dependencies(
+    storage: &dyn Storage,
+    package_id: &str,
+    manifest: &PackageManifest,
+) -> StdResult<()> {
+    let mut path = vec![package_id.to_string()];
+    let mut resolved = vec![];
+    for dependency in &manifest.dependencies {
+        resolve_dependency(storage, dependency, &mut path, &mut resolved)?;
+    }
+    Ok(())
+}
+
+/// returns the dependency closure of a package version in load order, every package after
+/// the ones it depends on and the requested version last
+pub fn resolve_package(
+    storage: &dyn Storage,
+    package_id: &str,
+    index: u32,
+) -> StdResult<Vec<ResolvedPackage>> {
+    let mut resolved = vec![];
+    resolve_package_version(storage, package_id, index, &mut vec![], &mut resolved)?;
+    Ok(resolved)
+}
+
+fn resolve_dependency(
+    storage: &dyn Storage,
+    dependency: &PackageDependency,
+    path: &mut Vec<String>,
+    resolved: &mut Vec<ResolvedPackage>,
+) -> StdResult<()> {
+    let index = find_package_index(
+        storage,
+        &dependency.package_id,
+        dependency.tag.clone(),
+        dependency.index,
+    )?;
+    resolve_package_version(storage, &dependency.package_id, index, path, resolved)
+}
+
+fn resolve_package_version(
+    storage: &dyn Storage,
+    package_id: &str,
+    index: u32,
+    path: &mut Vec<String>,
+    resolved: &mut Vec<ResolvedPackage>,
+) -> StdResult<()> {
+    if path.iter().any(|id| id == package_id) {
+        return Err(StdError::generic_err(format!(
+            "Dependency cycle: {} -> {}",
+            path.join(" -> "),
+            package_id
+        )));
+    }
+    if let Some(loaded) = resolved.iter().find(|package| package.package_id == package_id) {
+        if loaded.index != index {
+            return Err(StdError::generic_err(format!(
+                "Conflicting versions {} and {} of package {}",
+                loaded.index, index, package_id
+            )));
+        }
+        return Ok(());
+    }
+    let manifest = get_package_manifest(storage, package_id, index).unwrap_or_default();
+    path.push(package_id.to_string());
+    for dependency in &manifest.dependencies {
+        resolve_dependency(storage, dependency, path, resolved)?;
+    }
+    path.pop();
+    if resolved.len() == MAX_RESOLVED_PACKAGES {
+        return Err(StdError::generic_err(format!(
+            "A package can not depend on more than {} packages",
+            MAX_RESOLVED_PACKAGES - 1
+        )));
+    }
+    resolved.push(ResolvedPackage {
+        package_id: package_id.to_string(),
+        index,
+        entry_point: manifest.entry_point,
+        exports: manifest.exports,
+    });
+    Ok(())
+}
+
+/// a package version being uploaded in chunks over several transactions
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StoredPackageUpload {
+    /// minter that began the upload
+    pub uploader: CanonicalAddr,
+    /// package data attributes, without the bytes
+    pub data: StoredRawData,
+    /// tags for the version
+    pub tags: Option<Vec<String>>,
+    /// optional metadata
+    pub metadata: Option<String>,
+    /// access type for the version: public, owners, cleared
+    pub access: String,
+    /// optional manifest
+    pub manifest: Option<PackageManifest>,
+    /// declared length of the data
+    pub total_len: u32,
+    /// declared sha256 of the data
+    pub sha256: Vec<u8>,
+    /// number of chunks staged so far
+    pub chunk_count: u32,
+    /// number of bytes staged so far
+    pub received_len: u32,
+}
+
+/// packageuploads:[package-id] -> upload in progress for the package
+/// chunks:[package-id] [chunk index] -> staged chunk of that upload
+pub static PACKAGE_UPLOAD: Item<StoredPackageUpload> = Item::new(PREFIX_PACKAGE_UPLOADS);
+
+pub fn may_load_package_upload(
+    storage: &dyn Storage,
+    package_id: &str,
+) -> StdResult<Option<StoredPackageUpload>> {
+    PACKAGE_UPLOAD.add_suffix(package_id.as_bytes()).may_load(storage)
+}
+
+pub fn save_package_upload(
+    storage: &mut dyn Storage,
+    package_id: &str,
+    upload: &StoredPackageUpload,
+) -> StdResult<()> {
+    PACKAGE_UPLOAD.add_suffix(package_id.as_bytes()).save(storage, upload)
+}
+
+/// removes the upload in progress for a package along with its staged chunks
+pub fn remove_package_upload(
+    storage: &mut dyn Storage,
+    package_id: &str,
+) -> StdResult<()> {
+    if let Some(upload) = may_load_package_upload(storage, package_id)? {
+        let mut chunks = PrefixedStorage::multilevel(storage, &[PREFIX_RAW_DATA_CHUNK, package_id.as_bytes()]);
+        for index in 0..upload.chunk_count {
+            chunks.remove(&index.to_be_bytes());
+        }
+        PACKAGE_UPLOAD.add_suffix(package_id.as_bytes()).remove(storage);
+    }
+    Ok(())
+}
+
+/// stages the next chunk of the upload in progress for a package
+pub fn stage_package_chunk(
+    storage: &mut dyn Storage,
+    package_id: &str,
+    upload: &mut StoredPackageUpload,
+    chunk: &[u8],
+) -> StdResult<()> {
+    let mut chunks = PrefixedStorage::multilevel(storage, &[PREFIX_RAW_DATA_CHUNK, package_id.as_bytes()]);
+    chunks.set(&upload.chunk_count.to_be_bytes(), chunk);
+    upload.chunk_count += 1;
+    upload.received_len += chunk.len() as u32;
+    save_package_upload(storage, package_id, upload)
+}
+
+/// returns the data of the upload in progress for a package once it has all of its
+/// declared length and matches its declared sha256
+pub fn assemble_package_upload(
+    storage: &dyn Storage,
+    package_id: &str,
+    upload: &StoredPackageUpload,
+) -> StdResult<Vec<u8>> {
+    if upload.received_len != upload.total_len {
+        return Err(StdError::generic_err(format!(
+            "Received {} of the declared {} bytes",
+            upload.received_len, upload.total_len
+        )));
+    }
+    let chunks = ReadonlyPrefixedStorage::multilevel(storage, &[PREFIX_RAW_DATA_CHUNK, package_id.as_bytes()]);
+    let mut bytes = Vec::with_capacity(upload.total_len as usize);
+    for index in 0..upload.chunk_count {
+        let chunk = chunks
+            .get(&index.to_be_bytes())
+            .ok_or_else(|| StdError::generic_err(format!("Chunk {} is missing", index)))?;
+        bytes.extend_from_slice(&chunk);
+    }
+    if sha_256(&bytes).as_slice() != upload.sha256.as_slice() {
+        return Err(StdError::generic_err("Package data does not match the declared sha256"));
+    }
+    Ok(bytes)
+}
+
 /// set of addresses that are a delegate for ANY token for an owner
 ///   used with add_suffix(owner.as_slice())
 pub static ANY_DELEGATES: Keyset<CanonicalAddr> = Keyset::new(PREFIX_ANY_DELEGATES);
@@ -335,9 +702,12 @@ pub fn remove_any_delegate(
     owner: CanonicalAddr,
     address: CanonicalAddr,
 ) -> StdResult<()> {
-    ANY_DELEGATES
-        .add_suffix(owner.as_slice())
-        .remove(storage, &address)
+    let delegates = ANY_DELEGATES.add_suffix(owner.as_slice());
+    // the address may only be a token delegate
+    if !delegates.contains(storage, &address) {
+        return Ok(());
+    }
+    delegates.remove(storage, &address)
 }
 
 pub fn remove_all_any_delegates(
@@ -437,6 +807,9 @@ pub fn remove_token_delegate(
                 }
             }
         }
+    } else {
+        // the address may only be an ANY delegate
+        return Ok(());
     }
 
     TOKEN_DELEGATES
@@ -459,3 +832,411 @@ pub fn remove_all_token_delegates(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::*;
+    use cosmwasm_std::{from_binary, Api, OwnedDeps};
+    use crate::contract::{execute, query};
+    use crate::msg::{ExecuteAnswer, ExecuteMsg, QueryAnswer, QueryMsg};
+    use crate::test_helpers::{extract_error_msg, init_helper_default};
+    use super::*;
+
+    #[test]
+    fn test_package_manifests() {
+        let (init_result, mut deps) = init_helper_default();
+        init_result.unwrap();
+        let upload = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+                      package_id: &str,
+                      dependencies: Vec<PackageDependency>| {
+            let execute_msg = ExecuteMsg::UploadPackageVersion {
+                package_id: package_id.to_string(),
+                data: RawData::default(),
+                tags: Some(vec!["1.x".to_string()]),
+                metadata: None,
+                access: "public".to_string(),
+                manifest: Some(PackageManifest {
+                    entry_point: Some("main.js".to_string()),
+                    dependencies,
+                    exports: vec![package_id.to_string()],
+                }),
+                padding: None,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), execute_msg)
+        };
+        let by_tag = |package_id: &str| PackageDependency {
+            package_id: package_id.to_string(),
+            tag: Some("1.x".to_string()),
+            index: None,
+        };
+        let by_index = |package_id: &str, index: u32| PackageDependency {
+            package_id: package_id.to_string(),
+            tag: None,
+            index: Some(index),
+        };
+        let resolve = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, package_id: &str| {
+            let query_msg = QueryMsg::ResolvePackage {
+                package_id: package_id.to_string(),
+                tag: Some("1.x".to_string()),
+                index: None,
+            };
+            query(deps.as_ref(), mock_env(), query_msg).map(|answer| match from_binary(&answer).unwrap() {
+                QueryAnswer::ResolvePackage { packages } => packages
+                    .into_iter()
+                    .map(|package| (package.package_id, package.index))
+                    .collect::<Vec<_>>(),
+                _ => panic!("unexpected"),
+            })
+        };
+
+        // dependencies must exist
+        let exec_result = upload(&mut deps, "foo", vec![by_tag("bootloader")]);
+        assert!(extract_error_msg(exec_result).contains("not found"));
+
+        upload(&mut deps, "bootloader", vec![]).unwrap();
+        upload(&mut deps, "foo", vec![by_tag("bootloader")]).unwrap();
+        upload(&mut deps, "bar", vec![by_index("foo", 0), by_index("bootloader", 0)]).unwrap();
+        let manifest = get_package_version_by_index(&deps.storage, "bar".to_string(), 0).unwrap().manifest;
+        assert_eq!(manifest.unwrap().exports, vec!["bar".to_string()]);
+
+        // dependencies load first, each package once
+        let order = resolve(&deps, "bar").unwrap();
+        assert_eq!(
+            order,
+            vec![("bootloader".to_string(), 0), ("foo".to_string(), 0), ("bar".to_string(), 0)]
+        );
+        assert_eq!(resolve(&deps, "bootloader").unwrap(), vec![("bootloader".to_string(), 0)]);
+        assert!(extract_error_msg(resolve(&deps, "baz")).contains("not found"));
+
+        // cycles are rejected at upload time
+        let exec_result = upload(&mut deps, "foo", vec![by_index("foo", 0)]);
+        assert!(extract_error_msg(exec_result).contains("Dependency cycle: foo -> foo"));
+        let exec_result = upload(&mut deps, "bootloader", vec![by_tag("bar")]);
+        assert!(extract_error_msg(exec_result).contains("Dependency cycle: bootloader -> bar -> foo -> bootloader"));
+
+        // as are two versions of the same package
+        upload(&mut deps, "bootloader", vec![]).unwrap();
+        let exec_result = upload(&mut deps, "baz", vec![by_index("foo", 0), by_index("bootloader", 0)]);
+        assert!(extract_error_msg(exec_result).contains("Conflicting versions 1 and 0 of package bootloader"));
+        // tags are resolved when queried, so moving one can break a closure
+        assert!(extract_error_msg(resolve(&deps, "bar")).contains("Conflicting versions"));
+        let both = PackageDependency { index: Some(0), ..by_tag("foo") };
+        let exec_result = upload(&mut deps, "baz", vec![both]);
+        assert!(extract_error_msg(exec_result).contains("only one of `tag` or `index`"));
+    }
+
+    #[test]
+    fn test_chunked_package_upload() {
+        let (init_result, mut deps) = init_helper_default();
+        init_result.unwrap();
+        let bytes = b"console.log('hello from a large bundle');".repeat(8);
+        let begin = |first_chunk: &[u8], sha256: Vec<u8>| ExecuteMsg::BeginPackageUpload {
+            package_id: "bundle".to_string(),
+            data: RawData {
+                bytes: Binary::from(first_chunk),
+                content_type: Some("application/javascript".to_string()),
+                ..RawData::default()
+            },
+            total_len: bytes.len() as u32,
+            sha256: Binary::from(sha256),
+            tags: Some(vec!["latest".to_string()]),
+            metadata: None,
+            access: "public".to_string(),
+            manifest: None,
+            padding: None,
+        };
+        let append = |index: u32, chunk: &[u8]| ExecuteMsg::AppendPackageChunk {
+            package_id: "bundle".to_string(),
+            index,
+            chunk: Binary::from(chunk),
+            padding: None,
+        };
+        let finalize = || ExecuteMsg::FinalizePackageUpload {
+            package_id: "bundle".to_string(),
+            padding: None,
+        };
+        let exec = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, sender: &str, msg: ExecuteMsg| {
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)
+        };
+        let sha256 = sha_256(&bytes).to_vec();
+
+        let exec_result = exec(&mut deps, "alice", begin(&bytes[..100], sha256.clone()));
+        assert!(extract_error_msg(exec_result).contains("Only designated minters"));
+        let response = exec(&mut deps, "admin", begin(&bytes[..100], sha256.clone())).unwrap();
+        match from_binary(&response.data.unwrap()).unwrap() {
+            ExecuteAnswer::BeginPackageUpload { chunk_count, received_len } => {
+                assert_eq!((chunk_count, received_len), (1, 100));
+            }
+            _ => panic!("unexpected"),
+        }
+
+        // other minters can not replace the upload in progress
+        let add_minters = ExecuteMsg::AddMinters { minters: vec!["minter".to_string()], padding: None };
+        exec(&mut deps, "admin", add_minters).unwrap();
+        let exec_result = exec(&mut deps, "minter", begin(&bytes[..50], sha256.clone()));
+        assert!(extract_error_msg(exec_result).contains("Package bundle has an upload in progress by another minter"));
+        assert_eq!(may_load_package_upload(&deps.storage, "bundle").unwrap().unwrap().received_len, 100);
+
+        // chunks come in order, from the uploader, within the declared length
+        let exec_result = exec(&mut deps, "admin", append(0, &bytes[100..200]));
+        assert!(extract_error_msg(exec_result).contains("Expected chunk 1, got chunk 0"));
+        let exec_result = exec(&mut deps, "alice", append(1, &bytes[100..200]));
+        assert!(extract_error_msg(exec_result).contains("no upload in progress"));
+        let exec_result = exec(&mut deps, "admin", finalize());
+        assert!(extract_error_msg(exec_result).contains(&format!("Received 100 of the declared {} bytes", bytes.len())));
+        exec(&mut deps, "admin", append(1, &bytes[100..200])).unwrap();
+        let mut too_long = bytes[200..].to_vec();
+        too_long.push(0);
+        let exec_result = exec(&mut deps, "admin", append(2, &too_long));
+        assert!(extract_error_msg(exec_result).contains("exceeds the declared length"));
+        exec(&mut deps, "admin", append(2, &bytes[200..])).unwrap();
+
+        let response = exec(&mut deps, "admin", finalize()).unwrap();
+        match from_binary(&response.data.unwrap()).unwrap() {
+            ExecuteAnswer::FinalizePackageUpload { index } => assert_eq!(index, 0),
+            _ => panic!("unexpected"),
+        }
+        let package = get_package_version_by_index(&deps.storage, "bundle".to_string(), 0).unwrap();
+        assert_eq!(package.data.bytes.as_slice(), bytes.as_slice());
+        assert_eq!(package.data.content_type, Some("application/javascript".to_string()));
+        assert_eq!(package.tags, Some(vec!["latest".to_string()]));
+        assert!(may_load_package_upload(&deps.storage, "bundle").unwrap().is_none());
+
+        // data that does not match the declared sha256 is not published, and beginning
+        // again starts over
+        exec(&mut deps, "admin", begin(&bytes, vec![0; 32])).unwrap();
+        let exec_result = exec(&mut deps, "admin", finalize());
+        assert!(extract_error_msg(exec_result).contains("does not match the declared sha256"));
+        exec(&mut deps, "admin", begin(&[], sha256.clone())).unwrap();
+        exec(&mut deps, "admin", append(0, &bytes)).unwrap();
+        let response = exec(&mut deps, "admin", finalize()).unwrap();
+        match from_binary(&response.data.unwrap()).unwrap() {
+            ExecuteAnswer::FinalizePackageUpload { index } => assert_eq!(index, 1),
+            _ => panic!("unexpected"),
+        }
+
+        // the admin can discard an upload another minter abandoned
+        exec(&mut deps, "minter", begin(&bytes[..50], sha256.clone())).unwrap();
+        exec(&mut deps, "admin", begin(&bytes[..100], sha256)).unwrap();
+        let upload = may_load_package_upload(&deps.storage, "bundle").unwrap().unwrap();
+        assert_eq!(upload.uploader, deps.api.addr_canonicalize("admin").unwrap());
+    }
+
+    #[test]
+    fn test_package_hashes() {
+        let (init_result, mut deps) = init_helper_default();
+        init_result.unwrap();
+        let bytes = b"export const foo = 'foo';".to_vec();
+        let upload = ExecuteMsg::UploadPackageVersion {
+            package_id: "foo".to_string(),
+            data: RawData { bytes: Binary::from(bytes.clone()), ..RawData::default() },
+            tags: None,
+            metadata: None,
+            access: "public".to_string(),
+            manifest: None,
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), upload).unwrap();
+        let hash = Binary::from(sha_256(&bytes).to_vec());
+
+        // a version stored before hashes were kept
+        let legacy_bytes = b"export const foo = 'legacy';".to_vec();
+        let legacy = PackageVersion {
+            data: RawData { bytes: Binary::from(legacy_bytes.clone()), ..RawData::default() },
+            tags: None,
+            metadata: None,
+            access: "public".to_string(),
+            manifest: None,
+            hash: None,
+        };
+        PACKAGE_MANAGER_STORE
+            .add_suffix(b"foo")
+            .push(&mut deps.storage, &legacy.into_stored().unwrap())
+            .unwrap();
+
+        let query_msg = QueryMsg::PackageInfo { package_id: "foo".to_string(), page: None, page_size: None, viewer: None };
+        match from_binary(&query(deps.as_ref(), mock_env(), query_msg).unwrap()).unwrap() {
+            QueryAnswer::PackageInfo { info, .. } => {
+                assert_eq!(info[0].hash, hash);
+                assert_eq!(info[1].hash, Binary::from(sha_256(&legacy_bytes).to_vec()));
+            }
+            _ => panic!("unexpected"),
+        }
+
+        let package_version = |if_none_match: Option<Binary>| {
+            let query_msg = QueryMsg::PackageVersion {
+                package_id: "foo".to_string(),
+                tag: None,
+                index: Some(0),
+                token_id: "NFT1".to_string(),
+                viewer: None,
+                if_none_match,
+            };
+            from_binary(&query(deps.as_ref(), mock_env(), query_msg).unwrap()).unwrap()
+        };
+        match package_version(None) {
+            QueryAnswer::PackageVersion { package } => assert_eq!(package.unwrap().hash, Some(hash.clone())),
+            _ => panic!("unexpected"),
+        }
+        match package_version(Some(Binary::from(vec![0; 32]))) {
+            QueryAnswer::PackageVersion { package } => assert_eq!(package.unwrap().data.bytes.as_slice(), bytes),
+            _ => panic!("unexpected"),
+        }
+        match package_version(Some(hash.clone())) {
+            QueryAnswer::PackageVersionNotModified { hash: not_modified } => assert_eq!(not_modified, hash),
+            _ => panic!("unexpected"),
+        }
+    }
+
+    #[test]
+    fn test_package_tags() {
+        let (init_result, mut deps) = init_helper_default();
+        init_result.unwrap();
+        let add_minters = ExecuteMsg::AddMinters { minters: vec!["minter".to_string()], padding: None };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), add_minters).unwrap();
+
+        let upload = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, sender: &str, tag: &str| {
+            let execute_msg = ExecuteMsg::UploadPackageVersion {
+                package_id: "foo".to_string(),
+                data: RawData::default(),
+                tags: Some(vec![tag.to_string()]),
+                metadata: None,
+                access: "public".to_string(),
+                manifest: None,
+                padding: None,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), execute_msg)
+        };
+        let move_tag = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, sender: &str, tag: &str, index: u32| {
+            let execute_msg = ExecuteMsg::MovePackageTag {
+                package_id: "foo".to_string(),
+                tag: tag.to_string(),
+                index,
+                padding: None,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), execute_msg)
+        };
+        let version_tags = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>| {
+            let query_msg = QueryMsg::PackageInfo { package_id: "foo".to_string(), page: None, page_size: None, viewer: None };
+            match from_binary(&query(deps.as_ref(), mock_env(), query_msg).unwrap()).unwrap() {
+                QueryAnswer::PackageInfo { info, .. } => info
+                    .into_iter()
+                    .map(|version| version.tags.unwrap_or_default())
+                    .collect::<Vec<_>>(),
+                _ => panic!("unexpected"),
+            }
+        };
+        let tags = |list: &[&str]| list.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+
+        // a new version takes the tag off the version that held it
+        upload(&mut deps, "minter", "1.x").unwrap();
+        upload(&mut deps, "minter", "1.x").unwrap();
+        assert_eq!(version_tags(&deps), vec![tags(&[]), tags(&["1.x"])]);
+
+        // no tags are protected until the admin chooses some
+        let protected_tags = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>| {
+            match from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::ProtectedPackageTags {}).unwrap()).unwrap() {
+                QueryAnswer::ProtectedPackageTags { tags } => tags,
+                _ => panic!("unexpected"),
+            }
+        };
+        assert!(protected_tags(&deps).is_empty());
+        upload(&mut deps, "minter", "latest").unwrap();
+        let set_protected = |tags: Vec<String>| ExecuteMsg::SetProtectedPackageTags { tags, padding: None };
+        let error = extract_error_msg(execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), set_protected(tags(&["latest"]))));
+        assert!(error.contains("This is an admin command"));
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), set_protected(tags(&["latest"]))).unwrap();
+        assert_eq!(protected_tags(&deps), tags(&["latest"]));
+
+        // only the admin can set protected tags
+        let error = extract_error_msg(upload(&mut deps, "minter", "latest"));
+        assert!(error.contains("`latest` is a protected tag"));
+        upload(&mut deps, "admin", "latest").unwrap();
+        assert_eq!(version_tags(&deps), vec![tags(&[]), tags(&["1.x"]), tags(&[]), tags(&["latest"])]);
+
+        // AddPackageTags no longer moves tags
+        let add_tags = ExecuteMsg::AddPackageTags {
+            package_id: "foo".to_string(),
+            index: 0,
+            tags: vec!["beta".to_string(), "1.x".to_string()],
+            padding: None,
+        };
+        let error = extract_error_msg(execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), add_tags));
+        assert!(error.contains("`1.x` is an existing tag"));
+        assert_eq!(get_package_index_from_tag(&deps.storage, "foo".to_string(), "beta".to_string()), None);
+
+        move_tag(&mut deps, "minter", "1.x", 0).unwrap();
+        assert_eq!(version_tags(&deps), vec![tags(&["1.x"]), tags(&[]), tags(&[]), tags(&["latest"])]);
+        let error = extract_error_msg(move_tag(&mut deps, "minter", "latest", 0));
+        assert!(error.contains("`latest` is a protected tag"));
+        let error = extract_error_msg(move_tag(&mut deps, "minter", "beta", 0));
+        assert!(error.contains("`beta` is not a tag"));
+        let error = extract_error_msg(move_tag(&mut deps, "minter", "1.x", 4));
+        assert!(error.contains("Package foo has no version 4"));
+        let error = extract_error_msg(move_tag(&mut deps, "alice", "1.x", 1));
+        assert!(error.contains("Only designated minters can change package tags"));
+        move_tag(&mut deps, "admin", "latest", 1).unwrap();
+        assert_eq!(version_tags(&deps), vec![tags(&["1.x"]), tags(&["latest"]), tags(&[]), tags(&[])]);
+
+        let remove_tag = || ExecuteMsg::RemovePackageTag {
+            package_id: "foo".to_string(),
+            tag: "1.x".to_string(),
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), remove_tag()).unwrap();
+        assert_eq!(version_tags(&deps), vec![tags(&[]), tags(&["latest"]), tags(&[]), tags(&[])]);
+        assert_eq!(get_package_index_from_tag(&deps.storage, "foo".to_string(), "1.x".to_string()), None);
+        let error = extract_error_msg(execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), remove_tag()));
+        assert!(error.contains("`1.x` is not a tag"));
+
+        // the admin can change which tags are protected
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), set_protected(tags(&["stable"]))).unwrap();
+        assert_eq!(protected_tags(&deps), tags(&["stable"]));
+        move_tag(&mut deps, "minter", "latest", 3).unwrap();
+        assert_eq!(version_tags(&deps), vec![tags(&[]), tags(&[]), tags(&[]), tags(&["latest"])]);
+    }
+
+    #[test]
+    fn test_revoke_delegate() {
+        let (init_result, mut deps) = init_helper_default();
+        init_result.unwrap();
+        let execute_msg = ExecuteMsg::MintNft {
+            token_id: Some("NFT1".to_string()),
+            owner: Some("alice".to_string()),
+            public_metadata: None,
+            private_metadata: None,
+            royalty_info: None,
+            serial_number: None,
+            transferable: None,
+            memo: None,
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), execute_msg).unwrap();
+        let alice_raw = deps.api.addr_canonicalize("alice").unwrap();
+        let execute_msg = ExecuteMsg::ApproveOwnerDelegate {
+            address: "carol".to_string(),
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), execute_msg).unwrap();
+        let execute_msg = ExecuteMsg::ApproveTokenDelegate {
+            address: "dave".to_string(),
+            token_ids: vec!["NFT1".to_string()],
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), execute_msg).unwrap();
+
+        // an address that is only one kind of delegate can be revoked
+        let revoke = |address: &str| ExecuteMsg::RevokeDelegate {
+            address: address.to_string(),
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), revoke("carol")).unwrap();
+        let carol_raw = deps.api.addr_canonicalize("carol").unwrap();
+        assert!(!ANY_DELEGATES.add_suffix(alice_raw.as_slice()).contains(&deps.storage, &carol_raw));
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), revoke("dave")).unwrap();
+        let dave_raw = deps.api.addr_canonicalize("dave").unwrap();
+        assert!(TOKEN_DELEGATES.add_suffix(alice_raw.as_slice()).get(&deps.storage, &dave_raw).is_none());
+        // as can one that is no delegate at all
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), revoke("erin")).unwrap();
+    }
+}
@@ -0,0 +1,418 @@
+use schemars::JsonSchema;
+use secret_toolkit::storage::{AppendStore, Item, Keymap};
+use serde::{Deserialize, Serialize};
+use cosmwasm_storage::ReadonlyPrefixedStorage;
+use cosmwasm_std::{
+    to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, Response, StdError,
+    StdResult, Storage, Uint128,
+};
+use crate::battleship::DENOM;
+use crate::contract::{check_status, get_token};
+use crate::msg::{ExecuteAnswer, QueryAnswer};
+use crate::state::{may_load, Config, PREFIX_MAP_TO_INDEX};
+
+/// season points a token earns for winning a game
+pub const WIN_POINTS: u32 = 3;
+/// season points a token earns for playing a game to the end and losing
+pub const LOSS_POINTS: u32 = 1;
+/// number of places kept on a season's leaderboard, and the most places a season can pay
+pub const LEADERBOARD_SIZE: usize = 50;
+
+/// A leaderboard season
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Debug)]
+pub struct Season {
+    pub season_id: u32,
+    pub name: String,
+    /// time (in seconds since 01/01/1970) the season starts
+    pub start: u64,
+    /// time (in seconds since 01/01/1970) the season ends, exclusive
+    pub end: u64,
+    /// percent of each house fee collected during the season that funds the prize pool
+    pub fee_percent: u8,
+    /// percent of the prize pool paid to each place, first place first
+    pub prize_split: Vec<u8>,
+    /// uscrt collected in the prize pool
+    pub pool: Uint128,
+    /// true once the prizes have been paid out
+    pub closed: bool,
+}
+
+/// A token's place in a season's standings
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Debug)]
+pub struct SeasonStanding {
+    pub token_id: String,
+    pub points: u32,
+    /// uscrt paid (or, while the season is open, to be paid) for this place
+    pub prize: Uint128,
+}
+
+/// Returns StdResult<u128> of the part of the house fee withheld for the prize pool
+///
+/// credits season points for a decided game if a season is running
+///
+/// # Arguments
+///
+/// * `storage` - a mutable reference to the contract's storage
+/// * `time` - time (in seconds since 01/01/1970) the game was decided
+/// * `winner` - token id of the winner
+/// * `loser` - token id of the loser
+/// * `house_fee` - house fee collected from the game
+pub fn record_season_result(
+    storage: &mut dyn Storage,
+    time: u64,
+    winner: &str,
+    loser: &str,
+    house_fee: u128,
+) -> StdResult<u128> {
+    let mut season = match active_season(storage, time)? {
+        Some(season) => season,
+        None => return Ok(0),
+    };
+    let season_key = season.season_id.to_le_bytes();
+    let points = SEASON_POINTS_STORE.add_suffix(&season_key);
+    let leaderboard_store = SEASON_LEADERBOARD_STORE.add_suffix(&season_key);
+    let mut leaderboard = leaderboard_store.may_load(storage)?.unwrap_or_default();
+    for (token_id, earned) in [(winner, WIN_POINTS), (loser, LOSS_POINTS)] {
+        let token_id = token_id.to_string();
+        let total = points.get(storage, &token_id).unwrap_or(0) + earned;
+        points.insert(storage, &token_id, &total)?;
+        update_leaderboard(&mut leaderboard, token_id, total);
+    }
+    leaderboard_store.save(storage, &leaderboard)?;
+
+    let pooled = house_fee * season.fee_percent as u128 / 100;
+    if pooled > 0 {
+        season.pool += Uint128::from(pooled);
+        save_season(storage, &season)?;
+    }
+    Ok(pooled)
+}
+
+/// Returns StdResult<Response>
+///
+/// schedules a new season after any existing ones
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `sender` - a reference to the message sender
+/// * `config` - a reference to the Config
+/// * `priority` - u8 representation of highest status level this action is permitted at
+/// * `name` - name of the season
+/// * `start` - time (in seconds since 01/01/1970) the season starts
+/// * `end` - time (in seconds since 01/01/1970) the season ends
+/// * `fee_percent` - percent of each house fee that funds the prize pool
+/// * `prize_split` - percent of the prize pool paid to each place
+#[allow(clippy::too_many_arguments)]
+pub fn create_season(
+    deps: DepsMut,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    name: String,
+    start: u64,
+    end: u64,
+    fee_percent: u8,
+    prize_split: Vec<u8>,
+) -> StdResult<Response> {
+    check_status(config.status, priority)?;
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    if config.admin != sender_raw {
+        return Err(StdError::generic_err(
+            "This is an admin command and can only be run from the admin address",
+        ));
+    }
+    if start >= end {
+        return Err(StdError::generic_err("Season must end after it starts"));
+    }
+    if fee_percent > 100 {
+        return Err(StdError::generic_err("Fee percent can not be more than 100"));
+    }
+    if prize_split.is_empty() || prize_split.iter().map(|share| *share as u32).sum::<u32>() > 100 {
+        return Err(StdError::generic_err(
+            "Prize split must pay at least one place and can not add up to more than 100 percent",
+        ));
+    }
+    if prize_split.len() > LEADERBOARD_SIZE {
+        return Err(StdError::generic_err(format!(
+            "Prize split can not pay more than {} places",
+            LEADERBOARD_SIZE
+        )));
+    }
+
+    // seasons can not overlap, so the running season is easy to find
+    let season_id = SEASON_COUNT_STORE.may_load(deps.storage)?.unwrap_or(0);
+    if season_id > 0 && load_season(deps.storage, season_id - 1)?.end > start {
+        return Err(StdError::generic_err("Season can not start before the previous season ends"));
+    }
+
+    let season = Season {
+        season_id,
+        name,
+        start,
+        end,
+        fee_percent,
+        prize_split,
+        pool: Uint128::zero(),
+        closed: false,
+    };
+    save_season(deps.storage, &season)?;
+    SEASON_COUNT_STORE.save(deps.storage, &(season_id + 1))?;
+
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::CreateSeason { season })?))
+}
+
+/// Returns StdResult<Response>
+///
+/// pays the prize pool of an ended season to the owners of its top tokens and archives
+/// the final leaderboard
+///
+/// # Arguments
+///
+/// * `deps` - mutable reference to Extern containing all the contract's external dependencies
+/// * `env` - Env of contract's environment
+/// * `sender` - a reference to the message sender
+/// * `config` - a reference to the Config
+/// * `priority` - u8 representation of highest status level this action is permitted at
+/// * `season_id` - id of the season to close
+pub fn close_season(
+    deps: DepsMut,
+    env: Env,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    season_id: u32,
+) -> StdResult<Response> {
+    check_status(config.status, priority)?;
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    if config.admin != sender_raw {
+        return Err(StdError::generic_err(
+            "This is an admin command and can only be run from the admin address",
+        ));
+    }
+    let mut season = load_season(deps.storage, season_id)?;
+    if season.closed {
+        return Err(StdError::generic_err("Season is already closed"));
+    }
+    if env.block.time.seconds() < season.end {
+        return Err(StdError::generic_err("Season has not ended yet"));
+    }
+
+    let standings = rank_season(deps.storage, &season)?;
+    let archive = SEASON_STANDINGS_STORE.add_suffix(&season_id.to_le_bytes());
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut paid = 0_u128;
+    for standing in standings.iter() {
+        archive.push(deps.storage, standing)?;
+        if standing.prize.is_zero() {
+            continue;
+        }
+        // prizes go to whoever owns the token now, and the prize of a burned token is
+        // left in the remainder
+        let map2idx = ReadonlyPrefixedStorage::new(deps.storage, PREFIX_MAP_TO_INDEX);
+        if may_load::<u32>(&map2idx, standing.token_id.as_bytes())?.is_none() {
+            continue;
+        }
+        let (token, _) = get_token(deps.storage, &standing.token_id, None)?;
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: deps.api.addr_humanize(&token.owner)?.into_string(),
+            amount: vec![Coin {
+                denom: DENOM.to_string(),
+                amount: standing.prize,
+            }],
+        }));
+        paid += standing.prize.u128();
+    }
+
+    // anything not won (too few players, burned winners, rounding) goes back to the admin
+    let remainder = season.pool.u128() - paid;
+    if remainder > 0 {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: deps.api.addr_humanize(&config.admin)?.into_string(),
+            amount: vec![Coin {
+                denom: DENOM.to_string(),
+                amount: Uint128::from(remainder),
+            }],
+        }));
+    }
+
+    season.closed = true;
+    save_season(deps.storage, &season)?;
+
+    let winners = standings
+        .into_iter()
+        .take(season.prize_split.len())
+        .collect();
+    Ok(Response::new()
+        .add_messages(messages)
+        .set_data(to_binary(&ExecuteAnswer::CloseSeason { winners })?))
+}
+
+/// Returns StdResult<Binary> displaying a page of seasons, most recent first
+///
+/// # Arguments
+///
+/// * `deps` - a reference to Extern containing all the contract's external dependencies
+/// * `page` - optional page to display
+/// * `page_size` - optional number of seasons per page
+pub fn query_seasons(deps: Deps, page: Option<u32>, page_size: Option<u32>) -> StdResult<Binary> {
+    let count = SEASON_COUNT_STORE.may_load(deps.storage)?.unwrap_or(0);
+    let page = page.unwrap_or(0);
+    let page_size = page_size.unwrap_or(30);
+    let seasons = (0..count)
+        .rev()
+        .skip((page * page_size) as usize)
+        .take(page_size as usize)
+        .map(|season_id| load_season(deps.storage, season_id))
+        .collect::<StdResult<Vec<Season>>>()?;
+
+    to_binary(&QueryAnswer::Seasons { seasons, count })
+}
+
+/// Returns StdResult<Binary> displaying a page of a season's leaderboard.  Closed seasons show
+/// their archived final standings, open seasons show the current ones
+///
+/// # Arguments
+///
+/// * `deps` - a reference to Extern containing all the contract's external dependencies
+/// * `season_id` - id of the season
+/// * `page` - optional page to display
+/// * `page_size` - optional number of standings per page
+pub fn query_season_standings(
+    deps: Deps,
+    season_id: u32,
+    page: Option<u32>,
+    page_size: Option<u32>,
+) -> StdResult<Binary> {
+    let season = load_season(deps.storage, season_id)?;
+    let page = page.unwrap_or(0);
+    let page_size = page_size.unwrap_or(30);
+    let (standings, total) = if season.closed {
+        let archive = SEASON_STANDINGS_STORE.add_suffix(&season_id.to_le_bytes());
+        let standings = archive.paging(deps.storage, page, page_size)?;
+        (standings, archive.get_len(deps.storage)?)
+    } else {
+        let ranked = rank_season(deps.storage, &season)?;
+        let total = ranked.len() as u32;
+        let standings = ranked
+            .into_iter()
+            .skip((page * page_size) as usize)
+            .take(page_size as usize)
+            .collect();
+        (standings, total)
+    };
+
+    to_binary(&QueryAnswer::SeasonStandings {
+        season,
+        standings,
+        total,
+    })
+}
+
+/// returns a season's leaderboard with the prizes of each place
+fn rank_season(storage: &dyn Storage, season: &Season) -> StdResult<Vec<SeasonStanding>> {
+    let leaderboard = SEASON_LEADERBOARD_STORE
+        .add_suffix(&season.season_id.to_le_bytes())
+        .may_load(storage)?
+        .unwrap_or_default();
+
+    Ok(leaderboard
+        .into_iter()
+        .enumerate()
+        .map(|(place, (token_id, points))| SeasonStanding {
+            token_id,
+            points,
+            prize: season
+                .prize_split
+                .get(place)
+                .map(|share| season.pool.multiply_ratio(*share as u128, 100_u128))
+                .unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// moves a token to its place on a leaderboard sorted by points (ties by token id), dropping
+/// whoever falls past the last place.  points only ever go up, so a token that is not on the
+/// leaderboard can not outrank one that is without earning points itself
+fn update_leaderboard(leaderboard: &mut Vec<(String, u32)>, token_id: String, points: u32) {
+    leaderboard.retain(|(listed, _)| *listed != token_id);
+    let place = leaderboard
+        .iter()
+        .position(|(listed, listed_points)| {
+            *listed_points < points || (*listed_points == points && *listed > token_id)
+        })
+        .unwrap_or(leaderboard.len());
+    if place < LEADERBOARD_SIZE {
+        leaderboard.insert(place, (token_id, points));
+        leaderboard.truncate(LEADERBOARD_SIZE);
+    }
+}
+
+/// returns the season running at the given time, if any
+fn active_season(storage: &dyn Storage, time: u64) -> StdResult<Option<Season>> {
+    let count = SEASON_COUNT_STORE.may_load(storage)?.unwrap_or(0);
+    for season_id in (0..count).rev() {
+        let season = load_season(storage, season_id)?;
+        if season.end <= time {
+            break;
+        }
+        if season.start <= time {
+            return Ok(Some(season));
+        }
+    }
+    Ok(None)
+}
+
+fn load_season(storage: &dyn Storage, season_id: u32) -> StdResult<Season> {
+    SEASON_STORE
+        .add_suffix(&season_id.to_le_bytes())
+        .may_load(storage)?
+        .ok_or_else(|| StdError::generic_err(format!("No season with id {}", season_id)))
+}
+
+fn save_season(storage: &mut dyn Storage, season: &Season) -> StdResult<()> {
+    SEASON_STORE
+        .add_suffix(&season.season_id.to_le_bytes())
+        .save(storage, season)
+}
+
+// SEASONS STATE
+
+// number of seasons created
+pub static SEASON_COUNT_STORE: Item<u32> = Item::new(b"season-count");
+// season for prefix season_id
+pub static SEASON_STORE: Item<Season> = Item::new(b"season");
+// points per token_id for prefix season_id
+pub static SEASON_POINTS_STORE: Keymap<String, u32> = Keymap::new(b"season-points");
+// top LEADERBOARD_SIZE (token_id, points) for prefix season_id, best first
+pub static SEASON_LEADERBOARD_STORE: Item<Vec<(String, u32)>> = Item::new(b"season-leaderboard");
+// final standings for prefix season_id
+pub static SEASON_STANDINGS_STORE: AppendStore<SeasonStanding> = AppendStore::new(b"season-standings");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_leaderboard() {
+        let mut leaderboard = vec![];
+        for place in 0..LEADERBOARD_SIZE {
+            update_leaderboard(&mut leaderboard, format!("token{:02}", place), 10);
+        }
+        // ties are broken by token id, and a full leaderboard has no room for lower scores
+        update_leaderboard(&mut leaderboard, "token99".to_string(), 10);
+        assert_eq!(leaderboard.len(), LEADERBOARD_SIZE);
+        assert_eq!(leaderboard.last().unwrap().0, format!("token{:02}", LEADERBOARD_SIZE - 1));
+
+        // a token that earns points moves up, pushing out the last place
+        update_leaderboard(&mut leaderboard, "token99".to_string(), 13);
+        assert_eq!(leaderboard[0], ("token99".to_string(), 13));
+        assert_eq!(leaderboard.len(), LEADERBOARD_SIZE);
+        assert_eq!(leaderboard.last().unwrap().0, format!("token{:02}", LEADERBOARD_SIZE - 2));
+
+        // and is only listed once
+        update_leaderboard(&mut leaderboard, "token99".to_string(), 14);
+        assert_eq!(leaderboard[0], ("token99".to_string(), 14));
+        assert_eq!(leaderboard.iter().filter(|(token_id, _)| token_id == "token99").count(), 1);
+    }
+}
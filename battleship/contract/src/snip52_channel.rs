@@ -1,40 +1,154 @@
 use cosmwasm_std::{Storage, StdResult,};
-use secret_toolkit_storage::{Keyset, Keymap};
+use secret_toolkit_storage::{Item, Keyset, Keymap};
 use serde::{Serialize, Deserialize};
+use crate::msg::ChannelMode;
+use crate::snip52_exec_query::DATA_LEN;
 
-pub static CHANNELS: Keyset<String> = Keyset::new(b"channel-ids");
-pub static CHANNEL_SCHEMATA: Keymap<String,String> = Keymap::new(b"channel-schemata");
+/// registered channel ids (built per use because keysets cache their length)
+pub fn channels_store() -> Keyset<'static, String> {
+    Keyset::new(b"channel-ids")
+}
+/// CDDL schema of each channel that has one (built per use because keymaps cache their length)
+pub fn channel_schemata_store() -> Keymap<'static, String, String> {
+    Keymap::new(b"channel-schemata")
+}
+/// length the channel's notification data is padded to for prefix channel id. channels
+/// without one use `DATA_LEN`
+pub static CHANNEL_DATA_LEN: Item<u32> = Item::new(b"channel-data-len");
+/// largest notification data length a channel can declare
+pub const MAX_DATA_LEN: u32 = 4096;
+/// version of the channel's schema for prefix channel id, bumped every time it is updated.
+/// kept when the channel is removed, so a channel id never reuses a version number
+pub static CHANNEL_SCHEMA_VERSION: Item<u32> = Item::new(b"channel-schema-version");
+/// mode of the channel for prefix channel id. channels without one run in counter mode
+pub static CHANNEL_MODE: Item<ChannelMode> = Item::new(b"channel-mode");
+
+/// returns the mode a channel runs in
+pub fn channel_mode(storage: &dyn Storage, channel: &str) -> StdResult<ChannelMode> {
+    Ok(CHANNEL_MODE
+        .add_suffix(channel.as_bytes())
+        .may_load(storage)?
+        .unwrap_or(ChannelMode::Counter))
+}
+
+/// returns the length a channel's notification data is padded to
+pub fn data_len(storage: &dyn Storage, channel: &str) -> StdResult<usize> {
+    Ok(CHANNEL_DATA_LEN
+        .add_suffix(channel.as_bytes())
+        .may_load(storage)?
+        .map_or(DATA_LEN, |len| len as usize))
+}
+
+/// returns the version of the channel's schema.  channels stored before schemas were
+/// versioned are on version 1
+pub fn schema_version(storage: &dyn Storage, channel: &str) -> StdResult<u32> {
+    Ok(CHANNEL_SCHEMA_VERSION
+        .add_suffix(channel.as_bytes())
+        .may_load(storage)?
+        .unwrap_or(1))
+}
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct Channel {
     pub id: String,
     pub schema: Option<String>,
+    /// length the notification data is padded to. if None, a stored channel keeps its
+    /// length and a new one uses `DATA_LEN`
+    pub data_len: Option<u32>,
 }
 
 impl Channel {
     pub fn store(self, storage: &mut dyn Storage) -> StdResult<()> {
-        CHANNELS.insert(storage, &self.id)?;
+        channels_store().insert(storage, &self.id)?;
         if let Some(schema) = self.schema {
-            CHANNEL_SCHEMATA.insert(storage, &self.id, &schema)?;
-        } else if CHANNEL_SCHEMATA.get(storage, &self.id).is_some() { 
+            channel_schemata_store().insert(storage, &self.id, &schema)?;
+        } else if channel_schemata_store().get(storage, &self.id).is_some() { 
             // double check it does not already have a schema stored, and if 
             //   it does remove it.
-            CHANNEL_SCHEMATA.remove(storage, &self.id)?;
+            channel_schemata_store().remove(storage, &self.id)?;
+        }
+        if let Some(len) = self.data_len {
+            CHANNEL_DATA_LEN.add_suffix(self.id.as_bytes()).save(storage, &len)?;
         }
         Ok(())
     }
+
+    /// removes the channel along with its schema, data length and mode.  its schema
+    /// version is kept for when the channel is added again
+    pub fn remove(storage: &mut dyn Storage, id: &String) -> StdResult<()> {
+        // channels stored before schemas were versioned have no version saved yet
+        let version = schema_version(storage, id)?;
+        CHANNEL_SCHEMA_VERSION.add_suffix(id.as_bytes()).save(storage, &version)?;
+        channels_store().remove(storage, id)?;
+        if channel_schemata_store().contains(storage, id) {
+            channel_schemata_store().remove(storage, id)?;
+        }
+        CHANNEL_DATA_LEN.add_suffix(id.as_bytes()).remove(storage);
+        CHANNEL_MODE.add_suffix(id.as_bytes()).remove(storage);
+        Ok(())
+    }
 }
 
+// NFT channels
+
+/// id for the `nft_received` channel
+pub const NFT_RECEIVED_CHANNEL_ID: &str = "nft_received";
+/// CDDL Schema for nft received data
+pub const NFT_RECEIVED_CHANNEL_SCHEMA: &str = "nft_received=[token_id:text,from:text]";
+/// length the `nft_received` notification data is padded to
+pub const NFT_RECEIVED_CHANNEL_DATA_LEN: u32 = 128;
+
+/// id for the `nft_sent` channel
+pub const NFT_SENT_CHANNEL_ID: &str = "nft_sent";
+/// CDDL Schema for nft sent data
+pub const NFT_SENT_CHANNEL_SCHEMA: &str = "nft_sent=[token_id:text,to:text]";
+/// length the `nft_sent` notification data is padded to
+pub const NFT_SENT_CHANNEL_DATA_LEN: u32 = 128;
+
+/// id for the `approval_changed` channel
+pub const APPROVAL_CHANGED_CHANNEL_ID: &str = "approval_changed";
+/// CDDL Schema for approval changed data.  kind is "transfer" or "storage_delegate", and
+/// an empty token_ids list means every token of the owner
+pub const APPROVAL_CHANGED_CHANNEL_SCHEMA: &str = "approval_changed=[kind:text,token_ids:[*text],owner:text,spender:text,approved:bool]";
+/// length the `approval_changed` notification data is padded to
+pub const APPROVAL_CHANGED_CHANNEL_DATA_LEN: u32 = 256;
+
 // Battleship channels
 
 /// id for the `game_listed` channel
-//pub const GAME_LISTED_CHANNEL_ID: &str = "game_listed";
+pub const GAME_LISTED_CHANNEL_ID: &str = "game_listed";
 /// CDDL Schema for game listed data
-//pub const GAME_LISTED_CHANNEL_SCHEMA: &str = "game_listed=[game_id:text,title:text,wager_uscrt:biguint]";
+pub const GAME_LISTED_CHANNEL_SCHEMA: &str = "game_listed=[game_id:text,title:text,wager_uscrt:uint]";
+/// length the `game_listed` notification data is padded to
+pub const GAME_LISTED_CHANNEL_DATA_LEN: u32 = 128;
+
+/// id for the `player_joined` channel
+pub const PLAYER_JOINED_CHANNEL_ID: &str = "player_joined";
+/// CDDL Schema for player joined data
+pub const PLAYER_JOINED_CHANNEL_SCHEMA: &str = "player_joined=[game_id:text]";
+/// length the `player_joined` notification data is padded to
+pub const PLAYER_JOINED_CHANNEL_DATA_LEN: u32 = 64;
+
+/// id for the `opponent_attacked` channel
+pub const OPPONENT_ATTACKED_CHANNEL_ID: &str = "opponent_attacked";
+/// CDDL Schema for opponent attacked data
+pub const OPPONENT_ATTACKED_CHANNEL_SCHEMA: &str = "opponent_attacked=[game_id:text,cell:uint,result:uint]";
+/// length the `opponent_attacked` notification data is padded to
+pub const OPPONENT_ATTACKED_CHANNEL_DATA_LEN: u32 = 64;
 
 /// id for `game_updated` channel
 pub const GAME_UPDATED_CHANNEL_ID: &str = "game_updated";
 /// CDDL Schema for the game updated data
 pub const GAME_UPDATED_CHANNEL_SCHEMA: &str = "game_updated=[game_id:text,home:[100 uint],turn:uint]";
+/// length the `game_updated` notification data is padded to
+pub const GAME_UPDATED_CHANNEL_DATA_LEN: u32 = 256;
 
+/// id for the `game_delta` channel
+pub const GAME_DELTA_CHANNEL_ID: &str = "game_delta";
+/// CDDL Schema for the game delta data
+pub const GAME_DELTA_CHANNEL_SCHEMA: &str = "game_delta=[version:uint,game_id:text,seq:uint,cell:uint/null,result:uint/null,sunk:uint/null,turn:uint]";
+/// length the `game_delta` notification data is padded to
+pub const GAME_DELTA_CHANNEL_DATA_LEN: u32 = 64;
+/// version of the `game_delta` payload layout, sent as its first element
+pub const GAME_DELTA_VERSION: u8 = 1;
@@ -41,6 +41,23 @@ pub fn hkdf_sha_256(salt: &Option<Vec<u8>>, ikm: &[u8], info: &[u8]) -> StdResul
     }
 }
 
+/// decodes a hex string such as a transaction hash
+// usize::is_multiple_of needs Rust 1.87
+#[allow(unknown_lints, clippy::manual_is_multiple_of)]
+pub fn decode_hex(hex: &str) -> StdResult<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return Err(StdError::generic_err("Invalid hex string"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| StdError::generic_err("Invalid hex string"))
+        })
+        .collect()
+}
+
 /*
 pub fn sha_256(data: &[u8]) -> [u8; SHA256_HASH_SIZE] {
     let mut hasher = Sha256::new();
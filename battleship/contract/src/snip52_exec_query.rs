@@ -2,12 +2,23 @@ use bech32::ToBase32;
 use hkdf::hmac::Mac;
 use cosmwasm_std::{DepsMut, Env, Addr, StdResult, Response, StdError, to_binary, Api, Storage, CanonicalAddr, Binary, Deps, Uint64};
 use secret_toolkit::crypto::sha_256;
-use crate::snip52_channel::{CHANNELS, CHANNEL_SCHEMATA};
-use crate::snip52_signed_doc::{SignedDocument, pubkey_to_account, Document};
-use crate::snip52_state::{get_seed, store_seed, get_count};
-use crate::snip52_crypto::{HmacSha256, cipher_data};
-use crate::msg::{ExecuteAnswer, QueryAnswer, ChannelInfo, ChannelMode};
+use crate::snip52_channel::{
+    channels_store, channel_mode, channel_schemata_store, data_len, schema_version, Channel,
+    CHANNEL_MODE, CHANNEL_SCHEMA_VERSION, MAX_DATA_LEN,
+};
+use crate::snip52_signed_doc::{
+    pubkey_to_account, Adr036Document, DocFormat, Document, SignedDocument,
+};
+use crate::snip52_state::{
+    bech32_hrp, get_seed, store_seed, get_count, increment_count, load_inbox, push_txhash_payload,
+    push_inbox, remove_seed, replace_latest_inbox, secret_epoch, StoredInboxNotification, INTERNAL_SECRET, SECRET_EPOCH,
+};
+use crate::snip52_crypto::{HmacSha256, cipher_data, decode_hex, hkdf_sha_256};
+use crate::msg::{ExecuteAnswer, QueryAnswer, ChannelInfo, ChannelMode, InboxNotification, ResponseStatus};
+use crate::contract::check_status;
+use crate::state::Config;
 
+/// length notification data is padded to on channels that do not declare one
 pub const DATA_LEN: usize = 256;
 
 /// 
@@ -30,7 +41,8 @@ pub fn update_seed(
     sender: &Addr,
     signed_doc: SignedDocument,
 ) -> StdResult<Response> {
-    let account = validate_signed_doc(deps.api, &signed_doc, None)?;
+    let hrp = bech32_hrp(deps.storage)?;
+    let account = validate_signed_doc(deps.api, &signed_doc, Some(&hrp))?;
 
     if sender.as_str() != account {
         return Err(StdError::generic_err("Signed doc is not signed by sender"));
@@ -58,24 +70,249 @@ pub fn update_seed(
     })?))
 }
 
+/// 
+/// Execute ResetSeed message
+/// 
+///   Removes the seed the sender set with UpdateSeed, so that their seed is derived
+///   from the contract's internal secret again. Returns the derived seed.
+/// 
+pub fn reset_seed(
+    deps: DepsMut,
+    sender: &Addr,
+) -> StdResult<Response> {
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    remove_seed(deps.storage, &sender_raw)?;
+
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::ResetSeed {
+        seed: get_seed(deps.storage, &sender_raw)?,
+    })?))
+}
+
+/// 
+/// Execute RotateSecret message
+/// 
+///   Admin-only. Replaces the internal secret with one derived from the current
+///   secret, the given entropy and the block's randomness, and increments the epoch.
+///   Derived seeds change from the new epoch on, while seeds set with UpdateSeed are
+///   left alone. Clients learn the epoch and their seed from the ChannelInfo query.
+/// 
+pub fn rotate_secret(
+    deps: DepsMut,
+    env: &Env,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    entropy: String,
+) -> StdResult<Response> {
+    check_admin(deps.api, sender, config, priority)?;
+    let rng_seed = env
+        .block
+        .random
+        .as_ref()
+        .ok_or_else(|| StdError::generic_err("Block randomness is not available"))?;
+
+    let epoch = secret_epoch(deps.storage)? + 1;
+    let mut rng_entropy = INTERNAL_SECRET.load(deps.storage)?;
+    rng_entropy.extend_from_slice(&env.block.height.to_be_bytes());
+    rng_entropy.extend_from_slice(&epoch.to_be_bytes());
+    rng_entropy.extend_from_slice(entropy.as_bytes());
+    let salt = Some(sha_256(&rng_entropy).to_vec());
+    let internal_secret = hkdf_sha_256(
+        &salt,
+        rng_seed.0.as_slice(),
+        "contract internal secret".as_bytes(),
+    )?;
+    INTERNAL_SECRET.save(deps.storage, &internal_secret.to_vec())?;
+    SECRET_EPOCH.save(deps.storage, &epoch)?;
+
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::RotateSecret { epoch })?))
+}
+
+/// 
+/// Execute SetChannelMode message
+/// 
+///   Admin-only. Switches a channel between counter and txhash mode. In txhash mode
+///   notification ids and nonces are derived from the hash of the notifying
+///   transaction, so clients no longer need to track a counter per channel.
+/// 
+pub fn set_channel_mode(
+    deps: DepsMut,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    channel: String,
+    mode: ChannelMode,
+) -> StdResult<Response> {
+    check_admin(deps.api, sender, config, priority)?;
+    check_channel_exists(deps.storage, &channel)?;
+    CHANNEL_MODE.add_suffix(channel.as_bytes()).save(deps.storage, &mode)?;
+
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::SetChannelMode {
+        status: ResponseStatus::Success,
+    })?))
+}
+
+/// 
+/// Execute AddChannel message
+/// 
+///   Admin-only. Registers a new notification channel with an optional CDDL schema,
+///   starting at schema version 1 (or one past the version of a removed channel with the
+///   same id), and the length its notification data is padded to.
+/// 
+pub fn add_channel(
+    deps: DepsMut,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    channel: String,
+    cddl: Option<String>,
+    data_len: Option<u32>,
+) -> StdResult<Response> {
+    check_admin(deps.api, sender, config, priority)?;
+    if channel.is_empty() {
+        return Err(StdError::generic_err("Channel id can not be empty"));
+    }
+    if channels_store().contains(deps.storage, &channel) {
+        return Err(StdError::generic_err(format!("`{}` channel already exists", channel)));
+    }
+    if matches!(data_len, Some(len) if len == 0 || len > MAX_DATA_LEN) {
+        return Err(StdError::generic_err(format!(
+            "Channel data length must be between 1 and {}",
+            MAX_DATA_LEN
+        )));
+    }
+    let version_store = CHANNEL_SCHEMA_VERSION.add_suffix(channel.as_bytes());
+    let version = version_store.may_load(deps.storage)?.map_or(1, |removed| removed + 1);
+    version_store.save(deps.storage, &version)?;
+    Channel { id: channel, schema: cddl, data_len }.store(deps.storage)?;
+
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::AddChannel {
+        status: ResponseStatus::Success,
+    })?))
+}
+
+/// 
+/// Execute UpdateChannelSchema message
+/// 
+///   Admin-only. Replaces (or clears) the CDDL schema of a channel and bumps its
+///   schema version so clients can tell the notification data format changed.
+/// 
+pub fn update_channel_schema(
+    deps: DepsMut,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    channel: String,
+    cddl: Option<String>,
+) -> StdResult<Response> {
+    check_admin(deps.api, sender, config, priority)?;
+    check_channel_exists(deps.storage, &channel)?;
+    let version = schema_version(deps.storage, &channel)? + 1;
+    CHANNEL_SCHEMA_VERSION.add_suffix(channel.as_bytes()).save(deps.storage, &version)?;
+    Channel { id: channel, schema: cddl, data_len: None }.store(deps.storage)?;
+
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::UpdateChannelSchema {
+        schema_version: version,
+    })?))
+}
+
+/// 
+/// Execute RemoveChannel message
+/// 
+///   Admin-only. Retires a channel; no further notifications are sent on it.
+/// 
+pub fn remove_channel(
+    deps: DepsMut,
+    sender: &Addr,
+    config: &Config,
+    priority: u8,
+    channel: String,
+) -> StdResult<Response> {
+    check_admin(deps.api, sender, config, priority)?;
+    check_channel_exists(deps.storage, &channel)?;
+    Channel::remove(deps.storage, &channel)?;
+
+    Ok(Response::new().set_data(to_binary(&ExecuteAnswer::RemoveChannel {
+        status: ResponseStatus::Success,
+    })?))
+}
+
+/// checks the contract status allows the admin function and that the sender is the admin
+fn check_admin(api: &dyn Api, sender: &Addr, config: &Config, priority: u8) -> StdResult<()> {
+    check_status(config.status, priority)?;
+    let sender_raw = api.addr_canonicalize(sender.as_str())?;
+    if config.admin != sender_raw {
+        return Err(StdError::generic_err(
+            "This is an admin command and can only be run from the admin address",
+        ));
+    }
+    Ok(())
+}
+
+/// checks that a channel is registered
+fn check_channel_exists(storage: &dyn Storage, channel: &String) -> StdResult<()> {
+    if !channels_store().contains(storage, channel) {
+        return Err(StdError::generic_err(format!("`{}` channel is undefined", channel)));
+    }
+    Ok(())
+}
+
 ///
 /// ListChannels query
 /// 
 ///   Public query to list all notification channels.
 /// 
 pub fn query_list_channels(deps: Deps) -> StdResult<Binary> {
-    let channels: Vec<String> = CHANNELS
+    let channels: Vec<String> = channels_store()
         .iter(deps.storage)?
         .map(|channel| channel.unwrap())
         .collect();
     to_binary(&QueryAnswer::ListChannels { channels })
 }
 
+///
+/// NotificationInbox query
+/// 
+///   Authenticated query allows clients that missed notifications, e.g. while offline,
+///   to fetch the last ones kept for them on a channel. Only notifications whose inbox
+///   counter is at least `since_counter` are returned, oldest first. The returned
+///   `counter` is the `since_counter` to pass next time; a first notification with a
+///   higher counter than requested means that older ones were overwritten. Notifications
+///   stay encrypted with the seed that was current when they were sent.
+/// 
+pub fn query_notification_inbox(
+    deps: Deps,
+    channel: String,
+    since_counter: Option<Uint64>,
+    sender_raw: CanonicalAddr,
+) -> StdResult<Binary> {
+    check_channel_exists(deps.storage, &channel)?;
+    let since_counter = since_counter.map(|counter| counter.u64()).unwrap_or(0);
+    let (counter, stored) = load_inbox(deps.storage, &channel, &sender_raw, since_counter)?;
+    let notifications = stored
+        .into_iter()
+        .map(|notification| InboxNotification {
+            counter: Uint64::from(notification.counter),
+            id: Binary::from(notification.id),
+            nonce: Binary::from(notification.nonce),
+            aad: notification.aad,
+            data: Binary::from(notification.data),
+        })
+        .collect();
+
+    to_binary(&QueryAnswer::NotificationInbox {
+        channel,
+        counter: Uint64::from(counter),
+        notifications,
+    })
+}
+
 ///
 /// ChannelInfo query
 /// 
 ///   Authenticated query allows clients to obtain the seed, counter, 
-///   and Notification ID of a future event, for a specific channel.
+///   and Notification ID of a future event, for a specific channel, and the epoch
+///   of the internal secret their seed may be derived from.
 /// 
 pub fn query_channel_info(
     deps: Deps,
@@ -85,23 +322,35 @@ pub fn query_channel_info(
 ) -> StdResult<Binary> {
     let mut channels_result: Vec<ChannelInfo> = vec![];
     for channel in channels {
-        let next_id = notification_id(deps.storage, &sender_raw, &channel)?;
-        let counter = Uint64::from(get_count(deps.storage, &channel, &sender_raw));
-        let schema = CHANNEL_SCHEMATA.get(deps.storage, &channel);
+        let mode = channel_mode(deps.storage, &channel)?;
+        // txhash mode ids come from the notifying transaction, so there is no next id
+        let (counter, next_id) = match mode {
+            ChannelMode::Counter => (
+                Some(Uint64::from(get_count(deps.storage, &channel, &sender_raw))),
+                Some(notification_id(deps.storage, &sender_raw, &channel)?),
+            ),
+            ChannelMode::Txhash => (None, None),
+        };
+        let schema = channel_schemata_store().get(deps.storage, &channel);
+        let version = schema_version(deps.storage, &channel)?;
+        let len = data_len(deps.storage, &channel)? as u32;
         channels_result.push(
             ChannelInfo {
                 seed: get_seed(deps.storage, &sender_raw)?,
                 channel,
-                mode: ChannelMode::Counter,
+                mode,
                 counter, 
                 next_id, 
                 cddl: schema,
+                schema_version: version,
+                data_len: len,
             }
         )
     }
 
     to_binary(&QueryAnswer::ChannelInfo {
         as_of_block: Uint64::from(env.block.height),
+        epoch: secret_epoch(deps.storage)?,
         channels: channels_result,
     })
 }
@@ -110,7 +359,8 @@ pub fn query_channel_info(
 /// fn validate_signed_doc
 /// 
 ///   Validates a signed doc to verify the signature is correct. Returns the account
-///   derived from the public key.
+///   derived from the public key. The signed bytes depend on the doc's format: either
+///   the amino `notification_seed` document or an ADR-036 document signed by the account.
 /// 
 fn validate_signed_doc(
     api: &dyn Api,
@@ -123,9 +373,13 @@ fn validate_signed_doc(
     let pubkey = &signed_doc.signature.pub_key.value;
 
     let base32_addr = pubkey_to_account(pubkey).0.as_slice().to_base32();
-    let account: String = bech32::encode(account_hrp, base32_addr, bech32::Variant::Bech32).unwrap();
+    let account: String = bech32::encode(account_hrp, base32_addr, bech32::Variant::Bech32)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
 
-    let signed_bytes = to_binary(&Document::from_params(&signed_doc.params))?;
+    let signed_bytes = match signed_doc.format.unwrap_or_default() {
+        DocFormat::NotificationSeed => to_binary(&Document::from_params(&signed_doc.params))?,
+        DocFormat::Adr036 => to_binary(&Adr036Document::from_params(&signed_doc.params, &account)?)?,
+    };
     let signed_bytes_hash = sha_256(signed_bytes.as_slice());
 
     let verified = api
@@ -185,6 +439,40 @@ pub fn notification_id(
     Ok(Binary::from(code_bytes.as_slice()))
 }
 
+/// 
+/// fn txhash_notification_id
+/// 
+///   Returns the notification id for the given address and channel id on a channel
+///   running in txhash mode.
+/// 
+/// pseudocode:
+/// 
+/// fun txhashNotificationIDFor(recipientAddr, channelId, txHash) {
+///   let seed := getSeedFor(recipientAddr)
+///   let material := concatStrings(channelId, ":", txHash)
+///   let notificationID := hmac_sha256(key=seed, message=utf8ToBytes(material))
+///
+///   return notificationID
+/// }
+/// 
+pub fn txhash_notification_id(
+    storage: &dyn Storage,
+    addr: &CanonicalAddr,
+    channel: &String,
+    tx_hash: &str,
+) -> StdResult<Binary> {
+    let seed = get_seed(storage, addr)?;
+    let material = [
+        channel.as_bytes(),
+        ":".as_bytes(),
+        tx_hash.as_bytes(),
+    ].concat();
+
+    let mut mac: HmacSha256 = HmacSha256::new_from_slice(seed.0.as_slice()).unwrap();
+    mac.update(material.as_slice());
+    Ok(Binary::from(mac.finalize().into_bytes().to_vec()))
+}
+
 /// 
 /// fn encrypt_notification_data
 /// 
@@ -208,8 +496,8 @@ pub fn notification_id(
 ///   // produce the nonce by XOR'ing the two previous 12-byte results
 ///   let nonce := xorBytes(channelIdBytes, counterBytes)
 ///
-///   // right-pad the plaintext with 0x00 bytes until it is of the desired length (keep in mind, payload adds 16 bytes for tag)
-///   let message := concat(plaintext, zeros(DATA_LEN - len(plaintext)))
+///   // right-pad the plaintext with 0x00 bytes until it is of the channel's data length (keep in mind, payload adds 16 bytes for tag)
+///   let message := concat(plaintext, zeros(getDataLenFor(channelId) - len(plaintext)))
 ///
 ///   // construct the additional authenticated data
 ///   let aad := concatStrings(env.blockHeight, ":", env.senderAddress)
@@ -234,14 +522,10 @@ pub fn encrypt_notification_data(
 ) -> StdResult<Binary> {
     let counter = get_count(storage, channel, recipient);
     let mut padded_plaintext = plaintext.clone();
-    zero_pad(&mut padded_plaintext, DATA_LEN);
+    zero_pad(&mut padded_plaintext, data_len(storage, channel)?);
 
     let seed = get_seed(storage, recipient)?;
-    let channel_id_bytes = sha_256(channel.as_bytes())[..12].to_vec();
-    let counter_bytes = [&[0_u8, 0_u8, 0_u8, 0_u8], counter.to_be_bytes().as_slice()].concat();
-    let nonce: Vec<u8> = channel_id_bytes.iter().zip(counter_bytes.iter()).map(|(&b1, &b2)| b1 ^ b2 ).collect();
-    // TODO: add option to use tx hash instead of sender in aad
-    //       requires tx hash to be added to `env`
+    let nonce = counter_nonce(channel, counter);
     let aad = format!("{}:{}", env.block.height, sender.to_string());
 
     // encrypt notification data for this event
@@ -255,6 +539,174 @@ pub fn encrypt_notification_data(
     Ok(Binary::from(tag_ciphertext.clone()))
 }
 
+/// 
+/// fn encrypt_txhash_notification_data
+/// 
+///   Returns encrypted bytes given plaintext bytes, address, and channel id on a
+///   channel running in txhash mode.
+/// 
+/// pseudocode:
+/// 
+/// fun encryptTxhashNotificationData(recipientAddr, channelId, plaintext, env) {
+///   let seed := getSeedFor(recipientAddr)
+///
+///   // take the first 12 bytes of the channel id's sha256 hash and of the tx hash
+///   let channelIdBytes := slice(sha256(utf8ToBytes(channelId)), 0, 12)
+///   let txHashBytes := slice(hexToBytes(env.txHash), 0, 12)
+///
+///   // produce the nonce by XOR'ing the two previous 12-byte results
+///   let nonce := xorBytes(channelIdBytes, txHashBytes)
+///
+///   // right-pad the plaintext with 0x00 bytes until it is of the channel's data length
+///   let message := concat(plaintext, zeros(getDataLenFor(channelId) - len(plaintext)))
+///
+///   // construct the additional authenticated data
+///   let aad := concatStrings(env.blockHeight, ":", env.txHash)
+///
+///   let [ciphertext, tag] := chacha20poly1305_encrypt(key=seed, nonce=nonce, message=message, aad=aad)
+///
+///   return concat(ciphertext, tag)
+/// }
+/// 
+pub fn encrypt_txhash_notification_data(
+    storage: &dyn Storage,
+    env: &Env,
+    recipient: &CanonicalAddr,
+    channel: &String,
+    tx_hash: &str,
+    plaintext: Vec<u8>,
+) -> StdResult<Binary> {
+    let mut padded_plaintext = plaintext;
+    zero_pad(&mut padded_plaintext, data_len(storage, channel)?);
+
+    let seed = get_seed(storage, recipient)?;
+    let nonce = txhash_nonce(channel, tx_hash)?;
+    let aad = format!("{}:{}", env.block.height, tx_hash);
+
+    let tag_ciphertext = cipher_data(
+        seed.0.as_slice(),
+        nonce.as_slice(),
+        padded_plaintext.as_slice(),
+        aad.as_bytes()
+    )?;
+
+    Ok(Binary::from(tag_ciphertext))
+}
+
+/// nonce of a counter mode notification
+fn counter_nonce(channel: &String, counter: u64) -> Vec<u8> {
+    let channel_id_bytes = sha_256(channel.as_bytes())[..12].to_vec();
+    let counter_bytes = [&[0_u8, 0_u8, 0_u8, 0_u8], counter.to_be_bytes().as_slice()].concat();
+    channel_id_bytes.iter().zip(counter_bytes.iter()).map(|(&b1, &b2)| b1 ^ b2 ).collect()
+}
+
+/// nonce of a txhash mode notification
+fn txhash_nonce(channel: &String, tx_hash: &str) -> StdResult<Vec<u8>> {
+    let channel_id_bytes = sha_256(channel.as_bytes())[..12].to_vec();
+    let tx_hash_bytes = decode_hex(tx_hash)?;
+    if tx_hash_bytes.len() < 12 {
+        return Err(StdError::generic_err("Transaction hash is too short"));
+    }
+    Ok(channel_id_bytes.iter().zip(tx_hash_bytes.iter()).map(|(&b1, &b2)| b1 ^ b2 ).collect())
+}
+
+
+/// 
+/// fn render_notification
+/// 
+///   Returns the notification id and encrypted data of the next notification to
+///   the given recipient on the given channel, and increments the recipient's
+///   counter for that channel.
+/// 
+///   Channels in txhash mode derive the id and nonce from the transaction hash
+///   instead, falling back to the counter when the runtime does not provide one.
+///   Their data is a CBOR array of every payload sent to the recipient on the
+///   channel in the transaction, so a later notification in the same transaction
+///   is rendered together with the earlier ones, replacing them.
+/// 
+///   Every rendered notification is also kept in the recipient's inbox for the
+///   channel, so that clients who missed it can fetch it with NotificationInbox.
+/// 
+pub fn render_notification(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    recipient: &CanonicalAddr,
+    channel: &str,
+    plaintext: Vec<u8>,
+) -> StdResult<(Binary, Binary)> {
+    let channel = channel.to_string();
+    if let (ChannelMode::Txhash, Some(transaction)) = (channel_mode(storage, &channel)?, &env.transaction) {
+        let payloads = push_txhash_payload(storage, &channel, recipient, &transaction.hash, plaintext)?;
+        let id = txhash_notification_id(storage, recipient, &channel, &transaction.hash)?;
+        let encrypted_data = encrypt_txhash_notification_data(
+            storage,
+            env,
+            recipient,
+            &channel,
+            &transaction.hash,
+            cbor_array(&payloads),
+        )?;
+        let notification = StoredInboxNotification {
+            counter: 0,
+            id: id.to_vec(),
+            nonce: txhash_nonce(&channel, &transaction.hash)?,
+            aad: format!("{}:{}", env.block.height, transaction.hash),
+            data: encrypted_data.to_vec(),
+        };
+        if payloads.len() == 1 {
+            push_inbox(storage, &channel, recipient, notification)?;
+        } else {
+            replace_latest_inbox(storage, &channel, recipient, notification)?;
+        }
+        return Ok((id, encrypted_data));
+    }
+    let counter = get_count(storage, &channel, recipient);
+    let id = notification_id(storage, recipient, &channel)?;
+    let encrypted_data = encrypt_notification_data(
+        storage,
+        env,
+        sender,
+        recipient,
+        &channel,
+        plaintext,
+    )?;
+    increment_count(storage, &channel, recipient)?;
+    push_inbox(storage, &channel, recipient, StoredInboxNotification {
+        counter: 0,
+        id: id.to_vec(),
+        nonce: counter_nonce(&channel, counter),
+        aad: format!("{}:{}", env.block.height, sender),
+        data: encrypted_data.to_vec(),
+    })?;
+    Ok((id, encrypted_data))
+}
+
+/// adds rendered notifications to a response as plaintext log attributes.  A txhash mode
+/// notification rendered again in the same transaction replaces the earlier one
+pub fn add_notifications(
+    response: Response,
+    notifications: Vec<(Binary, Binary)>,
+) -> Response {
+    notifications.into_iter().fold(response, |mut response, (id, encrypted_data)| {
+        let key = id.to_base64();
+        response.attributes.retain(|attr| attr.key != key);
+        response.add_attribute_plaintext(key, encrypted_data.to_base64())
+    })
+}
+
+/// wraps already encoded CBOR items in a CBOR array
+fn cbor_array(items: &[Vec<u8>]) -> Vec<u8> {
+    let len = items.len();
+    let mut array = match len {
+        0..=23 => vec![0x80 | len as u8],
+        24..=0xff => vec![0x98, len as u8],
+        0x100..=0xffff => [&[0x99_u8][..], &(len as u16).to_be_bytes()].concat(),
+        _ => [&[0x9a_u8][..], &(len as u32).to_be_bytes()].concat(),
+    };
+    items.iter().for_each(|item| array.extend_from_slice(item));
+    array
+}
 
 /// Take a Vec<u8> and pad it up to a multiple of `block_size`, using 0x00 at the end.
 fn zero_pad(message: &mut Vec<u8>, block_size: usize) -> &mut Vec<u8> {
@@ -268,4 +720,501 @@ fn zero_pad(message: &mut Vec<u8>, block_size: usize) -> &mut Vec<u8> {
     message.reserve(missing);
     message.extend(std::iter::repeat(0x00).take(missing));
     message
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::*;
+    use cosmwasm_std::{from_binary, OwnedDeps};
+    use crate::contract::{execute, instantiate, query};
+    use crate::msg::{ExecuteMsg, InstantiateConfig, InstantiateMsg, QueryMsg};
+    use crate::snip52_channel::{
+        NFT_RECEIVED_CHANNEL_ID, OPPONENT_ATTACKED_CHANNEL_DATA_LEN, OPPONENT_ATTACKED_CHANNEL_ID, PLAYER_JOINED_CHANNEL_ID,
+    };
+    use crate::test_helpers::{extract_error_msg, init_helper_default};
+    use super::*;
+
+    #[test]
+    fn test_txhash_channel_mode() {
+        let (init_result, mut deps) = init_helper_default();
+        init_result.unwrap();
+        let opponent_attacked = OPPONENT_ATTACKED_CHANNEL_ID.to_string();
+        let bob = Addr::unchecked("bob");
+        let alice_raw = deps.api.addr_canonicalize("alice").unwrap();
+        let set_mode = |channel: &str| ExecuteMsg::SetChannelMode {
+            channel: channel.to_string(),
+            mode: ChannelMode::Txhash,
+            padding: None,
+        };
+
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), set_mode(&opponent_attacked));
+        assert!(extract_error_msg(exec_result).contains("admin command"));
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), set_mode("no_such_channel"));
+        assert!(extract_error_msg(exec_result).contains("undefined"));
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), set_mode(&opponent_attacked)).unwrap();
+
+        // channel info reports the mode and no counter
+        let env = mock_env();
+        let info = query_channel_info(deps.as_ref(), &env, vec![opponent_attacked.clone()], alice_raw.clone()).unwrap();
+        match from_binary(&info).unwrap() {
+            QueryAnswer::ChannelInfo { channels, .. } => {
+                assert_eq!(channels[0].mode, ChannelMode::Txhash);
+                assert_eq!(channels[0].counter, None);
+                assert_eq!(channels[0].next_id, None);
+            }
+            _ => panic!("unexpected"),
+        }
+
+        // the id comes from the tx hash and the counter is left alone
+        let tx_hash = env.transaction.as_ref().unwrap().hash.clone();
+        let id = txhash_notification_id(&deps.storage, &alice_raw, &opponent_attacked, &tx_hash).unwrap();
+        let payload = minicbor_ser::to_vec(&("game1", 0_u8, 0_u8)).unwrap();
+        let (first_id, _) = render_notification(
+            &mut deps.storage, &env, &bob, &alice_raw, &opponent_attacked, payload,
+        ).unwrap();
+        assert_eq!(first_id, id);
+        assert_eq!(get_count(&deps.storage, &opponent_attacked, &alice_raw), 0);
+
+        // a second notification in the same tx is rendered together with the first
+        let payload = minicbor_ser::to_vec(&("game2", 5_u8, 1_u8)).unwrap();
+        let (second_id, encrypted_data) = render_notification(
+            &mut deps.storage, &env, &bob, &alice_raw, &opponent_attacked, payload,
+        ).unwrap();
+        assert_eq!(second_id, id);
+        let plaintext = decrypt_txhash(&deps, &alice_raw, &opponent_attacked, &encrypted_data);
+        let payloads: Vec<(String, u8, u8)> = minicbor_ser::from_slice(&plaintext).unwrap();
+        assert_eq!(payloads.len(), 2);
+        assert_eq!(payloads[0], ("game1".to_string(), 0, 0));
+        assert_eq!(payloads[1], ("game2".to_string(), 5, 1));
+
+        // without a tx hash the channel falls back to the counter
+        let mut env = mock_env();
+        env.transaction = None;
+        render_notification(
+            &mut deps.storage, &env, &bob, &alice_raw, &opponent_attacked, vec![1],
+        ).unwrap();
+        assert_eq!(get_count(&deps.storage, &opponent_attacked, &alice_raw), 1);
+    }
+
+    /// decrypts a notification rendered to the recipient on a txhash mode channel in mock_env's tx
+    fn decrypt_txhash(
+        deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        recipient: &CanonicalAddr,
+        channel: &str,
+        encrypted_data: &[u8],
+    ) -> Vec<u8> {
+        use crate::client::{decrypt_notification, txhash_notification_aad, txhash_notification_nonce};
+
+        let env = mock_env();
+        let tx_hash = env.transaction.unwrap().hash;
+        decrypt_notification(
+            get_seed(&deps.storage, recipient).unwrap().as_slice(),
+            &txhash_notification_nonce(channel, &tx_hash).unwrap(),
+            &txhash_notification_aad(env.block.height, &tx_hash),
+            encrypted_data,
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_txhash_batch_transfer() {
+        use crate::msg::Transfer;
+
+        let (init_result, mut deps) = init_helper_default();
+        init_result.unwrap();
+        let nft_received = NFT_RECEIVED_CHANNEL_ID.to_string();
+        let set_mode = ExecuteMsg::SetChannelMode {
+            channel: nft_received.clone(),
+            mode: ChannelMode::Txhash,
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), set_mode).unwrap();
+        for token_id in ["NFT3", "NFT4"] {
+            let execute_msg = ExecuteMsg::MintNft {
+                token_id: Some(token_id.to_string()),
+                owner: Some("alice".to_string()),
+                public_metadata: None,
+                private_metadata: None,
+                royalty_info: None,
+                serial_number: None,
+                transferable: None,
+                memo: None,
+                padding: None,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), execute_msg).unwrap();
+        }
+
+        // both tokens sent to charlie in one tx arrive in a single notification
+        let execute_msg = ExecuteMsg::BatchTransferNft {
+            transfers: vec![Transfer {
+                recipient: "charlie".to_string(),
+                token_ids: vec!["NFT3".to_string(), "NFT4".to_string()],
+                memo: None,
+            }],
+            padding: None,
+        };
+        let response = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), execute_msg).unwrap();
+        let charlie_raw = deps.api.addr_canonicalize("charlie").unwrap();
+        let tx_hash = mock_env().transaction.unwrap().hash;
+        let id = txhash_notification_id(&deps.storage, &charlie_raw, &nft_received, &tx_hash).unwrap();
+        let attributes: Vec<_> = response.attributes.iter().filter(|attr| attr.key == id.to_base64()).collect();
+        assert_eq!(attributes.len(), 1);
+        let encrypted_data = Binary::from_base64(&attributes[0].value).unwrap();
+        let plaintext = decrypt_txhash(&deps, &charlie_raw, &nft_received, &encrypted_data);
+        let received: Vec<(String, String)> = minicbor_ser::from_slice(&plaintext).unwrap();
+        assert_eq!(received, vec![
+            ("NFT3".to_string(), "alice".to_string()),
+            ("NFT4".to_string(), "alice".to_string()),
+        ]);
+
+        // and are kept in the inbox once
+        let inbox = query_notification_inbox(deps.as_ref(), nft_received, None, charlie_raw).unwrap();
+        match from_binary(&inbox).unwrap() {
+            QueryAnswer::NotificationInbox { counter, notifications, .. } => {
+                assert_eq!(counter.u64(), 1);
+                assert_eq!(notifications[0].data, encrypted_data);
+            }
+            _ => panic!("unexpected"),
+        }
+    }
+
+    #[test]
+    fn test_channel_management() {
+        let (init_result, mut deps) = init_helper_default();
+        init_result.unwrap();
+        let opponent_attacked = OPPONENT_ATTACKED_CHANNEL_ID.to_string();
+        let alice_raw = deps.api.addr_canonicalize("alice").unwrap();
+        let admin_exec = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, msg: ExecuteMsg| {
+            execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg)
+        };
+        let channel_info = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, channel: &str| {
+            let info = query_channel_info(deps.as_ref(), &mock_env(), vec![channel.to_string()], alice_raw.clone()).unwrap();
+            match from_binary(&info).unwrap() {
+                QueryAnswer::ChannelInfo { mut channels, .. } => channels.remove(0),
+                _ => panic!("unexpected"),
+            }
+        };
+        let list_channels = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>| {
+            match from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::ListChannels {}).unwrap()).unwrap() {
+                QueryAnswer::ListChannels { channels } => channels,
+                _ => panic!("unexpected"),
+            }
+        };
+
+        // adding a channel
+        let add_with_len = |data_len: Option<u32>| ExecuteMsg::AddChannel {
+            channel: "tournament".to_string(),
+            cddl: Some("tournament=[round:uint]".to_string()),
+            data_len,
+            padding: None,
+        };
+        let add = || add_with_len(Some(64));
+        let exec_result = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), add());
+        assert!(extract_error_msg(exec_result).contains("admin command"));
+        for data_len in [0, 4097] {
+            let exec_result = admin_exec(&mut deps, add_with_len(Some(data_len)));
+            assert!(extract_error_msg(exec_result).contains("data length must be between 1 and 4096"));
+        }
+        admin_exec(&mut deps, add()).unwrap();
+        let exec_result = admin_exec(&mut deps, add());
+        assert!(extract_error_msg(exec_result).contains("already exists"));
+        let info = channel_info(&deps, "tournament");
+        assert_eq!(info.cddl, Some("tournament=[round:uint]".to_string()));
+        assert_eq!(info.schema_version, 1);
+        assert_eq!(info.data_len, 64);
+        assert_eq!(channel_info(&deps, &opponent_attacked).data_len, OPPONENT_ATTACKED_CHANNEL_DATA_LEN);
+
+        // notification data is padded to the channel's length, or a multiple of it
+        for (plaintext_len, encrypted_len) in [(3, 64 + 16), (70, 128 + 16)] {
+            let (_, encrypted_data) = render_notification(
+                &mut deps.storage, &mock_env(), &Addr::unchecked("bob"), &alice_raw, "tournament",
+                vec![1; plaintext_len],
+            ).unwrap();
+            assert_eq!(encrypted_data.len(), encrypted_len);
+        }
+        assert!(list_channels(&deps).contains(&"tournament".to_string()));
+
+        // updating a schema bumps its version
+        let update = |channel: &str| ExecuteMsg::UpdateChannelSchema {
+            channel: channel.to_string(),
+            cddl: Some("tournament=[round:uint,game_id:text]".to_string()),
+            padding: None,
+        };
+        let exec_result = admin_exec(&mut deps, update("no_such_channel"));
+        assert!(extract_error_msg(exec_result).contains("undefined"));
+        match from_binary(&admin_exec(&mut deps, update("tournament")).unwrap().data.unwrap()).unwrap() {
+            ExecuteAnswer::UpdateChannelSchema { schema_version } => assert_eq!(schema_version, 2),
+            _ => panic!("unexpected"),
+        }
+        let info = channel_info(&deps, "tournament");
+        assert_eq!(info.cddl, Some("tournament=[round:uint,game_id:text]".to_string()));
+        assert_eq!(info.schema_version, 2);
+        assert_eq!(info.data_len, 64);
+        // channels created at instantiation start on version 1
+        assert_eq!(channel_info(&deps, &opponent_attacked).schema_version, 1);
+
+        // a removed channel is no longer listed, and the game only notifies on listed channels
+        let remove = || ExecuteMsg::RemoveChannel {
+            channel: opponent_attacked.clone(),
+            padding: None,
+        };
+        admin_exec(&mut deps, remove()).unwrap();
+        let exec_result = admin_exec(&mut deps, remove());
+        assert!(extract_error_msg(exec_result).contains("undefined"));
+        assert!(!channels_store().contains(&deps.storage, &opponent_attacked));
+        assert!(!list_channels(&deps).contains(&opponent_attacked));
+        assert_eq!(channel_info(&deps, &opponent_attacked).cddl, None);
+
+        // a channel added again never goes back to a version it had before
+        let remove = ExecuteMsg::RemoveChannel { channel: "tournament".to_string(), padding: None };
+        admin_exec(&mut deps, remove).unwrap();
+        let execute_msg = ExecuteMsg::AddChannel {
+            channel: "tournament".to_string(),
+            cddl: Some("tournament=[round:uint,winner:text]".to_string()),
+            data_len: None,
+            padding: None,
+        };
+        admin_exec(&mut deps, execute_msg).unwrap();
+        let info = channel_info(&deps, "tournament");
+        assert_eq!(info.cddl, Some("tournament=[round:uint,winner:text]".to_string()));
+        assert_eq!(info.schema_version, 3);
+        assert_eq!(info.data_len, 256);
+    }
+
+    #[test]
+    fn test_update_seed_formats() {
+        use k256::ecdsa::{signature::Signer, Signature as EcdsaSignature, SigningKey};
+        use crate::snip52_signed_doc::{DocParams, PubKey, Signature};
+
+        let init = |hrp: &str| {
+            let mut deps = mock_dependencies();
+            let init_msg = InstantiateMsg {
+                name: "sec821".to_string(),
+                symbol: "S821".to_string(),
+                admin: Some("admin".to_string()),
+                entropy: "We're going to need a bigger boat".to_string(),
+                royalty_info: None,
+                config: Some(InstantiateConfig {
+                    bech32_hrp: Some(hrp.to_string()),
+                    ..InstantiateConfig::default()
+                }),
+                post_init_callback: None,
+                template: None,
+            };
+            let result = instantiate(deps.as_mut(), mock_env(), mock_info("instantiator", &[]), init_msg);
+            (result, deps)
+        };
+        for hrp in ["", "Secret", "sec ret"] {
+            assert!(extract_error_msg(init(hrp).0).contains("Invalid bech32 prefix"));
+        }
+        let (result, mut deps) = init("tsecret");
+        result.unwrap();
+
+        let key = SigningKey::from_bytes(&[1; 32]).unwrap();
+        let pub_key = Binary::from(key.verifying_key().to_bytes().to_vec());
+        let account = |hrp: &str| {
+            let data = pubkey_to_account(&pub_key).0.as_slice().to_base32();
+            bech32::encode(hrp, data, bech32::Variant::Bech32).unwrap()
+        };
+        let sender = account("tsecret");
+        let sender_raw = deps.api.addr_canonicalize(&sender).unwrap();
+        let sign = |doc: Binary, format: Option<DocFormat>, previous_seed: &Binary| {
+            let signature: EcdsaSignature = key.sign(doc.as_slice());
+            SignedDocument {
+                params: DocParams {
+                    chain_id: "secret-4".to_string(),
+                    contract: mock_env().contract.address.to_string(),
+                    previous_seed: previous_seed.clone(),
+                },
+                signature: Signature {
+                    pub_key: PubKey {
+                        r#type: "tendermint/PubKeySecp256k1".to_string(),
+                        value: pub_key.clone(),
+                    },
+                    signature: Binary::from(signature.as_ref()),
+                },
+                format,
+            }
+        };
+        let update_seed = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, sender: &str, signed_doc| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(sender, &[]),
+                ExecuteMsg::UpdateSeed { signed_doc, padding: None },
+            )
+        };
+
+        // an ADR-036 document signed by the account with the configured prefix
+        let previous_seed = get_seed(&deps.storage, &sender_raw).unwrap();
+        let unsigned = sign(Binary::default(), None, &previous_seed);
+        let signer_doc = |signer: &str| {
+            to_binary(&Adr036Document::from_params(&unsigned.params, signer).unwrap()).unwrap()
+        };
+        let signed_doc = sign(signer_doc(&sender), Some(DocFormat::Adr036), &previous_seed);
+
+        // the same signature does not verify as the amino document
+        let mut amino = signed_doc.clone();
+        amino.format = Some(DocFormat::NotificationSeed);
+        assert!(extract_error_msg(update_seed(&mut deps, &sender, amino)).contains("Failed to verify"));
+
+        // nor when signed for the default prefix
+        let wrong_signer = sign(signer_doc(&account("secret")), Some(DocFormat::Adr036), &previous_seed);
+        assert!(extract_error_msg(update_seed(&mut deps, &sender, wrong_signer)).contains("Failed to verify"));
+
+        let response = update_seed(&mut deps, &sender, signed_doc.clone()).unwrap();
+        match from_binary(&response.data.unwrap()).unwrap() {
+            ExecuteAnswer::UpdateSeed { seed } => assert_eq!(seed, signed_doc.signature.signature),
+            _ => panic!("unexpected"),
+        }
+        let seed = get_seed(&deps.storage, &sender_raw).unwrap();
+        assert_eq!(seed.as_slice(), sha_256(signed_doc.signature.signature.as_slice()));
+
+        // the amino document still works, chaining from the new seed
+        let amino_doc = |previous_seed: &Binary| {
+            let params = sign(Binary::default(), None, previous_seed).params;
+            to_binary(&Document::from_params(&params)).unwrap()
+        };
+        let signed_doc = sign(amino_doc(&seed), None, &seed);
+        update_seed(&mut deps, &sender, signed_doc.clone()).unwrap();
+        let new_seed = get_seed(&deps.storage, &sender_raw).unwrap();
+        assert_eq!(new_seed.as_slice(), sha_256(signed_doc.signature.signature.as_slice()));
+
+        // a replay no longer matches the previous seed
+        let exec_result = update_seed(&mut deps, &sender, signed_doc);
+        assert!(extract_error_msg(exec_result).contains("Previous seed does not match"));
+    }
+
+    #[test]
+    fn test_notification_inbox() {
+        use crate::client::{decrypt_notification, notification_id as client_notification_id};
+        use crate::snip52_state::INBOX_CAPACITY;
+
+        let (init_result, mut deps) = init_helper_default();
+        init_result.unwrap();
+        let player_joined = PLAYER_JOINED_CHANNEL_ID.to_string();
+        let opponent_attacked = OPPONENT_ATTACKED_CHANNEL_ID.to_string();
+        let alice_raw = deps.api.addr_canonicalize("alice").unwrap();
+        let inbox = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, channel: &str, since_counter: Option<u64>| {
+            let inbox = query_notification_inbox(
+                deps.as_ref(), channel.to_string(), since_counter.map(Uint64::from), alice_raw.clone(),
+            );
+            match from_binary(&inbox.unwrap()).unwrap() {
+                QueryAnswer::NotificationInbox { counter, notifications, .. } => (counter.u64(), notifications),
+                _ => panic!("unexpected"),
+            }
+        };
+
+        // a notification is kept for its recipient
+        let data = minicbor_ser::to_vec(&("game1", )).unwrap();
+        render_notification(
+            &mut deps.storage, &mock_env(), &Addr::unchecked("bob"), &alice_raw, &player_joined, data,
+        ).unwrap();
+        let (counter, notifications) = inbox(&deps, &player_joined, None);
+        assert_eq!(counter, 1);
+        assert_eq!(notifications.len(), 1);
+        let seed = get_seed(&deps.storage, &alice_raw).unwrap();
+        let plaintext = decrypt_notification(
+            seed.as_slice(),
+            notifications[0].nonce.as_slice().try_into().unwrap(),
+            &notifications[0].aad,
+            notifications[0].data.as_slice(),
+        ).unwrap();
+        let (game_id, ): (String, ) = minicbor_ser::from_slice(&plaintext).unwrap();
+        assert_eq!(game_id, "game1");
+
+        // once full, the oldest notifications are overwritten
+        let total = INBOX_CAPACITY + 4;
+        for i in 0..total {
+            render_notification(
+                &mut deps.storage, &mock_env(), &Addr::unchecked("bob"), &alice_raw, &opponent_attacked, vec![i as u8],
+            ).unwrap();
+        }
+        let (counter, notifications) = inbox(&deps, &opponent_attacked, None);
+        assert_eq!(counter, total);
+        assert_eq!(notifications.len() as u64, INBOX_CAPACITY);
+        assert_eq!(notifications[0].counter.u64(), 4);
+        assert_eq!(notifications.last().unwrap().counter.u64(), total - 1);
+        let id = client_notification_id(seed.as_slice(), &opponent_attacked, 4).unwrap();
+        assert_eq!(notifications[0].id.as_slice(), id);
+        let plaintext = decrypt_notification(
+            seed.as_slice(),
+            notifications[0].nonce.as_slice().try_into().unwrap(),
+            &notifications[0].aad,
+            notifications[0].data.as_slice(),
+        ).unwrap();
+        assert_eq!(plaintext[0], 4);
+
+        // only the ones since the given counter
+        let (_, notifications) = inbox(&deps, &opponent_attacked, Some(total - 2));
+        assert_eq!(notifications.iter().map(|n| n.counter.u64()).collect::<Vec<_>>(), vec![total - 2, total - 1]);
+        assert!(inbox(&deps, &opponent_attacked, Some(total)).1.is_empty());
+
+        // txhash mode notifications are kept too
+        let set_mode = ExecuteMsg::SetChannelMode {
+            channel: opponent_attacked.clone(),
+            mode: ChannelMode::Txhash,
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), set_mode).unwrap();
+        render_notification(
+            &mut deps.storage, &mock_env(), &Addr::unchecked("bob"), &alice_raw, &opponent_attacked, vec![1],
+        ).unwrap();
+        let (counter, notifications) = inbox(&deps, &opponent_attacked, Some(total));
+        assert_eq!(counter, total + 1);
+        let tx_hash = mock_env().transaction.unwrap().hash;
+        assert_eq!(notifications[0].aad, format!("{}:{}", mock_env().block.height, tx_hash));
+
+        let unknown = query_notification_inbox(deps.as_ref(), "no_such_channel".to_string(), None, alice_raw.clone());
+        assert!(extract_error_msg(unknown).contains("undefined"));
+    }
+
+    #[test]
+    fn test_rotate_secret_and_reset_seed() {
+        let (init_result, mut deps) = init_helper_default();
+        init_result.unwrap();
+        let alice_raw = deps.api.addr_canonicalize("alice").unwrap();
+        let channel_info = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>| {
+            let info = query_channel_info(
+                deps.as_ref(), &mock_env(), vec![PLAYER_JOINED_CHANNEL_ID.to_string()], alice_raw.clone(),
+            ).unwrap();
+            match from_binary(&info).unwrap() {
+                QueryAnswer::ChannelInfo { epoch, channels, .. } => (epoch, channels[0].seed.clone()),
+                _ => panic!("unexpected"),
+            }
+        };
+        let rotate = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, sender: &str| {
+            let execute_msg = ExecuteMsg::RotateSecret {
+                entropy: "more entropy".to_string(),
+                padding: None,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), execute_msg)
+        };
+        let initial_seed = get_seed(&deps.storage, &alice_raw).unwrap();
+        assert_eq!(channel_info(&deps), (0, initial_seed.clone()));
+
+        // derived seeds change with the epoch
+        assert!(extract_error_msg(rotate(&mut deps, "alice")).contains("admin command"));
+        let response = rotate(&mut deps, "admin").unwrap();
+        match from_binary(&response.data.unwrap()).unwrap() {
+            ExecuteAnswer::RotateSecret { epoch } => assert_eq!(epoch, 1),
+            _ => panic!("unexpected"),
+        }
+        let derived_seed = get_seed(&deps.storage, &alice_raw).unwrap();
+        assert_ne!(derived_seed, initial_seed);
+        assert_eq!(channel_info(&deps), (1, derived_seed.clone()));
+
+        // seeds set with UpdateSeed survive a rotation, until reset
+        store_seed(&mut deps.storage, &alice_raw, vec![5; 64]).unwrap();
+        rotate(&mut deps, "admin").unwrap();
+        assert_eq!(channel_info(&deps), (2, Binary::from(vec![5; 64])));
+        for _ in 0..2 {
+            let reset_seed = ExecuteMsg::ResetSeed { padding: None };
+            let response = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), reset_seed).unwrap();
+            let derived_seed = get_seed(&deps.storage, &alice_raw).unwrap();
+            assert_ne!(derived_seed, Binary::from(vec![5; 64]));
+            match from_binary(&response.data.unwrap()).unwrap() {
+                ExecuteAnswer::ResetSeed { seed } => assert_eq!(seed, derived_seed),
+                _ => panic!("unexpected"),
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use cosmwasm_std::{Binary, Uint128, CanonicalAddr};
+use cosmwasm_std::{to_binary, Binary, Uint128, CanonicalAddr, StdResult};
 use schemars::JsonSchema;
 use secret_toolkit::crypto::sha_256;
 use serde::{Serialize, Deserialize};
@@ -72,6 +72,70 @@ pub struct MsgValue {
     pub previous_seed: Binary,
 }
 
+/// ADR-036 document, as signed by wallets' `signArbitrary`
+/// Note: The order of fields in this struct is important for the document signature verification!
+#[remain::sorted]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Adr036Document {
+    /// account number, always zero
+    pub account_number: Uint128,
+    /// id of chain, always empty
+    pub chain_id: String,
+    /// fee, always empty
+    pub fee: Fee,
+    /// memo, always empty
+    pub memo: String,
+    /// the single `sign/MsgSignData` message
+    pub msgs: Vec<Adr036Msg>,
+    /// sequence, always zero
+    pub sequence: Uint128,
+}
+
+impl Adr036Document {
+    /// the signed data is the JSON encoded params
+    pub fn from_params(params: &DocParams, signer: &str) -> StdResult<Self> {
+        Ok(Self {
+            account_number: Uint128::zero(),
+            chain_id: String::new(),
+            fee: Fee::adr036(),
+            memo: String::new(),
+            msgs: vec![Adr036Msg {
+                r#type: "sign/MsgSignData".to_string(),
+                value: Adr036MsgValue {
+                    data: to_binary(params)?,
+                    signer: signer.to_string(),
+                },
+            }],
+            sequence: Uint128::zero(),
+        })
+    }
+}
+
+/// ADR-036 message
+/// Note: The order of fields in this struct is important for the document signature verification!
+#[remain::sorted]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Adr036Msg {
+    /// message type: "sign/MsgSignData"
+    pub r#type: String,
+    /// message value
+    pub value: Adr036MsgValue,
+}
+
+/// ADR-036 message value
+/// Note: The order of fields in this struct is important for the document signature verification!
+#[remain::sorted]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Adr036MsgValue {
+    /// base64-encoded signed data
+    pub data: Binary,
+    /// bech32 address of the signer
+    pub signer: String,
+}
+
 // Note: The order of fields in this struct is important for the permit signature verification!
 #[remain::sorted]
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -88,6 +152,14 @@ impl Fee {
             gas: Uint128::new(1),
         }
     }
+
+    /// the empty fee of an ADR-036 document
+    pub fn adr036() -> Self {
+        Self {
+            amount: vec![],
+            gas: Uint128::zero(),
+        }
+    }
 }
 
 impl Default for Fee {
@@ -151,6 +223,17 @@ impl PubKey {
     }
 }
 
+/// Kind of document that was signed
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DocFormat {
+    /// the amino `notification_seed` document
+    #[default]
+    NotificationSeed,
+    /// an ADR-036 `sign/MsgSignData` document whose data is the JSON encoded params
+    Adr036,
+}
+
 /// Seed
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -158,6 +241,8 @@ pub struct SignedDocument {
     #[serde(bound = "")]
     pub params: DocParams,
     pub signature: Signature,
+    /// defaults to the `notification_seed` document
+    pub format: Option<DocFormat>,
 }
 
 pub fn pubkey_to_account(pubkey: &Binary) -> CanonicalAddr {
@@ -1,11 +1,50 @@
 use base64::{engine::general_purpose, Engine};
 use secret_toolkit_storage::{Keymap, Item};
-use cosmwasm_std::{CanonicalAddr, Storage, StdResult, Binary,};
+use cosmwasm_std::{CanonicalAddr, Storage, StdError, StdResult, Binary,};
+use serde::{Deserialize, Serialize};
 use crate::snip52_crypto::hkdf_sha_256;
 
 pub static INTERNAL_SECRET: Item<Vec<u8>> = Item::new(b"snip52-secret");
 pub static COUNTERS: Keymap<CanonicalAddr,u64> = Keymap::new(b"snip52-counters");
 pub static SEEDS: Keymap<CanonicalAddr,Vec<u8>> = Keymap::new(b"snip52-seeds");
+/// hash of the last transaction that notified an address on a txhash mode channel, for prefix channel id
+pub static LAST_TXHASHES: Keymap<CanonicalAddr,String> = Keymap::new(b"snip52-last-txhashes");
+/// payloads the last transaction in LAST_TXHASHES sent to an address, for prefix channel id
+pub static TXHASH_PAYLOADS: Keymap<CanonicalAddr,Vec<Vec<u8>>> = Keymap::new(b"snip52-txhash-payloads");
+/// number of times the internal secret was rotated
+pub static SECRET_EPOCH: Item<u32> = Item::new(b"snip52-secret-epoch");
+/// bech32 prefix of the accounts that sign UpdateSeed documents
+pub static BECH32_HRP: Item<String> = Item::new(b"snip52-bech32-hrp");
+/// number of notifications ever kept in an address's inbox, for prefix channel id
+pub static INBOX_COUNTERS: Keymap<CanonicalAddr,u64> = Keymap::new(b"snip52-inbox-counters");
+/// inbox slot, for suffixes channel id, address and slot number
+pub static INBOX_SLOTS: Item<StoredInboxNotification> = Item::new(b"snip52-inbox");
+
+/// number of notifications kept per address and channel for clients that missed them
+pub const INBOX_CAPACITY: u64 = 16;
+
+/// a notification kept in an inbox, with what is needed to decrypt it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StoredInboxNotification {
+    /// position in the inbox
+    pub counter: u64,
+    pub id: Vec<u8>,
+    pub nonce: Vec<u8>,
+    pub aad: String,
+    pub data: Vec<u8>,
+}
+
+pub const DEFAULT_BECH32_HRP: &str = "secret";
+
+/// get the epoch of the internal secret
+pub fn secret_epoch(storage: &dyn Storage) -> StdResult<u32> {
+    Ok(SECRET_EPOCH.may_load(storage)?.unwrap_or(0))
+}
+
+/// get the bech32 prefix of the accounts that sign UpdateSeed documents
+pub fn bech32_hrp(storage: &dyn Storage) -> StdResult<String> {
+    Ok(BECH32_HRP.may_load(storage)?.unwrap_or_else(|| DEFAULT_BECH32_HRP.to_string()))
+}
 
 /// increment counter for a given address
 pub fn increment_count(
@@ -29,6 +68,85 @@ pub fn get_count(
     COUNTERS.add_suffix(channel.as_bytes()).get(storage, addr).unwrap_or(0_u64)
 }
 
+/// adds a payload to those the transaction sent to an address on a txhash mode channel,
+/// returning all of them in the order they were sent
+pub fn push_txhash_payload(
+    storage: &mut dyn Storage,
+    channel: &String,
+    addr: &CanonicalAddr,
+    tx_hash: &String,
+    payload: Vec<u8>,
+) -> StdResult<Vec<Vec<u8>>> {
+    let last_txhashes = LAST_TXHASHES.add_suffix(channel.as_bytes());
+    let txhash_payloads = TXHASH_PAYLOADS.add_suffix(channel.as_bytes());
+    let mut payloads = if last_txhashes.get(storage, addr).as_ref() == Some(tx_hash) {
+        txhash_payloads.get(storage, addr).unwrap_or_default()
+    } else {
+        last_txhashes.insert(storage, addr, tx_hash)?;
+        vec![]
+    };
+    payloads.push(payload);
+    txhash_payloads.insert(storage, addr, &payloads)?;
+    Ok(payloads)
+}
+
+/// keeps a notification in an address's inbox, overwriting the oldest one once it is full
+pub fn push_inbox(
+    storage: &mut dyn Storage,
+    channel: &String,
+    addr: &CanonicalAddr,
+    mut notification: StoredInboxNotification,
+) -> StdResult<()> {
+    let counters = INBOX_COUNTERS.add_suffix(channel.as_bytes());
+    let counter = counters.get(storage, addr).unwrap_or(0_u64);
+    notification.counter = counter;
+    inbox_slot(channel, addr, counter).save(storage, &notification)?;
+    counters.insert(storage, addr, &(counter + 1))
+}
+
+/// replaces the newest notification in an address's inbox
+pub fn replace_latest_inbox(
+    storage: &mut dyn Storage,
+    channel: &String,
+    addr: &CanonicalAddr,
+    mut notification: StoredInboxNotification,
+) -> StdResult<()> {
+    let counter = INBOX_COUNTERS
+        .add_suffix(channel.as_bytes())
+        .get(storage, addr)
+        .and_then(|counter| counter.checked_sub(1))
+        .ok_or_else(|| StdError::generic_err("Inbox is empty"))?;
+    notification.counter = counter;
+    inbox_slot(channel, addr, counter).save(storage, &notification)
+}
+
+/// returns the number of notifications ever kept in an address's inbox, and those still
+/// kept whose counter is at least `since_counter`, oldest first
+pub fn load_inbox(
+    storage: &dyn Storage,
+    channel: &String,
+    addr: &CanonicalAddr,
+    since_counter: u64,
+) -> StdResult<(u64, Vec<StoredInboxNotification>)> {
+    let counter = INBOX_COUNTERS.add_suffix(channel.as_bytes()).get(storage, addr).unwrap_or(0_u64);
+    let oldest = counter.saturating_sub(INBOX_CAPACITY).max(since_counter);
+    let notifications = (oldest..counter)
+        .map(|slot_counter| inbox_slot(channel, addr, slot_counter).load(storage))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok((counter, notifications))
+}
+
+fn inbox_slot<'a>(
+    channel: &String,
+    addr: &CanonicalAddr,
+    counter: u64,
+) -> Item<'a, StoredInboxNotification> {
+    INBOX_SLOTS
+        .add_suffix(channel.as_bytes())
+        .add_suffix(addr.as_slice())
+        .add_suffix(&(counter % INBOX_CAPACITY).to_be_bytes())
+}
+
 /// store the seed for a given address
 #[inline]
 pub fn store_seed(
@@ -39,6 +157,17 @@ pub fn store_seed(
     SEEDS.insert(storage, addr, &seed)
 }
 
+/// remove the seed set for a given address, if any
+pub fn remove_seed(
+    storage: &mut dyn Storage,
+    addr: &CanonicalAddr,
+) -> StdResult<()> {
+    if SEEDS.contains(storage, addr) {
+        SEEDS.remove(storage, addr)?;
+    }
+    Ok(())
+}
+
 /// get the seed for a given address
 /// fun getSeedFor(recipientAddr) {
 ///   // recipient has a shared secret with contract
@@ -1,14 +1,14 @@
 use std::any::type_name;
-use cosmwasm_std::{Api, BlockInfo, CanonicalAddr, StdError, StdResult, Storage, ContractInfo};
+use cosmwasm_std::{Api, BlockInfo, CanonicalAddr, StdError, StdResult, Storage, ContractInfo, Uint128};
 use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
 use secret_toolkit::{
     serialization::{Bincode2, Json, Serde},
-    storage::AppendStore,
+    storage::{AppendStore, Item},
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::expiration::Expiration;
-use crate::msg::{Tx, TxAction};
+use crate::msg::{Tx, TxAction, TxActionFilter};
 
 /// storage key for config
 pub const CONFIG_KEY: &[u8] = b"config";
@@ -52,6 +52,10 @@ pub const VIEWING_KEY_ERR_MSG: &str = "Wrong viewing key for this address or vie
 
 // append store for user's list of tx ids
 pub static TX_ID_STORE: AppendStore<u64> = AppendStore::new(b"txid");
+// append store for user's list of tx ids of each type, for prefixes address and tx type
+pub static TX_ID_BY_TYPE_STORE: AppendStore<u64> = AppendStore::new(b"txidbytype");
+// number of the user's tx ids recorded before they were indexed by type, for prefix address
+pub static TX_ID_BY_TYPE_START_STORE: Item<u32> = Item::new(b"txidbytypestart");
 
 /// Token contract config
 #[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Eq)]
@@ -119,6 +123,59 @@ pub enum StoredTxAction {
         /// burner's address if not owner
         burner: Option<CanonicalAddr>,
     },
+    /// token started a battleship game
+    GameStarted {
+        /// id of the game
+        game_id: String,
+        /// token's owner at the time
+        owner: CanonicalAddr,
+        /// token id of the opponent
+        opponent_token_id: String,
+        /// amount each player wagered
+        wager: Uint128,
+    },
+    /// token won a battleship game
+    GameWon {
+        /// id of the game
+        game_id: String,
+        /// token's owner at the time
+        owner: CanonicalAddr,
+        /// token id of the opponent
+        opponent_token_id: String,
+    },
+    /// token lost a battleship game
+    GameLost {
+        /// id of the game
+        game_id: String,
+        /// token's owner at the time
+        owner: CanonicalAddr,
+        /// token id of the opponent
+        opponent_token_id: String,
+    },
+    /// winnings or a refunded wager were paid to the token's owner
+    WagerPaid {
+        /// id of the game
+        game_id: String,
+        /// address that was paid
+        owner: CanonicalAddr,
+        /// amount of uscrt paid
+        amount: Uint128,
+    },
+}
+
+impl StoredTxAction {
+    /// Returns TxActionFilter of the type of this action
+    pub fn kind(&self) -> TxActionFilter {
+        match self {
+            StoredTxAction::Transfer { .. } => TxActionFilter::Transfer,
+            StoredTxAction::Mint { .. } => TxActionFilter::Mint,
+            StoredTxAction::Burn { .. } => TxActionFilter::Burn,
+            StoredTxAction::GameStarted { .. } => TxActionFilter::GameStarted,
+            StoredTxAction::GameWon { .. } => TxActionFilter::GameWon,
+            StoredTxAction::GameLost { .. } => TxActionFilter::GameLost,
+            StoredTxAction::WagerPaid { .. } => TxActionFilter::WagerPaid,
+        }
+    }
 }
 
 /// tx in storage
@@ -178,6 +235,44 @@ impl StoredTx {
                     burner: bnr,
                 }
             }
+            StoredTxAction::GameStarted {
+                game_id,
+                owner,
+                opponent_token_id,
+                wager,
+            } => TxAction::GameStarted {
+                game_id,
+                owner: api.addr_humanize(&owner)?,
+                opponent_token_id,
+                wager,
+            },
+            StoredTxAction::GameWon {
+                game_id,
+                owner,
+                opponent_token_id,
+            } => TxAction::GameWon {
+                game_id,
+                owner: api.addr_humanize(&owner)?,
+                opponent_token_id,
+            },
+            StoredTxAction::GameLost {
+                game_id,
+                owner,
+                opponent_token_id,
+            } => TxAction::GameLost {
+                game_id,
+                owner: api.addr_humanize(&owner)?,
+                opponent_token_id,
+            },
+            StoredTxAction::WagerPaid {
+                game_id,
+                owner,
+                amount,
+            } => TxAction::WagerPaid {
+                game_id,
+                owner: api.addr_humanize(&owner)?,
+                amount,
+            },
         };
         let tx = Tx {
             tx_id: self.tx_id,
@@ -236,11 +331,12 @@ pub fn store_transfer(
         recipient,
     } = tx.action
     {
-        append_tx_for_addr(storage, config.tx_cnt, &from)?;
-        append_tx_for_addr(storage, config.tx_cnt, &recipient)?;
+        let kind = TxActionFilter::Transfer;
+        append_tx_for_addr(storage, config.tx_cnt, &from, kind)?;
+        append_tx_for_addr(storage, config.tx_cnt, &recipient, kind)?;
         if let Some(sndr) = sender.as_ref() {
             if *sndr != recipient {
-                append_tx_for_addr(storage, config.tx_cnt, sndr)?;
+                append_tx_for_addr(storage, config.tx_cnt, sndr, kind)?;
             }
         }
     }
@@ -280,15 +376,54 @@ pub fn store_mint(
     let mut tx_store = PrefixedStorage::new(storage, PREFIX_TXS);
     json_save(&mut tx_store, &config.tx_cnt.to_le_bytes(), &tx)?;
     if let StoredTxAction::Mint { minter, recipient } = tx.action {
-        append_tx_for_addr(storage, config.tx_cnt, &recipient)?;
+        append_tx_for_addr(storage, config.tx_cnt, &recipient, TxActionFilter::Mint)?;
         if recipient != minter {
-            append_tx_for_addr(storage, config.tx_cnt, &minter)?;
+            append_tx_for_addr(storage, config.tx_cnt, &minter, TxActionFilter::Mint)?;
         }
     }
     config.tx_cnt += 1;
     Ok(())
 }
 
+/// Returns StdResult<()> after storing a battleship game tx
+///
+/// # Arguments
+///
+/// * `storage` - a mutable reference to the storage this item should go to
+/// * `config` - a mutable reference to the contract Config
+/// * `block` - a reference to the current BlockInfo
+/// * `token_id` - token id that played the game
+/// * `action` - one of the game actions
+pub fn store_game_event(
+    storage: &mut dyn Storage,
+    config: &mut Config,
+    block: &BlockInfo,
+    token_id: String,
+    action: StoredTxAction,
+) -> StdResult<()> {
+    let owner = match &action {
+        StoredTxAction::GameStarted { owner, .. }
+        | StoredTxAction::GameWon { owner, .. }
+        | StoredTxAction::GameLost { owner, .. }
+        | StoredTxAction::WagerPaid { owner, .. } => owner.clone(),
+        _ => return Err(StdError::generic_err("Not a game tx")),
+    };
+    let kind = action.kind();
+    let tx = StoredTx {
+        tx_id: config.tx_cnt,
+        block_height: block.height,
+        block_time: block.time.seconds(),
+        token_id,
+        action,
+        memo: None,
+    };
+    let mut tx_store = PrefixedStorage::new(storage, PREFIX_TXS);
+    json_save(&mut tx_store, &config.tx_cnt.to_le_bytes(), &tx)?;
+    append_tx_for_addr(storage, config.tx_cnt, &owner, kind)?;
+    config.tx_cnt += 1;
+    Ok(())
+}
+
 /// Returns StdResult<()> after saving tx id
 ///
 /// # Arguments
@@ -296,13 +431,24 @@ pub fn store_mint(
 /// * `storage` - a mutable reference to the storage this item should go to
 /// * `tx_id` - the tx id to store
 /// * `address` - a reference to the address for which to store this tx id
+/// * `kind` - the type of the tx, which also indexes it for filtered queries
 fn append_tx_for_addr(
     storage: &mut dyn Storage,
     tx_id: u64,
     address: &CanonicalAddr,
+    kind: TxActionFilter,
 ) -> StdResult<()> {
     let addr_store = TX_ID_STORE.add_suffix(address.as_slice());
-    addr_store.push(storage, &tx_id)
+    let start_store = TX_ID_BY_TYPE_START_STORE.add_suffix(address.as_slice());
+    if start_store.may_load(storage)?.is_none() {
+        let start = addr_store.get_len(storage)?;
+        start_store.save(storage, &start)?;
+    }
+    addr_store.push(storage, &tx_id)?;
+    TX_ID_BY_TYPE_STORE
+        .add_suffix(address.as_slice())
+        .add_suffix(&[kind as u8])
+        .push(storage, &tx_id)
 }
 
 /// Returns StdResult<(Vec<Tx>, u64)> of the txs to display and the total count of txs
@@ -314,14 +460,83 @@ fn append_tx_for_addr(
 /// * `address` - a reference to the address whose txs to display
 /// * `page` - page to start displaying
 /// * `page_size` - number of txs per page
+/// * `filter` - optional list of action types to display (the count is of matching txs)
 pub fn get_txs(
     api: &dyn Api,
     storage: &dyn Storage,
     address: &CanonicalAddr,
     page: u32,
     page_size: u32,
+    filter: Option<&[TxActionFilter]>,
 ) -> StdResult<(Vec<Tx>, u64)> {
     let addr_store = TX_ID_STORE.add_suffix(address.as_slice());
+    if let Some(filter) = filter {
+        let mut kinds = filter.to_vec();
+        kinds.sort_by_key(|kind| *kind as u8);
+        kinds.dedup();
+        let type_stores: Vec<AppendStore<u64>> = kinds
+            .iter()
+            .map(|kind| TX_ID_BY_TYPE_STORE.add_suffix(address.as_slice()).add_suffix(&[*kind as u8]))
+            .collect();
+        let mut count = 0u64;
+        let mut iters = Vec::with_capacity(type_stores.len());
+        for type_store in type_stores.iter() {
+            count += type_store.get_len(storage)? as u64;
+            iters.push(type_store.iter(storage)?.rev());
+        }
+        // merge the id lists of each type, latest first, until the page is filled
+        let mut heads = iters
+            .iter_mut()
+            .map(|iter| iter.next().transpose())
+            .collect::<StdResult<Vec<Option<u64>>>>()?;
+        let skip = (page * page_size) as usize;
+        let mut ids: Vec<u64> = Vec::new();
+        while ids.len() < skip + page_size as usize {
+            let latest = heads
+                .iter()
+                .enumerate()
+                .filter_map(|(i, head)| head.map(|id| (i, id)))
+                .max_by_key(|(_, id)| *id);
+            match latest {
+                Some((i, id)) => {
+                    ids.push(id);
+                    heads[i] = iters[i].next().transpose()?;
+                }
+                None => break,
+            }
+        }
+        let tx_store = ReadonlyPrefixedStorage::new(storage, PREFIX_TXS);
+        let mut txs = ids
+            .iter()
+            .skip(skip)
+            .map(|id| json_load(&tx_store, &id.to_le_bytes()))
+            .collect::<StdResult<Vec<StoredTx>>>()?;
+        // txs recorded before the type index existed are older than every indexed one, and
+        // have to be loaded to check their type
+        let unindexed = match TX_ID_BY_TYPE_START_STORE
+            .add_suffix(address.as_slice())
+            .may_load(storage)?
+        {
+            Some(start) => start,
+            None => addr_store.get_len(storage)?,
+        };
+        for pos in (0..unindexed).rev() {
+            let id = addr_store.get_at(storage, pos)?;
+            let tx: StoredTx = json_load(&tx_store, &id.to_le_bytes())?;
+            if !kinds.contains(&tx.action.kind()) {
+                continue;
+            }
+            if count as usize >= skip && txs.len() < page_size as usize {
+                txs.push(tx);
+            }
+            count += 1;
+        }
+        let txs = txs
+            .into_iter()
+            .map(|tx| tx.into_humanized(api))
+            .collect::<StdResult<Vec<Tx>>>()?;
+        return Ok((txs, count));
+    }
 
     let count = addr_store.get_len(storage)? as u64;
     // access tx storage
@@ -0,0 +1,39 @@
+//! Fixtures shared by the unit test modules
+use std::any::Any;
+
+use cosmwasm_std::testing::*;
+use cosmwasm_std::{OwnedDeps, Response, StdError, StdResult};
+
+use crate::contract::instantiate;
+use crate::msg::InstantiateMsg;
+
+pub fn init_helper_default() -> (
+    StdResult<Response>,
+    OwnedDeps<MockStorage, MockApi, MockQuerier>,
+) {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let info = mock_info("instantiator", &[]);
+    let init_msg = InstantiateMsg {
+        name: "sec721".to_string(),
+        symbol: "S721".to_string(),
+        admin: Some("admin".to_string()),
+        entropy: "We're going to need a bigger boat".to_string(),
+        royalty_info: None,
+        config: None,
+        post_init_callback: None,
+        template: None,
+    };
+
+    (instantiate(deps.as_mut(), env, info, init_msg), deps)
+}
+
+pub fn extract_error_msg<T: Any>(error: StdResult<T>) -> String {
+    match error {
+        Ok(_response) => panic!("Expected error, but had Ok response"),
+        Err(err) => match err {
+            StdError::GenericErr { msg, .. } => msg,
+            _ => panic!("Unexpected error result {:?}", err),
+        },
+    }
+}